@@ -0,0 +1,152 @@
+use bevy::render::renderer::{
+    RenderAdapter, RenderAdapterInfo, RenderDevice, RenderQueue, WgpuWrapper,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::Manager;
+
+use crate::bridge::CommandError;
+
+// The GPU bevy renders with in place of the one wgpu prefers, set with
+// TauriPlugin::with_adapter. On hybrid graphics laptops (Optimus, PRIME) that
+// can be a GPU the display isn't connected to, which then copies every frame
+// across to the other one.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AdapterSelection {
+    // position in the list list_adapters returns
+    Index(usize),
+    // part of the adapter's name, ignoring case, e.g. "nvidia"
+    Name(String),
+}
+
+impl AdapterSelection {
+    // A number is an index, anything else part of a name
+    pub fn parse(value: &str) -> Self {
+        match value.parse() {
+            Ok(index) => AdapterSelection::Index(index),
+            Err(_) => AdapterSelection::Name(value.to_lowercase()),
+        }
+    }
+
+    fn matches(&self, adapter: &AdapterInfo) -> bool {
+        match self {
+            AdapterSelection::Index(index) => adapter.index == *index,
+            AdapterSelection::Name(name) => adapter.name.to_lowercase().contains(name),
+        }
+    }
+}
+
+// An adapter of bevy's wgpu instance, stored by create_renderer
+#[derive(Serialize, Clone)]
+pub struct AdapterInfo {
+    pub index: usize,
+    pub name: String,
+    pub backend: String,
+    pub device_type: String,
+    pub driver: String,
+    // whether it can render to bevy's window surface
+    pub presents: bool,
+    // the one bevy renders with
+    pub selected: bool,
+}
+
+#[derive(Clone)]
+pub struct AdapterList(pub Vec<AdapterInfo>);
+
+impl AdapterList {
+    pub fn new(adapters: &[wgpu::Adapter], surface: &wgpu::Surface) -> Self {
+        Self(
+            adapters
+                .iter()
+                .enumerate()
+                .map(|(index, adapter)| {
+                    let info = adapter.get_info();
+                    AdapterInfo {
+                        index,
+                        name: info.name,
+                        backend: format!("{:?}", info.backend),
+                        device_type: format!("{:?}", info.device_type),
+                        driver: format!("{} {}", info.driver, info.driver_info),
+                        presents: adapter.is_surface_supported(surface),
+                        selected: false,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    // The first adapter `selection` matches that can render to the surface
+    pub fn find(&self, selection: &AdapterSelection) -> Result<usize, String> {
+        let matching: Vec<_> = self
+            .0
+            .iter()
+            .filter(|adapter| selection.matches(adapter))
+            .collect();
+        if let Some(adapter) = matching.iter().find(|adapter| adapter.presents) {
+            return Ok(adapter.index);
+        }
+        if let Some(adapter) = matching.first() {
+            return Err(format!(
+                "adapter {} ({}) can't render to the window surface",
+                adapter.index, adapter.name
+            ));
+        }
+        let names: Vec<_> = self
+            .0
+            .iter()
+            .map(|adapter| format!("{}: {} ({})", adapter.index, adapter.name, adapter.backend))
+            .collect();
+        Err(format!(
+            "no adapter matches {:?}, found {}",
+            selection,
+            names.join(", ")
+        ))
+    }
+}
+
+// bevy's initialize_renderer with the default WgpuSettings, for an adapter
+// picked here instead of by request_adapter
+pub async fn initialize_renderer(
+    adapter: wgpu::Adapter,
+) -> Result<(RenderDevice, RenderQueue, RenderAdapterInfo, RenderAdapter), String> {
+    let adapter_info = adapter.get_info();
+    let mut features = adapter.features();
+    // same as bevy: slow across PCI-E, and ray tracing can lose the device
+    if adapter_info.device_type == wgpu::DeviceType::DiscreteGpu {
+        features -= wgpu::Features::MAPPABLE_PRIMARY_BUFFERS;
+    }
+    features -= wgpu::Features::RAY_QUERY;
+    features -= wgpu::Features::RAY_TRACING_ACCELERATION_STRUCTURE;
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                required_features: features,
+                required_limits: adapter.limits(),
+                memory_hints: wgpu::MemoryHints::default(),
+            },
+            None,
+        )
+        .await
+        .map_err(|err| {
+            format!(
+                "failed to create a device on {}: {}",
+                adapter_info.name, err
+            )
+        })?;
+    Ok((
+        RenderDevice::from(device),
+        RenderQueue(Arc::new(WgpuWrapper::new(queue))),
+        RenderAdapterInfo(WgpuWrapper::new(adapter_info)),
+        RenderAdapter(Arc::new(WgpuWrapper::new(adapter))),
+    ))
+}
+
+// The adapters bevy's renderer could have used, for picking one to pass to
+// TauriPlugin::with_adapter (--adapter in the demo)
+#[tauri::command]
+pub fn list_adapters(app: tauri::AppHandle) -> Result<Vec<AdapterInfo>, CommandError> {
+    app.try_state::<AdapterList>()
+        .map(|adapters| adapters.0.clone())
+        .ok_or_else(|| CommandError::NotReady(String::from("renderer is not initialized yet")))
+}
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow};
+use std::time::{Duration, Instant};
+
+// Renders uncapped for latency while that's cheap, see TauriPlugin::with_adaptive_vsync.
+// Falls back to Fifo while bevy's window is unfocused, and for `retry_after`
+// once the frame rate went above `ceiling_fps`. Fifo caps it at the refresh
+// rate, so a retry that's still above the ceiling only shows up for a second.
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptiveVsync {
+    // false leaves the present mode alone, e.g. when a settings file turns it off
+    pub enabled: bool,
+    pub ceiling_fps: u32,
+    // the present mode while focused and under the ceiling
+    pub uncapped: PresentMode,
+    pub retry_after: Duration,
+}
+
+impl Default for AdaptiveVsync {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ceiling_fps: 240,
+            // Immediate or Mailbox, whichever the surface has, else Fifo
+            uncapped: PresentMode::AutoNoVsync,
+            retry_after: Duration::from_secs(10),
+        }
+    }
+}
+
+// The runner's side of AdaptiveVsync
+pub struct AdaptiveVsyncState {
+    config: AdaptiveVsync,
+    // Fifo until then after the frame rate went above the ceiling
+    capped_until: Option<Instant>,
+}
+
+impl AdaptiveVsyncState {
+    pub fn new(config: AdaptiveVsync) -> Self {
+        Self {
+            config,
+            capped_until: None,
+        }
+    }
+
+    // Called every runner iteration, with the frame rate of the last second
+    // once it's measured. Changing Window::present_mode makes bevy reconfigure
+    // the surface before the next frame.
+    pub fn update(&mut self, world: &mut World, fps: Option<u32>) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut windows = world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+        let Ok(mut window) = windows.get_single_mut(world) else {
+            return;
+        };
+        let now = Instant::now();
+        if let Some(fps) = fps {
+            if window.present_mode == self.config.uncapped && fps > self.config.ceiling_fps {
+                bevy::log::info!(
+                    "{} fps is above the {} fps ceiling, switching to Fifo",
+                    fps,
+                    self.config.ceiling_fps
+                );
+                self.capped_until = Some(now + self.config.retry_after);
+            }
+        }
+        if self.capped_until.is_some_and(|until| now >= until) {
+            self.capped_until = None;
+        }
+        let mode = if !window.focused || self.capped_until.is_some() {
+            PresentMode::Fifo
+        } else {
+            self.config.uncapped
+        };
+        if window.present_mode != mode {
+            window.present_mode = mode;
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::animation::graph::AnimationNodeIndex;
+use bevy::animation::{ActiveAnimation, AnimationTarget, RepeatAnimation};
+use bevy::prelude::*;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::scene;
+
+// Timeline controls for the first AnimationPlayer in the world, which is the
+// planet of the demo scene. They apply to every animation it is playing.
+
+// Names of the animations in the player's graph, in the order they were added.
+// Inserted by the scene setup, scene manifests don't have any.
+#[derive(Resource, Default)]
+pub struct AnimationNames(pub Vec<(String, AnimationNodeIndex)>);
+
+impl AnimationNames {
+    pub fn add(&mut self, name: &str, index: AnimationNodeIndex) {
+        self.0.push((name.to_string(), index));
+    }
+}
+
+fn player(world: &mut World) -> Result<Entity, CommandError> {
+    world
+        .query_filtered::<Entity, (With<AnimationPlayer>, With<AnimationGraphHandle>)>()
+        .iter(world)
+        .next()
+        .ok_or_else(|| CommandError::EntityNotFound(String::from("no animation player")))
+}
+
+// Duration of the clip played by node `index`, unbounded for blend nodes or
+// while the clip is loading
+fn clip_duration(world: &World, entity: Entity, index: AnimationNodeIndex) -> f32 {
+    let clip = world
+        .get::<AnimationGraphHandle>(entity)
+        .and_then(|handle| world.resource::<Assets<AnimationGraph>>().get(handle))
+        .and_then(|graph| match &graph.get(index)?.node_type {
+            AnimationNodeType::Clip(clip) => Some(clip.clone()),
+            _ => None,
+        });
+    clip.and_then(|clip| world.resource::<Assets<AnimationClip>>().get(&clip))
+        .map_or(f32::INFINITY, AnimationClip::duration)
+}
+
+fn with_animations<R>(
+    world: &mut World,
+    mut f: impl FnMut(&mut ActiveAnimation, f32) -> R,
+) -> Result<Vec<R>, CommandError> {
+    let entity = player(world)?;
+    let playing: Vec<AnimationNodeIndex> = world
+        .get::<AnimationPlayer>(entity)
+        .map(|player| {
+            player
+                .playing_animations()
+                .map(|(index, _)| *index)
+                .collect()
+        })
+        .unwrap_or_default();
+    if playing.is_empty() {
+        return Err(CommandError::Failed(String::from(
+            "the animation player isn't playing anything",
+        )));
+    }
+    let durations: Vec<f32> = playing
+        .iter()
+        .map(|&index| clip_duration(world, entity, index))
+        .collect();
+    let mut player = world.get_mut::<AnimationPlayer>(entity).unwrap();
+    Ok(playing
+        .iter()
+        .zip(durations)
+        .filter_map(|(&index, duration)| {
+            player
+                .animation_mut(index)
+                .map(|animation| f(animation, duration))
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn set_animation_repeat(
+    bridge: State<'_, BevyBridge>,
+    repeat: bool,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            with_animations(world, |animation, _| {
+                if repeat {
+                    animation.repeat();
+                } else {
+                    animation.set_repeat(RepeatAnimation::Never);
+                }
+            })
+            .map(drop)
+        })
+        .await?
+}
+
+// Jumps to `seconds` into the clip, clamped to its duration. A finished
+// animation starts playing again from there.
+#[tauri::command]
+pub async fn seek_animation(
+    bridge: State<'_, BevyBridge>,
+    seconds: f32,
+) -> Result<(), CommandError> {
+    if !seconds.is_finite() {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid time {}",
+            seconds
+        )));
+    }
+    bridge
+        .run(move |world| {
+            with_animations(world, |animation, duration| {
+                if animation.is_finished() {
+                    animation.replay();
+                }
+                animation.seek_to(seconds.clamp(0.0, duration));
+            })
+            .map(drop)
+        })
+        .await?
+}
+
+// Seconds into the clip of the first playing animation
+#[tauri::command]
+pub async fn get_animation_time(bridge: State<'_, BevyBridge>) -> Result<f32, CommandError> {
+    bridge
+        .run(|world| {
+            with_animations(world, |animation, _| animation.seek_time())
+                .map(|times| times.first().copied().unwrap_or_default())
+        })
+        .await?
+}
+
+// Empty when the scene has no named animations
+#[tauri::command]
+pub async fn list_animations(bridge: State<'_, BevyBridge>) -> Result<Vec<String>, CommandError> {
+    bridge
+        .run(|world| {
+            world.get_resource::<AnimationNames>().map_or_else(Vec::new, |names| {
+                names.0.iter().map(|(name, _)| name.clone()).collect()
+            })
+        })
+        .await
+}
+
+// Takes the place of the AnimationTarget of an entity taken out of its
+// player's animations by freeze_entity, to put back by unfreeze_entity
+#[derive(Component)]
+struct Frozen(AnimationTarget);
+
+fn animated_entity(world: &mut World, target: &str) -> Result<Entity, CommandError> {
+    scene::resolve_target(world, target)
+        .ok_or_else(|| CommandError::EntityNotFound(format!("no entity {}", target)))
+}
+
+// Stops animating `target`, an entity id or Name, so it keeps its pose and
+// set_transform can move it. Every curve of every animation targeting it stops
+// at once, the rest of the player's targets, including the entity's children,
+// keep playing. Returns the entity's id for set_transform.
+#[tauri::command]
+pub async fn freeze_entity(
+    bridge: State<'_, BevyBridge>,
+    target: String,
+) -> Result<u64, CommandError> {
+    bridge
+        .run(move |world| {
+            let entity = animated_entity(world, &target)?;
+            let mut entity = world.entity_mut(entity);
+            if !entity.contains::<Frozen>() {
+                let animation_target = entity.take::<AnimationTarget>().ok_or_else(|| {
+                    CommandError::InvalidArgument(format!("{} isn't animated", target))
+                })?;
+                entity.insert(Frozen(animation_target));
+            }
+            Ok(entity.id().to_bits())
+        })
+        .await?
+}
+
+// Hands `target` back to its player, which moves it to where the animation is
+// now on its next update, dropping the pose set while it was frozen
+#[tauri::command]
+pub async fn unfreeze_entity(
+    bridge: State<'_, BevyBridge>,
+    target: String,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let entity = animated_entity(world, &target)?;
+            let mut entity = world.entity_mut(entity);
+            let frozen = entity
+                .take::<Frozen>()
+                .ok_or_else(|| CommandError::InvalidArgument(format!("{} isn't frozen", target)))?;
+            entity.insert(frozen.0);
+            Ok(())
+        })
+        .await?
+}
@@ -0,0 +1,307 @@
+use bevy::asset::{LoadState, RecursiveDependencyLoadState, UntypedAssetId};
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_real_timer;
+use bevy::render::mesh::Indices;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::State;
+use tokio::sync::oneshot;
+
+use crate::bridge::{BevyBridge, CommandError};
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum AssetStatus {
+    // the asset and everything it depends on
+    Loaded,
+    // missing file, unknown extension or a loader error
+    Failed { error: String },
+    // still loading when the timeout ran out
+    TimedOut,
+}
+
+struct AssetWaiter {
+    id: UntypedAssetId,
+    reply: oneshot::Sender<AssetStatus>,
+}
+
+// Pending `load_asset` calls, resolved by poll_asset_loads
+#[derive(Resource, Default)]
+struct AssetWaiters(Vec<AssetWaiter>);
+
+pub struct CachedAsset {
+    pub handle: UntypedHandle,
+    // real time of the last load_asset for it, or the last check that found
+    // another handle to it
+    last_used: Duration,
+}
+
+// Handles of every asset loaded through `load_asset` by path, so they stay
+// loaded after the call resolved. Bevy unloads an asset once its last strong
+// handle is dropped, so this cache is what keeps idle assets in memory.
+#[derive(Resource, Default)]
+pub struct LoadedAssets(pub HashMap<String, CachedAsset>);
+
+const ANDROID_CACHE_LIMIT: u64 = 128 * 1024 * 1024;
+
+// Estimated size above which idle assets are evicted from LoadedAssets, least
+// recently used first. None keeps them all, the default except on Android,
+// which kills apps using a lot of memory in the background.
+#[derive(Resource, Clone, Copy)]
+pub struct AssetCacheLimit(pub Option<u64>);
+
+impl Default for AssetCacheLimit {
+    fn default() -> Self {
+        Self(cfg!(target_os = "android").then_some(ANDROID_CACHE_LIMIT))
+    }
+}
+
+const CACHE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Clone, Copy, Default, Debug)]
+pub struct AssetCacheUsage {
+    // assets loaded with load_asset that are still cached
+    pub assets: usize,
+    // the ones nothing but the cache holds, which can be evicted
+    pub idle: usize,
+    // CPU side image data and mesh buffers of the cached assets, assets they
+    // depend on (like a glTF's meshes) and GPU copies aren't counted
+    pub bytes: u64,
+    pub limit: Option<u64>,
+}
+
+pub struct AssetLoadPlugin;
+
+impl Plugin for AssetLoadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetWaiters>()
+            .init_resource::<LoadedAssets>()
+            .init_resource::<AssetCacheLimit>()
+            .add_systems(Update, poll_asset_loads)
+            .add_systems(
+                Last,
+                evict_idle_assets.run_if(on_real_timer(CACHE_CHECK_INTERVAL)),
+            );
+    }
+}
+
+fn is_idle(handle: &UntypedHandle) -> bool {
+    match handle {
+        UntypedHandle::Strong(handle) => Arc::strong_count(handle) == 1,
+        UntypedHandle::Weak(_) => true,
+    }
+}
+
+fn asset_bytes(world: &World, id: UntypedAssetId) -> u64 {
+    if let Ok(id) = id.try_typed::<Image>() {
+        return world
+            .resource::<Assets<Image>>()
+            .get(id)
+            .map_or(0, |image| image.data.len() as u64);
+    }
+    if let Ok(id) = id.try_typed::<Mesh>() {
+        let Some(mesh) = world.resource::<Assets<Mesh>>().get(id) else {
+            return 0;
+        };
+        let indices = match mesh.indices() {
+            Some(Indices::U16(indices)) => indices.len() * 2,
+            Some(Indices::U32(indices)) => indices.len() * 4,
+            None => 0,
+        };
+        return (mesh.count_vertices() * mesh.get_vertex_size() as usize + indices) as u64;
+    }
+    0
+}
+
+fn cache_usage(world: &World) -> AssetCacheUsage {
+    let loaded = world.resource::<LoadedAssets>();
+    AssetCacheUsage {
+        assets: loaded.0.len(),
+        idle: loaded.0.values().filter(|asset| is_idle(&asset.handle)).count(),
+        bytes: loaded
+            .0
+            .values()
+            .map(|asset| asset_bytes(world, asset.handle.id()))
+            .sum(),
+        limit: world.resource::<AssetCacheLimit>().0,
+    }
+}
+
+// Drops idle assets until the cache fits its limit
+fn evict_idle_assets(world: &mut World) {
+    let Some(limit) = world.resource::<AssetCacheLimit>().0 else {
+        return;
+    };
+    let now = world.resource::<Time<Real>>().elapsed();
+    let mut bytes = 0;
+    let mut idle = Vec::new();
+    world.resource_scope(|world, mut loaded: Mut<LoadedAssets>| {
+        for (path, asset) in loaded.0.iter_mut() {
+            let size = asset_bytes(world, asset.handle.id());
+            bytes += size;
+            if is_idle(&asset.handle) {
+                idle.push((asset.last_used, size, path.clone()));
+            } else {
+                asset.last_used = now;
+            }
+        }
+    });
+    if bytes <= limit {
+        return;
+    }
+
+    idle.sort_unstable();
+    let mut loaded = world.resource_mut::<LoadedAssets>();
+    let mut evicted = 0;
+    for (_, size, path) in idle {
+        if bytes <= limit {
+            break;
+        }
+        loaded.0.remove(&path);
+        bytes -= size;
+        evicted += 1;
+    }
+    info!(
+        "evicted {} idle assets, the cache holds about {} bytes of {}",
+        evicted, bytes, limit
+    );
+}
+
+fn load_status(asset_server: &AssetServer, id: UntypedAssetId) -> Option<AssetStatus> {
+    if let Some(LoadState::Failed(error)) = asset_server.get_load_state(id) {
+        return Some(AssetStatus::Failed {
+            error: error.to_string(),
+        });
+    }
+    match asset_server.get_recursive_dependency_load_state(id) {
+        Some(RecursiveDependencyLoadState::Loaded) => Some(AssetStatus::Loaded),
+        Some(RecursiveDependencyLoadState::Failed(error)) => Some(AssetStatus::Failed {
+            error: error.to_string(),
+        }),
+        // a handle that was dropped before it loaded
+        None => Some(AssetStatus::Failed {
+            error: String::from("asset was unloaded"),
+        }),
+        _ => None,
+    }
+}
+
+fn poll_asset_loads(mut waiters: ResMut<AssetWaiters>, asset_server: Res<AssetServer>) {
+    let mut pending = Vec::new();
+    for waiter in std::mem::take(&mut waiters.0) {
+        // the command already timed out
+        if waiter.reply.is_closed() {
+            continue;
+        }
+        match load_status(&asset_server, waiter.id) {
+            Some(status) => {
+                let _ = waiter.reply.send(status);
+            }
+            None => pending.push(waiter),
+        }
+    }
+    waiters.0 = pending;
+}
+
+pub const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+
+// Resolves once the asset `id` and its dependencies have loaded or failed, or
+// after `timeout`. The caller has to keep a strong handle to it meanwhile.
+pub async fn wait_for_asset(
+    bridge: &BevyBridge,
+    id: UntypedAssetId,
+    timeout: Duration,
+) -> Result<AssetStatus, CommandError> {
+    let (reply, rx) = oneshot::channel();
+    bridge.send(move |world| {
+        world
+            .resource_mut::<AssetWaiters>()
+            .0
+            .push(AssetWaiter { id, reply });
+    })?;
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(status) => {
+            status.map_err(|_| CommandError::NotReady(String::from("bevy app dropped the request")))
+        }
+        Err(_) => Ok(AssetStatus::TimedOut),
+    }
+}
+
+// Loads `path` as an `A` and returns its handle once it is ready to use
+pub async fn load<A: Asset>(
+    bridge: &BevyBridge,
+    path: String,
+    timeout: Duration,
+) -> Result<Handle<A>, CommandError> {
+    let load_path = path.clone();
+    let handle = bridge
+        .run(move |world| world.resource::<AssetServer>().load::<A>(load_path))
+        .await?;
+    match wait_for_asset(bridge, handle.id().untyped(), timeout).await? {
+        AssetStatus::Loaded => Ok(handle),
+        AssetStatus::Failed { error } => Err(CommandError::Failed(error)),
+        AssetStatus::TimedOut => Err(CommandError::Failed(format!(
+            "{} didn't load in time",
+            path
+        ))),
+    }
+}
+
+// Loads `path` from the assets folder and resolves once it and its dependencies
+// have loaded or failed, or after `timeout_ms` (default 30s). Loading an asset
+// that is already loaded resolves on the next update.
+#[tauri::command]
+pub async fn load_asset(
+    bridge: State<'_, BevyBridge>,
+    path: String,
+    timeout_ms: Option<u64>,
+) -> Result<AssetStatus, CommandError> {
+    if path.trim().is_empty() {
+        return Err(CommandError::InvalidArgument(String::from(
+            "asset path is empty",
+        )));
+    }
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+
+    let id = bridge
+        .run(move |world| {
+            let handle = world
+                .resource::<AssetServer>()
+                .load_untyped(&path)
+                .untyped();
+            let id = handle.id();
+            let last_used = world.resource::<Time<Real>>().elapsed();
+            world
+                .resource_mut::<LoadedAssets>()
+                .0
+                .insert(path, CachedAsset { handle, last_used });
+            id
+        })
+        .await?;
+    wait_for_asset(&bridge, id, timeout).await
+}
+
+// Evicts right away when the cache is already above the new limit, returns the
+// usage after that. None turns eviction off.
+#[tauri::command]
+pub async fn set_asset_cache_limit(
+    bridge: State<'_, BevyBridge>,
+    bytes: Option<u64>,
+) -> Result<AssetCacheUsage, CommandError> {
+    bridge
+        .run(move |world| {
+            world.resource_mut::<AssetCacheLimit>().0 = bytes;
+            evict_idle_assets(world);
+            cache_usage(world)
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn get_asset_cache_usage(
+    bridge: State<'_, BevyBridge>,
+) -> Result<AssetCacheUsage, CommandError> {
+    bridge.run(|world| cache_usage(world)).await
+}
@@ -0,0 +1,68 @@
+use bevy::audio::{AudioSinkPlayback, GlobalVolume, Volume};
+use bevy::prelude::*;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+
+// The level set with set_master_volume, kept while muted so unmuting restores it
+#[derive(Resource, Clone, Copy)]
+struct MasterVolume {
+    volume: f32,
+    muted: bool,
+}
+
+// Updates the volume `set` on MasterVolume, then GlobalVolume and the sounds
+// already playing, which bevy only applies GlobalVolume to when they start
+async fn update_volume(
+    bridge: &BevyBridge,
+    set: impl FnOnce(&mut MasterVolume) + Send + 'static,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let Some(global) = world.get_resource::<GlobalVolume>() else {
+                return Err(CommandError::Failed(String::from(
+                    "audio is disabled (--no-audio)",
+                )));
+            };
+            let initial = MasterVolume {
+                volume: global.volume.get(),
+                muted: false,
+            };
+            let mut master = world.get_resource_or_insert_with(|| initial);
+            set(&mut master);
+            let level = if master.muted { 0.0 } else { master.volume };
+            world.resource_mut::<GlobalVolume>().volume = Volume::new(level);
+
+            let mut sinks = world.query::<(&AudioSink, &PlaybackSettings)>();
+            for (sink, settings) in sinks.iter(world) {
+                sink.set_volume(settings.volume.get() * level);
+            }
+            let mut spatial_sinks = world.query::<(&SpatialAudioSink, &PlaybackSettings)>();
+            for (sink, settings) in spatial_sinks.iter(world) {
+                sink.set_volume(settings.volume.get() * level);
+            }
+            Ok(())
+        })
+        .await?
+}
+
+// Volume of all audio from 0 (silent) to 1 (as authored). Playing sounds
+// change right away, losing volumes set on their AudioSink since they started.
+#[tauri::command]
+pub async fn set_master_volume(
+    bridge: State<'_, BevyBridge>,
+    volume: f32,
+) -> Result<(), CommandError> {
+    if volume.is_nan() {
+        return Err(CommandError::InvalidArgument(String::from(
+            "invalid volume NaN",
+        )));
+    }
+    let volume = volume.clamp(0.0, 1.0);
+    update_volume(&bridge, move |master| master.volume = volume).await
+}
+
+#[tauri::command]
+pub async fn set_muted(bridge: State<'_, BevyBridge>, muted: bool) -> Result<(), CommandError> {
+    update_volume(&bridge, move |master| master.muted = muted).await
+}
@@ -0,0 +1,207 @@
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use serde::Deserialize;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::camera;
+
+// Background of one camera, unlike SceneSettings' clear color which applies to
+// every camera that doesn't set its own. Colors are sRGB components in 0..=1.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CameraClear {
+    // SceneSettings' clear color, what cameras start with
+    Default,
+    Solid { color: [f32; 3] },
+    Gradient { top: [f32; 3], bottom: [f32; 3] },
+    // keeps what cameras rendered before, for overlays
+    None,
+}
+
+// Vertical gradient drawn behind everything the camera sees, by an unlit quad
+// just inside its far plane. Other cameras can see the quad when they look
+// at it from behind this camera. Being opaque, it also hides the window behind
+// a transparent surface (set_webview_transparent).
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct GradientBackground {
+    pub top: Color,
+    pub bottom: Color,
+}
+
+// The quad drawing the camera's GradientBackground, a child of the camera
+#[derive(Component)]
+struct BackgroundQuad(Entity);
+
+// How far into the view volume the quad sits, as a fraction of the far plane
+const QUAD_DEPTH: f32 = 0.95;
+
+pub struct BackgroundPlugin;
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (spawn_background_quads, remove_background_quads, fit_background_quads)
+                .chain()
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+fn gradient_mesh(background: &GradientBackground) -> Mesh {
+    let top = background.top.to_linear().to_f32_array();
+    let bottom = background.bottom.to_linear().to_f32_array();
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::RENDER_WORLD)
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [-0.5, -0.5, 0.0],
+                [0.5, -0.5, 0.0],
+                [0.5, 0.5, 0.0],
+                [-0.5, 0.5, 0.0],
+            ],
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; 4])
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, vec![bottom, bottom, top, top])
+        .with_inserted_indices(Indices::U16(vec![0, 1, 2, 0, 2, 3]))
+}
+
+fn spawn_background_quads(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    cameras: Query<
+        (Entity, &GradientBackground, Option<&BackgroundQuad>),
+        Changed<GradientBackground>,
+    >,
+) {
+    for (camera, background, quad) in cameras.iter() {
+        let mesh = Mesh3d(meshes.add(gradient_mesh(background)));
+        if let Some(quad) = quad {
+            commands.entity(quad.0).insert(mesh);
+            continue;
+        }
+        let material = materials.add(StandardMaterial {
+            unlit: true,
+            fog_enabled: false,
+            ..default()
+        });
+        let quad = commands
+            .spawn((
+                mesh,
+                MeshMaterial3d(material),
+                Transform::default(),
+                NotShadowCaster,
+                NotShadowReceiver,
+                // hovering the sky shouldn't report an entity
+                PickingBehavior::IGNORE,
+                Name::new("background"),
+            ))
+            .set_parent(camera)
+            .id();
+        commands.entity(camera).insert(BackgroundQuad(quad));
+    }
+}
+
+fn remove_background_quads(
+    mut commands: Commands,
+    mut removed: RemovedComponents<GradientBackground>,
+    quads: Query<&BackgroundQuad, Without<GradientBackground>>,
+) {
+    for camera in removed.read() {
+        if let Ok(quad) = quads.get(camera) {
+            commands.entity(quad.0).despawn_recursive();
+            commands.entity(camera).remove::<BackgroundQuad>();
+        }
+    }
+}
+
+// Scales each quad to cover the whole view at its depth, following fov,
+// aspect ratio and projection changes
+fn fit_background_quads(
+    cameras: Query<(&Projection, &BackgroundQuad)>,
+    mut transforms: Query<&mut Transform>,
+) {
+    for (projection, quad) in cameras.iter() {
+        let Ok(mut transform) = transforms.get_mut(quad.0) else {
+            continue;
+        };
+        let (depth, size) = match projection {
+            Projection::Perspective(perspective) => {
+                let depth = perspective.far * QUAD_DEPTH;
+                let height = 2.0 * depth * (perspective.fov / 2.0).tan();
+                (depth, Vec2::new(height * perspective.aspect_ratio, height))
+            }
+            Projection::Orthographic(orthographic) => (
+                orthographic.near + (orthographic.far - orthographic.near) * QUAD_DEPTH,
+                orthographic.area.size(),
+            ),
+        };
+        *transform = Transform::from_xyz(0.0, 0.0, -depth).with_scale(size.extend(1.0));
+    }
+}
+
+// Shows or hides the quad drawing `camera`'s GradientBackground, if it has one,
+// e.g. while measure_latency flashes the clear color behind it
+pub fn set_background_visibility(world: &mut World, camera: Entity, visibility: Visibility) {
+    let Some(quad) = world.get::<BackgroundQuad>(camera).map(|quad| quad.0) else {
+        return;
+    };
+    if let Some(mut quad_visibility) = world.get_mut::<Visibility>(quad) {
+        *quad_visibility = visibility;
+    }
+}
+
+fn srgb([r, g, b]: [f32; 3]) -> Color {
+    Color::srgb(r, g, b)
+}
+
+// Sets the background of the camera `camera_id`, a window's camera returned
+// by open_window, or of the main camera without one
+#[tauri::command]
+pub async fn set_camera_clear(
+    bridge: State<'_, BevyBridge>,
+    mode: CameraClear,
+    camera_id: Option<u64>,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let entity = match camera_id {
+                Some(id) => Entity::try_from_bits(id)
+                    .ok()
+                    .filter(|&entity| {
+                        world
+                            .get_entity(entity)
+                            .is_ok_and(|entity| entity.contains::<Camera>())
+                    })
+                    .ok_or_else(|| {
+                        CommandError::EntityNotFound(format!("no camera with id {}", id))
+                    })?,
+                None => camera::main_camera(world)?,
+            };
+            let mut entity = world.entity_mut(entity);
+            let clear_color = match mode {
+                CameraClear::Solid { color } => ClearColorConfig::Custom(srgb(color)),
+                // the quad covers it
+                CameraClear::Default | CameraClear::Gradient { .. } => ClearColorConfig::Default,
+                CameraClear::None => ClearColorConfig::None,
+            };
+            entity.get_mut::<Camera>().unwrap().clear_color = clear_color;
+            match mode {
+                CameraClear::Gradient { top, bottom } => {
+                    entity.insert(GradientBackground {
+                        top: srgb(top),
+                        bottom: srgb(bottom),
+                    });
+                }
+                _ => {
+                    entity.remove::<GradientBackground>();
+                }
+            }
+            Ok(())
+        })
+        .await?
+}
@@ -0,0 +1,395 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::camera::{self, CameraTween};
+use crate::picking;
+use crate::scene::{self, LightKind};
+
+// One scene edit of a `commit_batch`, tagged by "op" with the arguments of the
+// command of the same name, e.g. { "op": "remove_light", "id": 42 }
+#[derive(Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    AddLight {
+        kind: LightKind,
+        x: f32,
+        y: f32,
+        z: f32,
+        intensity: f32,
+        color: [f32; 3],
+    },
+    RemoveLight {
+        id: u64,
+    },
+    SetPointLight {
+        target: String,
+        intensity: f32,
+        range: f32,
+    },
+    SetEntityColor {
+        entity_id: u64,
+        r: f32,
+        g: f32,
+        b: f32,
+    },
+    SetTransform {
+        entity_id: u64,
+        pos: [f32; 3],
+        rot: [f32; 3],
+        scale: [f32; 3],
+    },
+    SetCameraTransform {
+        pos: [f32; 3],
+        look_at: [f32; 3],
+    },
+}
+
+// What an applied op changed, put back when a later op of the batch fails
+enum Undo {
+    Despawn(Entity),
+    PointLight(Entity, PointLight),
+    Material(Entity, Handle<StandardMaterial>),
+    Transform(Entity, Transform),
+    CameraTween(CameraTween),
+}
+
+impl Undo {
+    fn restore(self, world: &mut World) {
+        match self {
+            Undo::Despawn(entity) => {
+                if let Ok(entity) = world.get_entity_mut(entity) {
+                    entity.despawn_recursive();
+                }
+            }
+            Undo::PointLight(entity, light) => {
+                if let Some(mut current) = world.get_mut::<PointLight>(entity) {
+                    *current = light;
+                }
+            }
+            Undo::Material(entity, material) => picking::set_base_material(world, entity, material),
+            Undo::Transform(entity, transform) => {
+                if let Some(mut current) = world.get_mut::<Transform>(entity) {
+                    *current = transform;
+                }
+            }
+            Undo::CameraTween(tween) => world.insert_resource(tween),
+        }
+    }
+}
+
+// Lights removed earlier in the batch are only despawned at the end, later ops
+// treat them as gone already
+fn live_entity(world: &World, entity_id: u64, removed: &[Entity]) -> Result<Entity, CommandError> {
+    scene::entity_by_id(world, entity_id)
+        .ok()
+        .filter(|entity| !removed.contains(entity))
+        .ok_or_else(|| CommandError::EntityNotFound(format!("no entity with id {}", entity_id)))
+}
+
+impl Op {
+    // Returns the id of the spawned entity for ops that spawn one. Records how
+    // to take the op back in `undo`, and the lights to despawn in `removed`.
+    fn apply(
+        self,
+        world: &mut World,
+        removed: &mut Vec<Entity>,
+        undo: &mut Vec<Undo>,
+    ) -> Result<Option<u64>, CommandError> {
+        match self {
+            Op::AddLight {
+                kind,
+                x,
+                y,
+                z,
+                intensity,
+                color,
+            } => {
+                let position = Vec3::new(x, y, z);
+                let id = scene::spawn_light(world, kind, position, intensity, color);
+                undo.push(Undo::Despawn(Entity::from_bits(id)));
+                Ok(Some(id))
+            }
+            Op::RemoveLight { id } => {
+                let entity = scene::light_entity(world, id)
+                    .ok()
+                    .filter(|entity| !removed.contains(entity))
+                    .ok_or_else(|| {
+                        CommandError::EntityNotFound(format!("no light with id {}", id))
+                    })?;
+                removed.push(entity);
+                Ok(None)
+            }
+            Op::SetPointLight {
+                target,
+                intensity,
+                range,
+            } => {
+                let entity = scene::resolve_target(world, &target)
+                    .filter(|entity| !removed.contains(entity))
+                    .ok_or_else(|| CommandError::EntityNotFound(format!("no entity {}", target)))?;
+                let light = world.get::<PointLight>(entity).cloned();
+                scene::configure_point_light(world, &target, intensity, range)?;
+                undo.extend(light.map(|light| Undo::PointLight(entity, light)));
+                Ok(None)
+            }
+            Op::SetEntityColor { entity_id, r, g, b } => {
+                let entity = live_entity(world, entity_id, removed)?;
+                let material = picking::base_material(world, entity);
+                scene::recolor_entity(world, entity_id, [r, g, b])?;
+                undo.extend(material.map(|material| Undo::Material(entity, material)));
+                Ok(None)
+            }
+            Op::SetTransform {
+                entity_id,
+                pos,
+                rot,
+                scale,
+            } => {
+                let entity = live_entity(world, entity_id, removed)?;
+                let transform = world.get::<Transform>(entity).copied();
+                scene::write_transform(world, entity_id, pos, rot, scale)?;
+                undo.extend(transform.map(|transform| Undo::Transform(entity, transform)));
+                Ok(None)
+            }
+            Op::SetCameraTransform { pos, look_at } => {
+                let camera = camera::main_camera(world)?;
+                let transform = world.get::<Transform>(camera).copied();
+                // place_camera stops a running animate_camera_to
+                let tween = std::mem::take(&mut *world.resource_mut::<CameraTween>());
+                if let Err(err) = camera::place_camera(world, pos, look_at) {
+                    world.insert_resource(tween);
+                    return Err(err);
+                }
+                undo.push(Undo::CameraTween(tween));
+                undo.extend(transform.map(|transform| Undo::Transform(camera, transform)));
+                Ok(None)
+            }
+        }
+    }
+}
+
+// Applies `ops` in order, all or none of them: an op that fails takes back the
+// ones before it, and lights are only despawned once every op succeeded.
+// Returns the spawned entity id of each op, or None.
+pub fn apply_batch(world: &mut World, ops: Vec<Op>) -> Result<Vec<Option<u64>>, CommandError> {
+    let mut removed = Vec::new();
+    let mut undo = Vec::new();
+    let mut spawned = Vec::with_capacity(ops.len());
+    for (index, op) in ops.into_iter().enumerate() {
+        match op.apply(world, &mut removed, &mut undo) {
+            Ok(id) => spawned.push(id),
+            Err(err) => {
+                for undo in undo.into_iter().rev() {
+                    undo.restore(world);
+                }
+                return Err(err.prefixed(format_args!("op {}", index)));
+            }
+        }
+    }
+    for entity in removed {
+        world.entity_mut(entity).despawn_recursive();
+    }
+    Ok(spawned)
+}
+
+// Ops collected with `stage_ops` until their batch is committed, by the id
+// `begin_batch` returned
+#[derive(Resource, Default)]
+struct StagedBatches {
+    next_id: u64,
+    batches: HashMap<u64, Vec<Op>>,
+}
+
+fn staged_batches(world: &mut World) -> Mut<'_, StagedBatches> {
+    world.get_resource_or_insert_with(StagedBatches::default)
+}
+
+fn unknown_batch(batch: u64) -> CommandError {
+    CommandError::InvalidArgument(format!("no batch {}", batch))
+}
+
+// Starts a batch that ops can be staged in from several places before it is
+// committed with `commit_batch`. Returns its id.
+#[tauri::command]
+pub async fn begin_batch(bridge: State<'_, BevyBridge>) -> Result<u64, CommandError> {
+    bridge
+        .run(|world| {
+            let mut staged = staged_batches(world);
+            let id = staged.next_id;
+            staged.next_id += 1;
+            staged.batches.insert(id, Vec::new());
+            id
+        })
+        .await
+}
+
+// Adds `ops` to a batch from `begin_batch`, nothing is applied yet
+#[tauri::command]
+pub async fn stage_ops(
+    bridge: State<'_, BevyBridge>,
+    batch: u64,
+    ops: Vec<Op>,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            staged_batches(world)
+                .batches
+                .get_mut(&batch)
+                .ok_or_else(|| unknown_batch(batch))?
+                .extend(ops);
+            Ok(())
+        })
+        .await?
+}
+
+// Drops a batch from `begin_batch` without applying it
+#[tauri::command]
+pub async fn discard_batch(bridge: State<'_, BevyBridge>, batch: u64) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            staged_batches(world)
+                .batches
+                .remove(&batch)
+                .map(drop)
+                .ok_or_else(|| unknown_batch(batch))
+        })
+        .await?
+}
+
+// Applies the ops staged in `batch`, if given, followed by `ops` between two
+// bevy updates, so they all show up in the same frame. When one fails none of
+// them is applied, see apply_batch. The batch is gone either way.
+#[tauri::command]
+pub async fn commit_batch(
+    bridge: State<'_, BevyBridge>,
+    batch: Option<u64>,
+    ops: Vec<Op>,
+) -> Result<Vec<Option<u64>>, CommandError> {
+    bridge
+        .run(move |world| {
+            let ops = match batch {
+                Some(batch) => {
+                    let mut staged = staged_batches(world)
+                        .batches
+                        .remove(&batch)
+                        .ok_or_else(|| unknown_batch(batch))?;
+                    staged.extend(ops);
+                    staged
+                }
+                None => ops,
+            };
+            apply_batch(world, ops)
+        })
+        .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An entity with a Transform and a point light, with their ids
+    fn scene() -> (World, u64, u64) {
+        let mut world = World::new();
+        let entity = world.spawn(Transform::default()).id().to_bits();
+        let light = scene::spawn_light(
+            &mut world,
+            LightKind::Point,
+            Vec3::Y,
+            1000.0,
+            [1.0, 1.0, 1.0],
+        );
+        (world, entity, light)
+    }
+
+    fn ops(entity: u64, light: u64) -> Vec<Op> {
+        vec![
+            Op::SetTransform {
+                entity_id: entity,
+                pos: [1.0, 2.0, 3.0],
+                rot: [0.0; 3],
+                scale: [1.0; 3],
+            },
+            Op::AddLight {
+                kind: LightKind::Spot,
+                x: 0.0,
+                y: 4.0,
+                z: 0.0,
+                intensity: 500.0,
+                color: [1.0, 1.0, 1.0],
+            },
+            Op::SetPointLight {
+                target: light.to_string(),
+                intensity: 5.0,
+                range: 10.0,
+            },
+            Op::RemoveLight { id: light },
+        ]
+    }
+
+    fn translation(world: &World, id: u64) -> Vec3 {
+        world
+            .get::<Transform>(Entity::from_bits(id))
+            .unwrap()
+            .translation
+    }
+
+    fn light_count(world: &mut World) -> usize {
+        let mut lights = world.query_filtered::<(), Or<(With<PointLight>, With<SpotLight>)>>();
+        lights.iter(world).count()
+    }
+
+    #[test]
+    fn applies_every_op() {
+        let (mut world, entity, light) = scene();
+        let spawned = apply_batch(&mut world, ops(entity, light)).unwrap();
+        assert!(spawned[0].is_none() && spawned[2].is_none() && spawned[3].is_none());
+        let spot = Entity::from_bits(spawned[1].unwrap());
+        assert!(world.get::<SpotLight>(spot).is_some());
+        assert_eq!(translation(&world, entity), Vec3::new(1.0, 2.0, 3.0));
+        assert!(world.get_entity(Entity::from_bits(light)).is_err());
+        assert_eq!(light_count(&mut world), 1);
+    }
+
+    #[test]
+    fn an_invalid_op_leaves_the_world_unchanged() {
+        let (mut world, entity, light) = scene();
+        let mut ops = ops(entity, light);
+        // the light is gone by then
+        ops.push(Op::SetTransform {
+            entity_id: light,
+            pos: [0.0; 3],
+            rot: [0.0; 3],
+            scale: [1.0; 3],
+        });
+        let err = apply_batch(&mut world, ops).unwrap_err();
+        assert!(matches!(err, CommandError::EntityNotFound(_)), "{:?}", err);
+
+        assert_eq!(translation(&world, entity), Vec3::ZERO);
+        let point_light = world.get::<PointLight>(Entity::from_bits(light)).unwrap();
+        assert_eq!(point_light.intensity, 1000.0);
+        assert_eq!(translation(&world, light), Vec3::Y);
+        assert_eq!(light_count(&mut world), 1);
+    }
+
+    #[test]
+    fn an_invalid_first_op_applies_nothing() {
+        let (mut world, entity, light) = scene();
+        let mut ops = ops(entity, light);
+        ops.insert(
+            0,
+            Op::SetTransform {
+                entity_id: entity,
+                pos: [f32::NAN; 3],
+                rot: [0.0; 3],
+                scale: [1.0; 3],
+            },
+        );
+        let err = apply_batch(&mut world, ops).unwrap_err();
+        assert!(matches!(err, CommandError::InvalidArgument(_)), "{:?}", err);
+        assert_eq!(translation(&world, entity), Vec3::ZERO);
+        assert_eq!(light_count(&mut world), 1);
+    }
+}
@@ -1,29 +1,175 @@
 use bevy::animation::{animated_field, AnimationTarget, AnimationTargetId};
 use bevy::prelude::*;
+use bevy::render::settings::Backends;
 use std::f32::consts::PI;
-use std::sync::atomic::Ordering;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use crate::tauri_plugin::{TauriPlugin, AVERAGE_FRAME_RATE};
+use crate::adapters::{self, AdapterSelection};
+use crate::adaptive_vsync::AdaptiveVsync;
+use crate::animation::{self, AnimationNames};
+use crate::assets::{self, AssetLoadPlugin};
+use crate::audio;
+use crate::background::{self, BackgroundPlugin};
+use crate::batch;
+use crate::camera::{self, CameraFollowPlugin, CameraPose};
+use crate::child_window;
+use crate::clipboard::{ClipboardPaste, ClipboardPlugin, CopyToClipboard, RequestClipboardPaste};
+use crate::color_picker::{self, ColorPickerPlugin};
+use crate::debug_gizmos::{self, DebugGizmosPlugin};
+use crate::diagnostics;
+use crate::dynamic_resolution::DynamicResolution;
+use crate::environment::{self, EnvironmentPlugin, SceneSettings};
+use crate::frame_capture::{self, FrameCapturePlugin};
+use crate::frame_limiter;
+use crate::frame_recording::{self, FrameRecordingPlugin};
+use crate::frame_stats;
+use crate::frame_sync;
+use crate::game_state::{self, GameStatePlugin};
+use crate::gamepad::{self, GamepadEventsPlugin};
+use crate::gpu_memory;
+#[cfg(feature = "grayscale")]
+use crate::grayscale::{Grayscale, GrayscalePlugin};
+use crate::input_record::{self, InputRecordPlugin, InputRecording, InputReplay};
+use crate::interpolation::InterpolationPlugin;
+use crate::latency;
+use crate::log_filter;
+use crate::manifest::{self, SceneManifest};
+use crate::mouselook::{self, MouseLookPlugin};
+use crate::offscreen::{self, OffscreenStream};
+use crate::one_shot;
+use crate::picking::{HoverPlugin, SelectionPlugin};
+use crate::power::{self, PowerProfile};
+use crate::quality::{self, QualityPreset};
+use crate::render_region::{self, RenderRegionPlugin};
+use crate::render_resolution::RenderResolution;
+use crate::render_stats::{self, RenderStatsPlugin};
+use crate::safe_mode::RunSentinel;
+use crate::scene::{self, Persistent, SceneSetup};
+#[cfg(desktop)]
+use crate::menu::{self, MenuItemTriggered};
+#[cfg(desktop)]
+use crate::shortcuts::{self, ShortcutPlugin, ShortcutTriggered};
+use crate::sprites::{self, SpriteOverlayPlugin};
+use crate::tauri_plugin::TauriPlugin;
+use crate::ui;
+use crate::webview_region::WebviewRegion;
+use crate::wgpu;
+use crate::window::{self, SizeConstraints, WindowTitlePlugin};
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
 #[tauri::command]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust-Bevy App!", name)
 }
-#[tauri::command]
-pub fn get_average_frame_rate() -> usize {
-    AVERAGE_FRAME_RATE.load(Ordering::Relaxed)
+
+
+// Demo settings parsed from the command line in main.rs
+#[derive(Default)]
+pub struct DemoOptions {
+    // the raw wgpu demo runs in a second window on the same event loop
+    pub with_wgpu_window: bool,
+    // frames are streamed to a canvas in the webview instead of the window surface
+    pub offscreen: bool,
+    // restricts wgpu to the GL backend
+    pub force_gl: bool,
+    pub camera_pose: CameraPose,
+    // pins the render size
+    pub render_resolution: Option<RenderResolution>,
+    // adds a native File menu
+    pub with_menu: bool,
+    // keeps running after the main window closes
+    pub close_to_tray: bool,
+    // JSON file with the entities to spawn instead of the demo scene
+    pub scene_manifest: Option<PathBuf>,
+    // GL on a fallback adapter without pipelined rendering, see TauriPlugin::with_safe_mode.
+    // Also used when the last run crashed, see RunSentinel.
+    pub safe_mode: bool,
+    // skips bevy's audio, see TauriPlugin::without_audio
+    pub no_audio: bool,
+    // paces frames for input latency, see TauriPlugin::with_latency_pacing
+    pub low_latency: bool,
+    // uncapped presenting with a Fifo fallback, see TauriPlugin::with_adaptive_vsync
+    pub adaptive_vsync: bool,
+    // the GPU to render with, see TauriPlugin::with_adapter
+    pub force_adapter: Option<AdapterSelection>,
+    // where the webview sits, see TauriPlugin::with_webview_region
+    pub webview_region: Option<WebviewRegion>,
+    // input recording to play back from the first frame
+    pub replay: Option<PathBuf>,
+    // lowers the 3d render size while the frame rate is low, see DynamicResolution
+    pub dynamic_resolution: bool,
+    // removes the window decorations for the title bar App.tsx draws
+    pub custom_titlebar: bool,
+    // camera settings to start with, see TauriPlugin::with_quality
+    pub quality: Option<QualityPreset>,
+    // warns when the main thread stops updating, see TauriPlugin::with_watchdog
+    pub watchdog: bool,
+    // how small the window can get, see TauriPlugin::with_size_constraints
+    pub size_constraints: Option<SizeConstraints>,
+    // paces to the monitor instead of 60Hz, see TauriPlugin::with_refresh_rate_pacing
+    pub refresh_pacing: bool,
+    // caps the frame rate on battery, see TauriPlugin::with_power_profile
+    pub power_profile: bool,
+    // keeps the app open when the renderer fails, see TauriPlugin::with_renderer_error_screen
+    pub error_screen: bool,
 }
 
+// The windows in tauri.conf.json keep their decorations unless the title bar
+// is drawn in HTML, see window::start_window_drag. With a webview region the
+// plugin creates the window instead of tauri, see webview_region::create_window.
+fn demo_context(custom_titlebar: bool, webview_region: bool) -> tauri::Context {
+    let mut context = crate::generate_tauri_context();
+    for window in context.config_mut().app.windows.iter_mut() {
+        if custom_titlebar {
+            window.decorations = false;
+        }
+        if webview_region {
+            window.create = false;
+        }
+    }
+    context
+}
+
+// This function is called from the main thread to setup the Bevy app. Returns
+// how the app exited, an error when bevy sent one or the renderer failed to start.
+pub fn setup_bevy(options: DemoOptions) -> AppExit {
+    let DemoOptions {
+        with_wgpu_window,
+        offscreen,
+        force_gl,
+        camera_pose,
+        render_resolution,
+        with_menu,
+        close_to_tray,
+        scene_manifest,
+        safe_mode,
+        no_audio,
+        low_latency,
+        adaptive_vsync,
+        force_adapter,
+        webview_region,
+        replay,
+        dynamic_resolution,
+        custom_titlebar,
+        quality,
+        watchdog,
+        size_constraints,
+        refresh_pacing,
+        power_profile,
+        error_screen,
+    } = options;
 
-// This function is called from the main thread to setup the Bevy app
-pub fn setup_bevy() {
     // Configure Bevy to use the existing surface
     let mut app: App = App::new();
-    app.insert_resource(ClearColor(Color::srgb_u8(0, 0, 0)));
+    app.insert_resource(camera_pose);
+    app.insert_resource(SceneSettings {
+        ambient_brightness: 150.0,
+        ..default()
+    });
     app.add_plugins((
         bevy::app::PanicHandlerPlugin::default(),
-        bevy::log::LogPlugin::default(),
+        log_filter::log_plugin(),
         bevy::core::TaskPoolPlugin::default(),
         bevy::core::TypeRegistrationPlugin::default(),
         bevy::core::FrameCountPlugin::default(),
@@ -34,6 +180,15 @@ pub fn setup_bevy() {
         bevy::input::InputPlugin::default(),
         
     ));
+
+    // a run that didn't exit cleanly may have been a driver crash, so the next
+    // one starts in safe mode like with --safe-mode. Checked once logging is up.
+    let (sentinel, crashed) = RunSentinel::create();
+    if crashed && !safe_mode {
+        warn!("the last run didn't exit cleanly, starting in safe mode");
+    }
+    let safe_mode = safe_mode || crashed;
+
     app.add_plugins(WindowPlugin {
         primary_window: Some(Window::default()),
         ..default()
@@ -42,42 +197,366 @@ pub fn setup_bevy() {
         bevy::a11y::AccessibilityPlugin::default(),
         bevy::asset::AssetPlugin::default(),
         bevy::scene::ScenePlugin::default(),
+        // added early so plugins below can init their states
+        bevy::state::app::StatesPlugin,
     ));
 
     // create tauri app
-    app.add_plugins(TauriPlugin::new(|| {
+    let has_webview_region = webview_region.is_some();
+    let mut tauri_plugin = TauriPlugin::new("main", move || {
         tauri::Builder::default()
-            .invoke_handler(tauri::generate_handler![greet])
-            .invoke_handler(tauri::generate_handler![get_average_frame_rate])
-            .build(crate::generate_tauri_context())
+            .setup(move |app| {
+                if with_wgpu_window {
+                    wgpu::setup_wgpu_secondary_window(
+                        app,
+                        ::wgpu::InstanceDescriptor::default(),
+                        None,
+                        wgpu::TriangleOptions::default(),
+                    )?;
+                }
+                Ok(())
+            })
+            .plugin(tauri_plugin_clipboard_manager::init())
+            .invoke_handler(tauri::generate_handler![
+                greet,
+                assets::load_asset,
+                assets::set_asset_cache_limit,
+                assets::get_asset_cache_usage,
+                animation::set_animation_repeat,
+                animation::seek_animation,
+                animation::get_animation_time,
+                animation::list_animations,
+                animation::freeze_entity,
+                animation::unfreeze_entity,
+                frame_stats::get_average_frame_rate,
+                log_filter::set_log_level,
+                input_record::start_input_recording,
+                input_record::stop_input_recording,
+                batch::begin_batch,
+                batch::stage_ops,
+                batch::discard_batch,
+                batch::commit_batch,
+                scene::add_light,
+                scene::remove_light,
+                scene::set_point_light,
+                scene::set_entity_color,
+                scene::get_transform,
+                scene::set_transform,
+                scene::save_scene,
+                scene::load_scene,
+                scene::reset_scene,
+                ui::spawn_ui_text,
+                ui::remove_ui,
+                sprites::spawn_sprite,
+                sprites::remove_sprite,
+                camera::set_camera_fov,
+                camera::set_camera_projection,
+                camera::set_camera_transform,
+                camera::follow_entity,
+                camera::stop_follow,
+                camera::save_camera_bookmark,
+                camera::animate_camera_to,
+                camera::world_to_screen,
+                background::set_camera_clear,
+                one_shot::run_system,
+                audio::set_master_volume,
+                audio::set_muted,
+                mouselook::send_mouse_motion,
+                mouselook::set_pointer_locked,
+                quality::set_quality,
+                quality::set_bloom,
+                diagnostics::get_surface_info,
+                diagnostics::get_renderer_status,
+                adapters::list_adapters,
+                render_stats::get_render_stats,
+                gpu_memory::get_gpu_memory,
+                debug_gizmos::set_debug_gizmos,
+                environment::get_scene_settings,
+                environment::set_scene_settings,
+                environment::set_fog,
+                environment::set_environment_map,
+                frame_sync::wait_for_frame,
+                frame_limiter::set_cpu_throttle,
+                frame_limiter::set_render_fps_cap,
+                frame_limiter::get_render_fps_cap,
+                frame_limiter::set_render_enabled,
+                frame_limiter::get_render_enabled,
+                frame_limiter::set_simulation_enabled,
+                frame_limiter::get_simulation_enabled,
+                power::get_power_status,
+                power::set_power_mode,
+                latency::measure_latency,
+                gamepad::get_gamepads,
+                game_state::set_game_state,
+                color_picker::pick_color_at,
+                frame_capture::capture_frame,
+                frame_recording::start_recording,
+                frame_recording::stop_recording,
+                render_region::set_render_region,
+                render_region::clear_render_region,
+                offscreen::subscribe_offscreen_frames,
+                window::start_window_drag,
+                window::minimize_window,
+                window::toggle_maximize_window,
+                window::close_window,
+                window::set_window_opacity,
+                child_window::open_window,
+                window::list_monitors,
+                window::set_size_constraints,
+                window::recreate_surface,
+                wgpu::set_renderer,
+                wgpu::set_wgpu_clear,
+                window::set_webview_transparent,
+                window::set_window_title,
+                window::get_window_title,
+                #[cfg(desktop)]
+                shortcuts::register_shortcut,
+                #[cfg(desktop)]
+                shortcuts::unregister_shortcut,
+            ])
+            .build(demo_context(custom_titlebar, has_webview_region))
             .expect("error while building tauri application")
-    }));
+    })
+    .with_render_plugin(ColorPickerPlugin)
+    .with_render_plugin(FrameCapturePlugin)
+    // invoke("run_system", { name: "toggle_lights" })
+    .with_system("toggle_lights", toggle_lights);
+    #[cfg(feature = "grayscale")]
+    {
+        tauri_plugin = tauri_plugin.with_render_plugin(GrayscalePlugin);
+    }
+    // phones run on battery, give up some frame rate while the scene is cheap
+    #[cfg(mobile)]
+    {
+        tauri_plugin = tauri_plugin.with_cpu_throttle(frame_limiter::CpuThrottle::default());
+    }
+    if offscreen {
+        tauri_plugin = tauri_plugin.with_offscreen_stream(OffscreenStream { fps: 30 });
+    }
+    if let Some(resolution) = render_resolution {
+        tauri_plugin = tauri_plugin.with_render_resolution(resolution);
+    }
+    if dynamic_resolution {
+        tauri_plugin = tauri_plugin.with_dynamic_resolution(DynamicResolution::default());
+    }
+    if let Some(preset) = quality {
+        tauri_plugin = tauri_plugin.with_quality(preset);
+    }
+    if watchdog {
+        tauri_plugin = tauri_plugin.with_watchdog(Duration::from_secs(5));
+    }
+    if let Some(constraints) = size_constraints {
+        tauri_plugin = tauri_plugin.with_size_constraints(constraints);
+    }
+    if refresh_pacing {
+        tauri_plugin = tauri_plugin.with_refresh_rate_pacing();
+    }
+    if power_profile {
+        // 30fps on battery, the refresh rate on AC
+        tauri_plugin = tauri_plugin.with_power_profile(PowerProfile::default());
+    }
+    if error_screen {
+        // shows why in App.tsx instead of closing
+        tauri_plugin = tauri_plugin.with_renderer_error_screen();
+    }
+    #[cfg(desktop)]
+    if with_menu {
+        tauri_plugin = tauri_plugin
+            .with_menu(menu::example_menu)
+            .with_menu_event("quit", AppExit::Success);
+    }
+    #[cfg(desktop)]
+    if close_to_tray {
+        tauri_plugin = tauri_plugin.with_close_to_tray();
+    }
+    if force_gl {
+        tauri_plugin = tauri_plugin.with_backends(Backends::GL);
+    }
+    if safe_mode {
+        tauri_plugin = tauri_plugin.with_safe_mode();
+    }
+    if no_audio {
+        tauri_plugin = tauri_plugin.without_audio();
+    }
+    if low_latency {
+        tauri_plugin = tauri_plugin.with_latency_pacing();
+    }
+    if adaptive_vsync {
+        tauri_plugin = tauri_plugin.with_adaptive_vsync(AdaptiveVsync::default());
+    }
+    if let Some(selection) = force_adapter {
+        tauri_plugin = tauri_plugin.with_adapter(selection);
+    }
+    if let Some(region) = webview_region {
+        tauri_plugin = tauri_plugin.with_webview_region(region);
+    }
+    // does nothing until the triangle demo runs, with --both or set_renderer
+    tauri_plugin = tauri_plugin.with_event_handler(wgpu::wgpu_callback);
+    app.add_plugins(tauri_plugin);
+    app.add_plugins((
+        AssetLoadPlugin,
+        ClipboardPlugin,
+        EnvironmentPlugin,
+        GamepadEventsPlugin,
+        GameStatePlugin,
+        HoverPlugin::default(),
+        SelectionPlugin,
+        DebugGizmosPlugin,
+        RenderStatsPlugin,
+        InterpolationPlugin,
+        CameraFollowPlugin,
+        WindowTitlePlugin,
+        InputRecordPlugin,
+    ))
+    // bevy implements Plugins for tuples of up to 15
+    .add_plugins((
+        SpriteOverlayPlugin,
+        MouseLookPlugin,
+        BackgroundPlugin,
+        FrameRecordingPlugin,
+        RenderRegionPlugin,
+    ));
+    #[cfg(feature = "grayscale")]
+    app.add_systems(Update, gray_out_cameras);
+    #[cfg(desktop)]
+    app.add_plugins(ShortcutPlugin)
+        .add_systems(Update, (log_shortcuts, log_menu_items));
 
 
     // App setup
     app.add_systems(Startup, setup)
-        .insert_resource(AmbientLight {
-            color: Color::WHITE,
-            brightness: 150.0,
+        .add_systems(Update, edit_text_buffer);
+    if let Some(path) = replay {
+        match InputRecording::load(&path) {
+            Ok(recording) => {
+                app.insert_resource(InputReplay::new(recording));
+            }
+            Err(err) => error!("{}, not replaying input", err),
+        }
+    }
+
+    // a manifest that doesn't load leaves the demo scene in place
+    let manifest = scene_manifest.and_then(|path| match SceneManifest::load(&path) {
+        Ok(manifest) => Some(manifest),
+        Err(err) => {
+            error!("{}, showing the demo scene instead", err);
+            None
+        }
+    });
+    // registered so reset_scene can spawn the scene again
+    let scene_setup = match manifest {
+        Some(manifest) => {
+            info!("spawning {} entities from the scene manifest", manifest.entities.len());
+            app.insert_resource(manifest);
+            app.register_system(manifest::spawn_manifest)
+        }
+        None => app.register_system(setup_demo_scene),
+    };
+    app.insert_resource(SceneSetup::new(scene_setup))
+        .add_systems(Startup, scene::run_scene_setup);
+
+    let exit = app.run();
+    // also after an error exit, only a crash or a kill leaves the sentinel behind
+    sentinel.remove();
+    exit
+}
+
+// Marks the text node used as a minimal clipboard demo
+#[derive(Component)]
+struct TextBuffer;
+
+fn edit_text_buffer(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut pastes: EventReader<ClipboardPaste>,
+    mut paste_requests: EventWriter<RequestClipboardPaste>,
+    mut copy_requests: EventWriter<CopyToClipboard>,
+    mut buffer: Query<&mut Text, With<TextBuffer>>,
+) {
+    let Ok(mut text) = buffer.get_single_mut() else {
+        return;
+    };
+
+    let ctrl = keys.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+    ]);
+    if ctrl && keys.just_pressed(KeyCode::KeyV) {
+        paste_requests.send(RequestClipboardPaste);
+    }
+    if ctrl && keys.just_pressed(KeyCode::KeyC) {
+        copy_requests.send(CopyToClipboard(text.0.clone()));
+    }
+
+    for ClipboardPaste(pasted) in pastes.read() {
+        text.0.push_str(pasted);
+    }
+}
+
+#[cfg(feature = "grayscale")]
+fn gray_out_cameras(mut commands: Commands, cameras: Query<Entity, Added<Camera3d>>) {
+    for camera in cameras.iter() {
+        commands.entity(camera).insert(Grayscale::default());
+    }
+}
+
+// Editor hotkeys registered from the webview end up here
+#[cfg(desktop)]
+fn log_shortcuts(mut shortcuts: EventReader<ShortcutTriggered>) {
+    for shortcut in shortcuts.read() {
+        info!("shortcut triggered: {}", shortcut.name);
+    }
+}
+
+// File > New of the example menu, File > Quit is bound to AppExit
+#[cfg(desktop)]
+fn log_menu_items(mut items: EventReader<MenuItemTriggered>) {
+    for item in items.read() {
+        info!("menu item clicked: {}", item.id);
+    }
+}
+
+fn setup(mut commands: Commands, camera_pose: Res<CameraPose>) {
+    // Camera
+    commands.spawn((
+        Camera3d::default(),
+        camera_pose.transform(),
+        Name::new("camera"),
+        Persistent,
+    ));
+
+    // Text buffer for the clipboard demo: Ctrl+V appends the clipboard, Ctrl+C copies it
+    commands.spawn((
+        Text::new(""),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.0),
+            left: Val::Px(12.0),
             ..default()
-        });
+        },
+        TextBuffer,
+        Persistent,
+    ));
+}
 
-    let _ = app.run();
+// The demo's one-shot system, switches the point lights off and on
+fn toggle_lights(mut lights: Query<&mut Visibility, With<PointLight>>) {
+    for mut visibility in lights.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
 }
 
-fn setup(
+fn setup_demo_scene(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut animations: ResMut<Assets<AnimationClip>>,
     mut graphs: ResMut<Assets<AnimationGraph>>,
 ) {
-    // Camera
-    commands.spawn((
-        Camera3d::default(),
-        Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
-    ));
-
     // Light
     commands.spawn((
         PointLight {
@@ -85,6 +564,7 @@ fn setup(
             ..default()
         },
         Transform::from_xyz(0.0, 2.5, 0.0),
+        Name::new("light"),
     ));
 
     // Let's use the `Name` component to target entities. We can use anything we
@@ -180,6 +660,9 @@ fn setup(
 
     // Create the animation graph
     let (graph, animation_index) = AnimationGraph::from_clip(animations.add(animation));
+    let mut animation_names = AnimationNames::default();
+    animation_names.add("orbit", animation_index);
+    commands.insert_resource(animation_names);
 
     // Create the animation player, and set it to repeat
     let mut player = AnimationPlayer::default();
@@ -197,6 +680,7 @@ fn setup(
             player,
         ))
         .id();
+    commands.spawn(sprites::health_bar(planet_entity));
     commands
         .entity(planet_entity)
         .insert(AnimationTarget {
@@ -215,10 +699,15 @@ fn setup(
                 },
             ))
             .with_children(|p| {
-                // The satellite, placed at a distance of the planet
+                // The satellite, placed at a distance of the planet. It glows
+                // once bloom is enabled with set_bloom.
                 p.spawn((
                     Mesh3d(meshes.add(Cuboid::new(0.5, 0.5, 0.5))),
-                    MeshMaterial3d(materials.add(Color::srgb(0.3, 0.9, 0.3))),
+                    MeshMaterial3d(materials.add(StandardMaterial {
+                        base_color: Color::srgb(0.3, 0.9, 0.3),
+                        emissive: LinearRgba::rgb(0.6, 3.0, 0.6),
+                        ..default()
+                    })),
                     Transform::from_xyz(1.5, 0.0, 0.0),
                     AnimationTarget {
                         id: satellite_animation_target_id,
@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use serde::Serialize;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use tokio::sync::oneshot;
+
+// What commands reject with, so the webview can tell the failures apart
+// without matching on messages. Serialized as { kind, message }, e.g.
+// { kind: "entity-not-found", message: "no entity with id 42" }.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", content = "message", rename_all = "kebab-case")]
+pub enum CommandError {
+    // the entity, camera, sprite... the command names doesn't exist
+    EntityNotFound(String),
+    // bevy isn't running, or not far enough along (renderer, paused updates)
+    NotReady(String),
+    // an argument is out of range or can't be parsed
+    InvalidArgument(String),
+    // anything else, like an I/O error or a failing driver
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::EntityNotFound(message)
+            | CommandError::NotReady(message)
+            | CommandError::InvalidArgument(message)
+            | CommandError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl CommandError {
+    // Same kind with `prefix` in front of the message, e.g. the failing op
+    pub fn prefixed(self, prefix: impl fmt::Display) -> Self {
+        let prefix = |message| format!("{}: {}", prefix, message);
+        match self {
+            CommandError::EntityNotFound(message) => CommandError::EntityNotFound(prefix(message)),
+            CommandError::NotReady(message) => CommandError::NotReady(prefix(message)),
+            CommandError::InvalidArgument(message) => {
+                CommandError::InvalidArgument(prefix(message))
+            }
+            CommandError::Failed(message) => CommandError::Failed(prefix(message)),
+        }
+    }
+}
+
+// Lets `?` pass on the String errors of helpers as Failed
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed(message)
+    }
+}
+
+type WorldCallback = Box<dyn FnOnce(&mut World) + Send>;
+
+// Handle managed by tauri so commands can run code against the bevy world.
+// Commands must be `async`: the bevy app is updated on the same main thread
+// that runs synchronous tauri commands, so waiting there would deadlock.
+#[derive(Clone)]
+pub struct BevyBridge {
+    sender: Sender<WorldCallback>,
+}
+
+impl BevyBridge {
+    // Queue `f` to run at the start of the next bevy update without waiting for it
+    pub fn send<F>(&self, f: F) -> Result<(), CommandError>
+    where
+        F: FnOnce(&mut World) + Send + 'static,
+    {
+        self.sender
+            .send(Box::new(f))
+            .map_err(|_| CommandError::NotReady(String::from("bevy app is not running")))
+    }
+
+    // Queue `f` to run at the start of the next bevy update and wait for its result
+    pub async fn run<F, R>(&self, f: F) -> Result<R, CommandError>
+    where
+        F: FnOnce(&mut World) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.send(move |world| {
+            let _ = tx.send(f(world));
+        })?;
+        rx.await
+            .map_err(|_| CommandError::NotReady(String::from("bevy app dropped the request")))
+    }
+}
+
+#[derive(Resource)]
+pub struct WorldCallbackReceiver(Mutex<Receiver<WorldCallback>>);
+
+pub fn channel() -> (BevyBridge, WorldCallbackReceiver) {
+    let (sender, receiver) = mpsc::channel();
+    (
+        BevyBridge { sender },
+        WorldCallbackReceiver(Mutex::new(receiver)),
+    )
+}
+
+pub fn apply_world_callbacks(world: &mut World) {
+    let callbacks: Vec<WorldCallback> = world
+        .resource::<WorldCallbackReceiver>()
+        .0
+        .lock()
+        .unwrap()
+        .try_iter()
+        .collect();
+
+    for callback in callbacks {
+        callback(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn command_errors_serialize_as_kind_and_message() {
+        let message = String::from("no entity with id 42");
+        let cases = [
+            (
+                CommandError::EntityNotFound(message.clone()),
+                "entity-not-found",
+            ),
+            (CommandError::NotReady(message.clone()), "not-ready"),
+            (
+                CommandError::InvalidArgument(message.clone()),
+                "invalid-argument",
+            ),
+            (CommandError::Failed(message.clone()), "failed"),
+        ];
+        for (error, kind) in cases {
+            assert_eq!(
+                serde_json::to_value(&error).unwrap(),
+                json!({ "kind": kind, "message": message })
+            );
+        }
+    }
+
+    #[test]
+    fn prefixed_keeps_the_kind() {
+        let error = CommandError::NotReady(String::from("no renderer")).prefixed("op 2");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            json!({ "kind": "not-ready", "message": "op 2: no renderer" })
+        );
+    }
+}
@@ -0,0 +1,496 @@
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::window::PrimaryWindow;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::scene;
+
+#[derive(Deserialize, Clone, Copy)]
+pub enum ProjectionKind {
+    Perspective,
+    Orthographic,
+}
+
+// Where the demo camera starts, see `--camera` in main
+#[derive(Resource, Clone, Copy)]
+pub struct CameraPose {
+    pub position: Vec3,
+    pub look_at: Vec3,
+}
+
+impl Default for CameraPose {
+    fn default() -> Self {
+        Self {
+            position: Vec3::new(-2.0, 2.5, 5.0),
+            look_at: Vec3::ZERO,
+        }
+    }
+}
+
+impl CameraPose {
+    fn validate(&self) -> Result<(), String> {
+        if !self.position.is_finite() || !self.look_at.is_finite() {
+            return Err(String::from("camera coordinates must be finite"));
+        }
+        if self.position.distance_squared(self.look_at) < 1e-6 {
+            return Err(String::from("camera can't look at its own position"));
+        }
+        Ok(())
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.position).looking_at(self.look_at, Vec3::Y)
+    }
+}
+
+// Parses the `x,y,z` value of `--camera`
+pub fn parse_camera_position(value: &str) -> Result<CameraPose, String> {
+    let coordinates = value
+        .split(',')
+        .map(|coordinate| coordinate.trim().parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("invalid camera position {}: {}", value, err))?;
+    let [x, y, z] = coordinates[..] else {
+        return Err(format!("camera position {} isn't x,y,z", value));
+    };
+    let pose = CameraPose {
+        position: Vec3::new(x, y, z),
+        ..default()
+    };
+    pose.validate()?;
+    Ok(pose)
+}
+
+const MIN_FOV_DEGREES: f32 = 10.0;
+const MAX_FOV_DEGREES: f32 = 120.0;
+
+// Perspective settings of a camera switched to orthographic, restored when it
+// switches back
+#[derive(Component)]
+struct SavedPerspective(PerspectiveProjection);
+
+// The Camera3d rendered first
+pub fn main_camera(world: &mut World) -> Result<Entity, CommandError> {
+    world
+        .query_filtered::<(Entity, &Camera), With<Camera3d>>()
+        .iter(world)
+        .min_by_key(|(_, camera)| camera.order)
+        .map(|(entity, _)| entity)
+        .ok_or_else(|| CommandError::EntityNotFound(String::from("no 3d camera")))
+}
+
+// Distance along the view direction to the origin, which the demo camera looks at
+fn focus_distance(transform: &Transform) -> f32 {
+    let along_forward = (-transform.translation).dot(*transform.forward());
+    if along_forward > 0.0 {
+        along_forward
+    } else {
+        transform.translation.length().max(1.0)
+    }
+}
+
+// Vertical field of view in degrees, clamped to 10..=120. An orthographic
+// camera keeps it for when it switches back to perspective.
+#[tauri::command]
+pub async fn set_camera_fov(
+    bridge: State<'_, BevyBridge>,
+    degrees: f32,
+) -> Result<(), CommandError> {
+    let fov = degrees.clamp(MIN_FOV_DEGREES, MAX_FOV_DEGREES).to_radians();
+    bridge
+        .run(move |world| {
+            let camera = main_camera(world)?;
+            let mut camera = world.entity_mut(camera);
+            if let Some(mut saved) = camera.get_mut::<SavedPerspective>() {
+                saved.0.fov = fov;
+            } else if let Some(mut projection) = camera.get_mut::<Projection>() {
+                if let Projection::Perspective(perspective) = projection.as_mut() {
+                    perspective.fov = fov;
+                }
+            }
+            Ok(())
+        })
+        .await?
+}
+
+// The transform is left alone, so the camera keeps looking the same way. The
+// orthographic view is sized to match what the perspective one showed at the
+// origin.
+#[tauri::command]
+pub async fn set_camera_projection(
+    bridge: State<'_, BevyBridge>,
+    kind: ProjectionKind,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let camera = main_camera(world)?;
+            let mut camera = world.entity_mut(camera);
+            let distance = camera.get::<Transform>().map_or(1.0, focus_distance);
+            let Some(projection) = camera.get::<Projection>().cloned() else {
+                return Err(CommandError::Failed(String::from(
+                    "camera has no projection",
+                )));
+            };
+
+            match (kind, projection) {
+                (ProjectionKind::Orthographic, Projection::Perspective(perspective)) => {
+                    let viewport_height = 2.0 * distance * (perspective.fov / 2.0).tan();
+                    camera.insert((
+                        Projection::Orthographic(OrthographicProjection {
+                            scaling_mode: ScalingMode::FixedVertical { viewport_height },
+                            ..OrthographicProjection::default_3d()
+                        }),
+                        SavedPerspective(perspective),
+                    ));
+                }
+                (ProjectionKind::Perspective, Projection::Orthographic(_)) => {
+                    let perspective = camera
+                        .take::<SavedPerspective>()
+                        .map(|saved| saved.0)
+                        .unwrap_or_default();
+                    camera.insert(Projection::Perspective(perspective));
+                }
+                _ => {}
+            }
+            Ok(())
+        })
+        .await?
+}
+
+pub fn place_camera(
+    world: &mut World,
+    pos: [f32; 3],
+    look_at: [f32; 3],
+) -> Result<(), CommandError> {
+    let pose = CameraPose {
+        position: Vec3::from_array(pos),
+        look_at: Vec3::from_array(look_at),
+    };
+    pose.validate().map_err(CommandError::InvalidArgument)?;
+    let camera = main_camera(world)?;
+    world.resource_mut::<CameraTween>().0 = None;
+    world.entity_mut(camera).insert(pose.transform());
+    Ok(())
+}
+
+// Moves the main camera to `pos`, looking at `look_at` with +Y up
+#[tauri::command]
+pub async fn set_camera_transform(
+    bridge: State<'_, BevyBridge>,
+    pos: [f32; 3],
+    look_at: [f32; 3],
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| place_camera(world, pos, look_at))
+        .await?
+}
+
+// How quickly the camera catches up with a followed entity, the remaining
+// distance shrinks by about this factor each second
+const FOLLOW_RATE: f32 = 4.0;
+
+// Entity the main camera tracks, set with `follow_entity`
+#[derive(Resource, Default)]
+pub struct CameraFollow(Option<FollowTarget>);
+
+struct FollowTarget {
+    entity: Entity,
+    // camera position relative to the target, kept from when following started
+    offset: Vec3,
+}
+
+// Camera poses saved with `save_camera_bookmark`, by name
+#[derive(Resource, Default)]
+pub struct CameraBookmarks(HashMap<String, Transform>);
+
+// The main camera's move to a bookmark, set with `animate_camera_to`
+#[derive(Resource, Default)]
+pub struct CameraTween(Option<Tween>);
+
+struct Tween {
+    camera: Entity,
+    from: Transform,
+    to: Transform,
+    elapsed: f32,
+    duration: f32,
+}
+
+// Moves the main camera: following an entity, and tweening it to bookmarks
+pub struct CameraFollowPlugin;
+
+impl Plugin for CameraFollowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraFollow>()
+            .init_resource::<CameraBookmarks>()
+            .init_resource::<CameraTween>()
+            .add_systems(
+                PostUpdate,
+                (follow_target, tween_camera)
+                    .after(bevy::app::Animation)
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+// Reads the target's GlobalTransform, so a child target is followed one frame late
+fn follow_target(
+    mut follow: ResMut<CameraFollow>,
+    time: Res<Time>,
+    targets: Query<&GlobalTransform>,
+    mut cameras: Query<(&Camera, &mut Transform), With<Camera3d>>,
+) {
+    let Some(target) = &follow.0 else {
+        return;
+    };
+    let Ok(target_transform) = targets.get(target.entity) else {
+        bevy::log::info!("followed entity was despawned, camera stops following");
+        follow.0 = None;
+        return;
+    };
+    let Some((_, mut transform)) = cameras.iter_mut().min_by_key(|(camera, _)| camera.order)
+    else {
+        return;
+    };
+    let focus = target_transform.translation();
+    let goal = Transform::from_translation(focus + target.offset).looking_at(focus, Vec3::Y);
+    let alpha = 1.0 - (-FOLLOW_RATE * time.delta_secs()).exp();
+    transform.translation = transform.translation.lerp(goal.translation, alpha);
+    transform.rotation = transform.rotation.slerp(goal.rotation, alpha);
+}
+
+// Makes the main camera track `name`, a Name or entity id, from its current
+// distance and direction until `stop_follow`
+#[tauri::command]
+pub async fn follow_entity(
+    bridge: State<'_, BevyBridge>,
+    name: String,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let camera = main_camera(world)?;
+            let entity = scene::resolve_target(world, &name)
+                .ok_or_else(|| CommandError::EntityNotFound(format!("no entity {}", name)))?;
+            if entity == camera {
+                return Err(CommandError::InvalidArgument(String::from(
+                    "the camera can't follow itself",
+                )));
+            }
+            let target = world
+                .get::<GlobalTransform>(entity)
+                .ok_or_else(|| format!("{} has no transform", name))?
+                .translation();
+            let position = world
+                .get::<Transform>(camera)
+                .map_or(Vec3::ZERO, |transform| transform.translation);
+            let mut offset = position - target;
+            if offset.length() < 0.1 {
+                offset = Vec3::new(0.0, 2.0, 5.0);
+            }
+            world.resource_mut::<CameraTween>().0 = None;
+            world.resource_mut::<CameraFollow>().0 = Some(FollowTarget { entity, offset });
+            Ok(())
+        })
+        .await?
+}
+
+// The camera stays where following left it
+#[tauri::command]
+pub async fn stop_follow(bridge: State<'_, BevyBridge>) -> Result<(), CommandError> {
+    bridge
+        .run(|world| world.resource_mut::<CameraFollow>().0 = None)
+        .await
+}
+
+// Eases in and out, so the camera starts and stops without a jolt
+fn tween_camera(
+    mut tween: ResMut<CameraTween>,
+    time: Res<Time>,
+    mut cameras: Query<&mut Transform, With<Camera3d>>,
+) {
+    let Some(active) = &mut tween.0 else {
+        return;
+    };
+    let Ok(mut transform) = cameras.get_mut(active.camera) else {
+        tween.0 = None;
+        return;
+    };
+    active.elapsed += time.delta_secs();
+    let progress = if active.duration > 0.0 {
+        (active.elapsed / active.duration).min(1.0)
+    } else {
+        1.0
+    };
+    let eased = EasingCurve::new(0.0, 1.0, EaseFunction::CubicInOut).sample_clamped(progress);
+    transform.translation = active.from.translation.lerp(active.to.translation, eased);
+    transform.rotation = active.from.rotation.slerp(active.to.rotation, eased);
+    if progress >= 1.0 {
+        tween.0 = None;
+    }
+}
+
+// Saves where the main camera is and which way it looks as `name`, replacing
+// an earlier bookmark of that name
+#[tauri::command]
+pub async fn save_camera_bookmark(
+    bridge: State<'_, BevyBridge>,
+    name: String,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let camera = main_camera(world)?;
+            let transform = *world
+                .get::<Transform>(camera)
+                .ok_or_else(|| String::from("camera has no transform"))?;
+            world
+                .resource_mut::<CameraBookmarks>()
+                .0
+                .insert(name, transform);
+            Ok(())
+        })
+        .await?
+}
+
+// Moves the main camera from wherever it is to the bookmark `name` over
+// `seconds`. Resolves once the move started. A move already under way is
+// cancelled and the new one starts from where that one got to; following an
+// entity stops, and set_camera_transform cancels the move.
+#[tauri::command]
+pub async fn animate_camera_to(
+    bridge: State<'_, BevyBridge>,
+    bookmark: String,
+    seconds: f32,
+) -> Result<(), CommandError> {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid duration {}",
+            seconds
+        )));
+    }
+    bridge
+        .run(move |world| {
+            let camera = main_camera(world)?;
+            let to = *world
+                .resource::<CameraBookmarks>()
+                .0
+                .get(&bookmark)
+                .ok_or_else(|| {
+                    CommandError::InvalidArgument(format!("no camera bookmark {}", bookmark))
+                })?;
+            let from = *world
+                .get::<Transform>(camera)
+                .ok_or_else(|| String::from("camera has no transform"))?;
+            world.resource_mut::<CameraFollow>().0 = None;
+            world.resource_mut::<CameraTween>().0 = Some(Tween {
+                camera,
+                from,
+                to,
+                elapsed: 0.0,
+                duration: seconds,
+            });
+            Ok(())
+        })
+        .await?
+}
+
+// Maps a point of the camera's viewport to logical window pixels. Cameras
+// redirected to an image (--offscreen, --resolution) are shown scaled to fit
+// the window and centered, like render_resolution's blit.
+fn viewport_to_window(camera: &Camera, point: Vec2, window_size: Vec2) -> Option<Vec2> {
+    let point = point + camera.logical_viewport_rect()?.min;
+    if !matches!(camera.target, RenderTarget::Image(_)) {
+        return Some(point);
+    }
+    let target_size = camera.logical_target_size()?;
+    let scale = (window_size / target_size).min_element();
+    Some(point * scale + (window_size - target_size * scale) / 2.0)
+}
+
+// Where `entity_id` is on screen, in logical pixels from the top left of
+// bevy's window, the same as CSS pixels in the webview. None while it's
+// behind the main camera. Positions are those of the last update, poll it for
+// HTML labels that follow an entity.
+#[tauri::command]
+pub async fn world_to_screen(
+    bridge: State<'_, BevyBridge>,
+    entity_id: u64,
+) -> Result<Option<(f32, f32)>, CommandError> {
+    bridge
+        .run(move |world| {
+            let point = screen_position(world, entity_id)?;
+            Ok(point.map(|point| (point.x, point.y)))
+        })
+        .await?
+}
+
+fn screen_position(world: &mut World, entity_id: u64) -> Result<Option<Vec2>, CommandError> {
+    let entity = scene::entity_by_id(world, entity_id)?;
+    let window_size = world
+        .query_filtered::<&Window, With<PrimaryWindow>>()
+        .get_single(world)
+        .map(|window| window.size())
+        .map_err(|_| CommandError::EntityNotFound(String::from("no primary window")))?;
+    let camera_entity = main_camera(world)?;
+    let position = world
+        .get::<GlobalTransform>(entity)
+        .ok_or_else(|| format!("entity {} has no transform", entity_id))?
+        .translation();
+    let (Some(camera), Some(camera_transform)) = (
+        world.get::<Camera>(camera_entity),
+        world.get::<GlobalTransform>(camera_entity),
+    ) else {
+        return Ok(None);
+    };
+    Ok(camera
+        .world_to_viewport(camera_transform, position)
+        .ok()
+        .and_then(|point| viewport_to_window(camera, point, window_size)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::AssetEvent;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::render::camera::{camera_system, ManualTextureViews};
+    use bevy::window::{WindowCreated, WindowResized, WindowScaleFactorChanged};
+
+    use crate::tauri_plugin;
+
+    #[test]
+    fn world_to_screen_is_in_logical_pixels() {
+        let mut world = World::new();
+        world.init_resource::<Events<WindowCreated>>();
+        world.init_resource::<Events<WindowResized>>();
+        world.init_resource::<Events<WindowScaleFactorChanged>>();
+        world.init_resource::<Events<AssetEvent<Image>>>();
+        world.init_resource::<Assets<Image>>();
+        world.init_resource::<ManualTextureViews>();
+
+        // an 800x600 window on a HiDPI monitor, as the runner sets it up
+        let mut window = Window::default();
+        tauri_plugin::resize_window(&mut window, tauri::PhysicalSize::new(1600, 1200), 2.0);
+        world.spawn((window, PrimaryWindow));
+        let transform = Transform::from_xyz(0.0, 0.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y);
+        world.spawn((
+            Camera3d::default(),
+            transform,
+            GlobalTransform::from(transform),
+        ));
+        let origin = world
+            .spawn((Transform::default(), GlobalTransform::default()))
+            .id();
+        world.run_system_once(camera_system::<Projection>).unwrap();
+
+        let point = screen_position(&mut world, origin.to_bits())
+            .unwrap()
+            .unwrap();
+        assert!(
+            point.abs_diff_eq(Vec2::new(400.0, 300.0), 1e-3),
+            "{}",
+            point
+        );
+    }
+}
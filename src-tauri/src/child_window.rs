@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::window::{
+    PrimaryWindow, RawHandleWrapper, WindowCreated, WindowRef, WindowResized,
+    WindowScaleFactorChanged, WindowWrapper,
+};
+use tauri::{Manager, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::camera;
+use crate::scene::Persistent;
+
+// Bevy window rendering into the webview window with this label, spawned by
+// `open_window`. The primary window is left to TauriPlugin.
+#[derive(Component)]
+pub struct ChildWindow(pub String);
+
+fn child_window(world: &mut World, label: &str) -> Option<Entity> {
+    world
+        .query::<(Entity, &ChildWindow)>()
+        .iter(world)
+        .find(|(_, child)| child.0 == label)
+        .map(|(entity, _)| entity)
+}
+
+// Spawns a bevy window for `tauri_window` and a camera rendering to it, which
+// starts as a copy of the main camera. Returns the camera.
+fn spawn_window(world: &mut World, tauri_window: &WebviewWindow) -> Result<Entity, String> {
+    let label = tauri_window.label().to_string();
+    let size = tauri_window.inner_size().map_err(|err| err.to_string())?;
+    let scale_factor = tauri_window.scale_factor().map_err(|err| err.to_string())?;
+    let handle = RawHandleWrapper::new(&WindowWrapper::new(tauri_window.clone()))
+        .map_err(|err| format!("no window handle for {}: {}", label, err))?;
+
+    // same alpha mode as the primary window, so the webview shows through the same way
+    let composite_alpha_mode = world
+        .query_filtered::<&Window, With<PrimaryWindow>>()
+        .get_single(world)
+        .map(|window| window.composite_alpha_mode)
+        .unwrap_or_default();
+    let mut window = Window {
+        title: label.clone(),
+        composite_alpha_mode,
+        ..default()
+    };
+    window.resolution.set_scale_factor(scale_factor as f32);
+    window
+        .resolution
+        .set_physical_resolution(size.width, size.height);
+    let window = world.spawn((window, handle, ChildWindow(label))).id();
+    world.send_event(WindowCreated { window });
+
+    let main = camera::main_camera(world).ok();
+    let mut camera = main
+        .and_then(|main| world.get::<Camera>(main).cloned())
+        .unwrap_or_default();
+    // main_camera picks the lowest order, keep that the primary window's camera
+    camera.order += 1;
+    camera.target = RenderTarget::Window(WindowRef::Entity(window));
+    let transform = main
+        .and_then(|main| world.get::<Transform>(main).copied())
+        .unwrap_or_else(|| Transform::from_xyz(0.0, 4.5, 9.0).looking_at(Vec3::ZERO, Vec3::Y));
+    Ok(world
+        .spawn((Camera3d::default(), camera, transform, Persistent))
+        .id())
+}
+
+// Despawns the bevy window labeled `label` and the cameras rendering to it,
+// returns false when there is none
+fn despawn_window(world: &mut World, label: &str) -> bool {
+    let Some(window) = child_window(world, label) else {
+        return false;
+    };
+    let cameras: Vec<Entity> = world
+        .query::<(Entity, &Camera)>()
+        .iter(world)
+        .filter(|(_, camera)| match camera.target {
+            RenderTarget::Window(WindowRef::Entity(target)) => target == window,
+            _ => false,
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+    for camera in cameras {
+        world.entity_mut(camera).despawn_recursive();
+    }
+    world.entity_mut(window).despawn_recursive();
+    true
+}
+
+fn resize_window(world: &mut World, label: &str, size: tauri::PhysicalSize<u32>, scale: f64) {
+    let Some(entity) = child_window(world, label) else {
+        return;
+    };
+    let mut window = world.get_mut::<Window>(entity).unwrap();
+    let scale_changed = window.resolution.scale_factor() != scale as f32;
+    window.resolution.set_scale_factor(scale as f32);
+    window
+        .resolution
+        .set_physical_resolution(size.width.max(1), size.height.max(1));
+    let (width, height) = (window.resolution.width(), window.resolution.height());
+    if scale_changed {
+        world.send_event(WindowScaleFactorChanged {
+            window: entity,
+            scale_factor: scale,
+        });
+    }
+    world.send_event(WindowResized {
+        window: entity,
+        width,
+        height,
+    });
+}
+
+// Closes the window labeled `label`. When bevy renders into it, its surface is
+// dropped before the native window goes away, which some drivers don't survive
+// the other way around.
+pub async fn close(
+    app: &tauri::AppHandle,
+    bridge: &BevyBridge,
+    label: &str,
+) -> Result<(), CommandError> {
+    let window = app
+        .get_webview_window(label)
+        .ok_or_else(|| CommandError::EntityNotFound(format!("no window labeled {}", label)))?;
+    let owned = label.to_string();
+    if !bridge.run(move |world| despawn_window(world, &owned)).await? {
+        return window
+            .close()
+            .map_err(|err| CommandError::Failed(err.to_string()));
+    }
+    // bevy drops the surface while extracting the update that despawned its
+    // window, so it is gone once the next update starts
+    bridge.run(|_| ()).await?;
+    window
+        .destroy()
+        .map_err(|err| CommandError::Failed(err.to_string()))
+}
+
+// Keeps the bevy window in step with its tauri window, closing it the same way
+// as `close_window`
+fn forward_window_events(app: &tauri::AppHandle, bridge: &BevyBridge, window: &WebviewWindow) {
+    let app = app.clone();
+    let bridge = bridge.clone();
+    let label = window.label().to_string();
+    let scale_window = window.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Resized(size) => {
+            let (label, size) = (label.clone(), *size);
+            let scale = scale_window.scale_factor().unwrap_or(1.0);
+            let _ = bridge.send(move |world| resize_window(world, &label, size, scale));
+        }
+        WindowEvent::ScaleFactorChanged {
+            scale_factor,
+            new_inner_size,
+            ..
+        } => {
+            let (label, size, scale) = (label.clone(), *new_inner_size, *scale_factor);
+            let _ = bridge.send(move |world| resize_window(world, &label, size, scale));
+        }
+        WindowEvent::CloseRequested { api, .. } => {
+            api.prevent_close();
+            let (app, bridge, label) = (app.clone(), bridge.clone(), label.clone());
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = close(&app, &bridge, &label).await {
+                    bevy::log::warn!("failed to close {}: {}", label, err);
+                }
+            });
+        }
+        // destroyed without going through `close`, e.g. when the app exits
+        WindowEvent::Destroyed => {
+            let label = label.clone();
+            let _ = bridge.send(move |world| {
+                despawn_window(world, &label);
+            });
+        }
+        _ => (),
+    });
+}
+
+// Opens a webview window labeled `label` showing `url`, a path of the frontend
+// like "/" or an http(s) URL. With `host_bevy` bevy renders into it as well,
+// under a transparent webview, and the id of its camera is returned so it can
+// be moved with set_transform. Fails if the label is taken.
+#[tauri::command]
+pub async fn open_window(
+    app: tauri::AppHandle,
+    bridge: State<'_, BevyBridge>,
+    label: String,
+    width: f64,
+    height: f64,
+    url: String,
+    host_bevy: bool,
+) -> Result<Option<u64>, CommandError> {
+    if !(width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0) {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid window size {}x{}",
+            width, height
+        )));
+    }
+    if app.get_webview_window(&label).is_some() {
+        return Err(CommandError::InvalidArgument(format!(
+            "a window labeled {} already exists",
+            label
+        )));
+    }
+    let url = if url.starts_with("http://") || url.starts_with("https://") {
+        WebviewUrl::External(url.parse().map_err(|err| {
+            CommandError::InvalidArgument(format!("invalid url {}: {}", url, err))
+        })?)
+    } else {
+        WebviewUrl::App(url.into())
+    };
+    let window = WebviewWindowBuilder::new(&app, &label, url)
+        .title(&label)
+        .inner_size(width, height)
+        .transparent(host_bevy)
+        .build()
+        .map_err(|err| format!("failed to open {}: {}", label, err))?;
+    if !host_bevy {
+        return Ok(None);
+    }
+
+    let tauri_window = window.clone();
+    let camera = bridge
+        .run(move |world| spawn_window(world, &tauri_window))
+        .await?;
+    match camera {
+        Ok(camera) => {
+            forward_window_events(&app, &bridge, &window);
+            Ok(Some(camera.to_bits()))
+        }
+        Err(err) => {
+            let _ = window.destroy();
+            Err(CommandError::Failed(err))
+        }
+    }
+}
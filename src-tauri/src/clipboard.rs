@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+// winit is bypassed, so bevy never sees the system clipboard. Text fields send
+// these events instead and the requests are served by tauri's clipboard manager.
+#[derive(Event)]
+pub struct RequestClipboardPaste;
+
+#[derive(Event)]
+pub struct ClipboardPaste(pub String);
+
+#[derive(Event)]
+pub struct CopyToClipboard(pub String);
+
+// Needs the tauri::AppHandle inserted by TauriPlugin, so add it after that one
+pub struct ClipboardPlugin;
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RequestClipboardPaste>()
+            .add_event::<ClipboardPaste>()
+            .add_event::<CopyToClipboard>()
+            .add_systems(PostUpdate, handle_clipboard_requests);
+    }
+}
+
+fn handle_clipboard_requests(
+    mut paste_requests: EventReader<RequestClipboardPaste>,
+    mut copy_requests: EventReader<CopyToClipboard>,
+    mut pastes: EventWriter<ClipboardPaste>,
+    tauri_app: NonSend<tauri::AppHandle>,
+) {
+    // copy first so a copy followed by a paste in the same frame sees the new text
+    for CopyToClipboard(text) in copy_requests.read() {
+        if let Err(err) = tauri_app.clipboard().write_text(text.as_str()) {
+            warn!("failed to write clipboard: {}", err);
+        }
+    }
+
+    if paste_requests.read().count() > 0 {
+        match tauri_app.clipboard().read_text() {
+            Ok(text) => {
+                pastes.send(ClipboardPaste(text));
+            }
+            Err(err) => warn!("failed to read clipboard: {}", err),
+        }
+    }
+}
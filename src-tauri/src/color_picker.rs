@@ -0,0 +1,230 @@
+use bevy::core_pipeline::core_3d::graph::{Core3d, Node3d};
+use bevy::ecs::query::QueryItem;
+use bevy::prelude::*;
+use bevy::render::camera::{CameraMainTextureUsages, ExtractedCamera};
+use bevy::render::render_graph::{
+    NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+};
+use bevy::render::render_resource::{
+    Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageCopyTexture,
+    ImageDataLayout, MapMode, Origin3d, TextureFormat, TextureUsages,
+};
+use bevy::render::renderer::{render_system, RenderContext};
+use bevy::render::view::ViewTarget;
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::ui::graph::NodeUi;
+use std::sync::{Arc, Mutex};
+use tauri::{Manager, State};
+use tokio::sync::oneshot;
+
+use crate::bridge::CommandError;
+
+type PickReply = oneshot::Sender<Result<[u8; 4], CommandError>>;
+
+struct ColorPick {
+    x: u32,
+    y: u32,
+    reply: PickReply,
+}
+
+// Shared by the `pick_color_at` command and the render world, requests don't
+// need to go through the main world
+#[derive(Resource, Clone, Default)]
+pub struct ColorPickQueue(Arc<Mutex<Vec<ColorPick>>>);
+
+// Texels copied this frame, mapped once the frame has been submitted
+#[derive(Resource, Default)]
+struct CopiedPicks(Mutex<Vec<CopiedPick>>);
+
+struct CopiedPick {
+    buffer: Buffer,
+    format: TextureFormat,
+    reply: PickReply,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct ColorPickLabel;
+
+// Reads single texels of the window camera's main texture after the UI pass,
+// see `pick_color_at`. Register it with TauriPlugin::with_render_plugin.
+pub struct ColorPickerPlugin;
+
+impl Plugin for ColorPickerPlugin {
+    fn build(&self, app: &mut App) {
+        let queue = ColorPickQueue::default();
+        app.world()
+            .non_send_resource::<tauri::AppHandle>()
+            .manage(queue.clone());
+        app.add_systems(PostUpdate, allow_texture_copies);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(queue)
+            .init_resource::<CopiedPicks>()
+            .add_systems(
+                Render,
+                map_copied_picks
+                    .after(render_system)
+                    .in_set(RenderSet::Render),
+            )
+            .add_render_graph_node::<ViewNodeRunner<ColorPickNode>>(Core3d, ColorPickLabel)
+            .add_render_graph_edges(Core3d, (NodeUi::UiPass, ColorPickLabel, Node3d::Upscaling));
+    }
+}
+
+// Main textures can't be copied from unless the camera asks for it
+fn allow_texture_copies(mut cameras: Query<&mut CameraMainTextureUsages, Added<Camera3d>>) {
+    for mut usages in cameras.iter_mut() {
+        usages.0 |= TextureUsages::COPY_SRC;
+    }
+}
+
+#[derive(Default)]
+struct ColorPickNode;
+
+impl ViewNode for ColorPickNode {
+    type ViewQuery = (&'static ViewTarget, &'static ExtractedCamera);
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, camera): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        // a camera redirected to an image, e.g. by dynamic resolution, still
+        // renders the frame the picks are for
+        if camera.target.is_none() {
+            return Ok(());
+        }
+        let picks: Vec<ColorPick> =
+            std::mem::take(&mut *world.resource::<ColorPickQueue>().0.lock().unwrap());
+        if picks.is_empty() {
+            return Ok(());
+        }
+
+        let texture = view_target.main_texture();
+        let format = view_target.main_texture_format();
+        let mut copied = world.resource::<CopiedPicks>().0.lock().unwrap();
+        for pick in picks {
+            if pick.x >= texture.width() || pick.y >= texture.height() {
+                let _ = pick.reply.send(Err(CommandError::InvalidArgument(format!(
+                    "({}, {}) is outside the {}x{} frame",
+                    pick.x,
+                    pick.y,
+                    texture.width(),
+                    texture.height()
+                ))));
+                continue;
+            }
+            let Some(texel_size) = texel_size(format) else {
+                let _ = pick.reply.send(Err(CommandError::Failed(format!(
+                    "can't read {:?} frames",
+                    format
+                ))));
+                continue;
+            };
+
+            let buffer = render_context
+                .render_device()
+                .create_buffer(&BufferDescriptor {
+                    label: Some("color_pick_buffer"),
+                    size: texel_size,
+                    usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                });
+            render_context.command_encoder().copy_texture_to_buffer(
+                ImageCopyTexture {
+                    origin: Origin3d {
+                        x: pick.x,
+                        y: pick.y,
+                        z: 0,
+                    },
+                    ..texture.as_image_copy()
+                },
+                ImageCopyBuffer {
+                    buffer: &buffer,
+                    layout: ImageDataLayout::default(),
+                },
+                Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+            );
+            copied.push(CopiedPick {
+                buffer,
+                format,
+                reply: pick.reply,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn texel_size(format: TextureFormat) -> Option<u64> {
+    match format {
+        TextureFormat::Rgba8Unorm
+        | TextureFormat::Rgba8UnormSrgb
+        | TextureFormat::Bgra8Unorm
+        | TextureFormat::Bgra8UnormSrgb => Some(4),
+        TextureFormat::Rgba16Float => Some(8),
+        _ => None,
+    }
+}
+
+// sRGB 8-bit RGBA, HDR main textures hold linear values after tonemapping
+fn texel_to_srgba(format: TextureFormat, bytes: &[u8]) -> [u8; 4] {
+    match format {
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+            [bytes[2], bytes[1], bytes[0], bytes[3]]
+        }
+        TextureFormat::Rgba16Float => {
+            let channel =
+                |i: usize| half::f16::from_le_bytes([bytes[i * 2], bytes[i * 2 + 1]]).to_f32();
+            Srgba::from(LinearRgba::new(
+                channel(0),
+                channel(1),
+                channel(2),
+                channel(3),
+            ))
+            .to_u8_array()
+        }
+        _ => [bytes[0], bytes[1], bytes[2], bytes[3]],
+    }
+}
+
+// Runs after the frame has been submitted, the callbacks fire once the GPU is done
+fn map_copied_picks(copied: Res<CopiedPicks>) {
+    for pick in copied.0.lock().unwrap().drain(..) {
+        let buffer = pick.buffer.clone();
+        pick.buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                let texel = result
+                    .map(|_| texel_to_srgba(pick.format, &buffer.slice(..).get_mapped_range()))
+                    .map_err(|err| {
+                        CommandError::Failed(format!("failed to read the frame: {}", err))
+                    });
+                buffer.unmap();
+                let _ = pick.reply.send(texel);
+            });
+    }
+}
+
+// Resolves with the sRGB RGBA color at physical pixel (x, y) of the next
+// rendered frame, counted from the top left. With dynamic resolution the
+// frame is smaller than the window, so scale window coordinates first.
+#[tauri::command]
+pub async fn pick_color_at(
+    queue: State<'_, ColorPickQueue>,
+    x: u32,
+    y: u32,
+) -> Result<[u8; 4], CommandError> {
+    let (reply, rx) = oneshot::channel();
+    queue.0.lock().unwrap().push(ColorPick { x, y, reply });
+    rx.await
+        .map_err(|_| CommandError::NotReady(String::from("bevy app dropped the request")))?
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::picking::Selected;
+
+// Whether draw_debug_gizmos outlines the selected entities
+#[derive(Resource, Default)]
+pub struct DebugGizmos {
+    pub enabled: bool,
+}
+
+// Draws AABBs and axes of Selected entities while DebugGizmos is enabled.
+// Gizmos are drawn by every camera within its own viewport, so they line up
+// with the scene even when the camera only covers part of the window.
+pub struct DebugGizmosPlugin;
+
+impl Plugin for DebugGizmosPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugGizmos>().add_systems(
+            PostUpdate,
+            draw_debug_gizmos
+                .after(TransformSystem::TransformPropagate)
+                .run_if(|debug: Res<DebugGizmos>| debug.enabled),
+        );
+    }
+}
+
+fn draw_debug_gizmos(
+    mut gizmos: Gizmos,
+    selected: Query<(&GlobalTransform, Option<&Aabb>), With<Selected>>,
+) {
+    for (transform, aabb) in selected.iter() {
+        let mut axis_length = 0.5;
+        if let Some(aabb) = aabb {
+            let bounds = Transform::from_translation(aabb.center.into())
+                .with_scale(Vec3::from(aabb.half_extents) * 2.0);
+            gizmos.cuboid(*transform * bounds, Color::srgb(1.0, 0.9, 0.2));
+            axis_length = aabb.half_extents.max_element() * 1.5;
+        }
+        gizmos.axes(*transform, axis_length);
+    }
+}
+
+#[tauri::command]
+pub async fn set_debug_gizmos(
+    bridge: State<'_, BevyBridge>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            world.resource_mut::<DebugGizmos>().enabled = enabled;
+        })
+        .await
+}
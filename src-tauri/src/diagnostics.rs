@@ -0,0 +1,87 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::bridge::CommandError;
+
+// What the window surface supports, stored by whichever path creates the renderer
+#[derive(Serialize, Clone)]
+pub struct SurfaceInfo {
+    pub formats: Vec<String>,
+    pub present_modes: Vec<String>,
+    pub alpha_modes: Vec<String>,
+    // offers a format with more range than 8 bit, see quality::hdr_supported
+    pub hdr: bool,
+}
+
+impl SurfaceInfo {
+    pub fn new(capabilities: &wgpu::SurfaceCapabilities) -> Self {
+        Self {
+            formats: debug_names(&capabilities.formats),
+            present_modes: debug_names(&capabilities.present_modes),
+            alpha_modes: debug_names(&capabilities.alpha_modes),
+            hdr: capabilities.formats.iter().any(|format| {
+                matches!(
+                    format,
+                    wgpu::TextureFormat::Rgba16Float | wgpu::TextureFormat::Rgb10a2Unorm
+                )
+            }),
+        }
+    }
+}
+
+fn debug_names<T: std::fmt::Debug>(values: &[T]) -> Vec<String> {
+    values.iter().map(|value| format!("{:?}", value)).collect()
+}
+
+// tauri keeps the first value managed for a type, so the info sits behind a
+// Mutex that a renderer started later, e.g. by set_renderer, writes into
+#[derive(Default)]
+struct SurfaceInfoStore(Mutex<Option<SurfaceInfo>>);
+
+pub fn store_surface_info<R: tauri::Runtime>(manager: &impl Manager<R>, info: SurfaceInfo) {
+    manager.manage(SurfaceInfoStore::default());
+    *manager.state::<SurfaceInfoStore>().0.lock().unwrap() = Some(info);
+}
+
+pub fn surface_info(app: &tauri::AppHandle) -> Result<SurfaceInfo, CommandError> {
+    app.try_state::<SurfaceInfoStore>()
+        .and_then(|store| store.0.lock().unwrap().clone())
+        .ok_or_else(|| CommandError::NotReady(String::from("renderer is not initialized yet")))
+}
+
+#[tauri::command]
+pub fn get_surface_info(app: tauri::AppHandle) -> Result<SurfaceInfo, CommandError> {
+    surface_info(&app)
+}
+
+// How far starting the renderer got, stored on tauri's Ready event whether it
+// succeeded or not, so the webview can explain a blank window
+#[derive(Serialize, Clone, Default, Debug)]
+pub struct RendererStatus {
+    pub surface: bool,
+    pub adapter: bool,
+    pub device: bool,
+    // of the adapter, e.g. "Vulkan"
+    pub backend: Option<String>,
+    pub adapter_name: Option<String>,
+    // why it stopped, None once the renderer is running
+    pub error: Option<String>,
+}
+
+impl RendererStatus {
+    pub fn found_adapter(&mut self, info: &wgpu::AdapterInfo) {
+        self.adapter = true;
+        self.backend = Some(format!("{:?}", info.backend));
+        self.adapter_name = Some(info.name.clone());
+    }
+}
+
+// NotReady until the renderer was started, then the status even if it failed.
+// The app only stays open after a failure with TauriPlugin::with_renderer_error_screen.
+#[tauri::command]
+pub fn get_renderer_status(app: tauri::AppHandle) -> Result<RendererStatus, CommandError> {
+    app.try_state::<RendererStatus>()
+        .map(|status| status.inner().clone())
+        .ok_or_else(|| CommandError::NotReady(String::from("renderer is not started yet")))
+}
@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_resource::Extent3d;
+use bevy::render::view::RenderLayers;
+use bevy::window::{PrimaryWindow, WindowRef};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use crate::render_resolution;
+use crate::scene::Persistent;
+use crate::tauri_plugin::AVERAGE_FRAME_RATE;
+
+// Lowers the render resolution while the average frame rate is below
+// `lower_fps` and restores it once it reaches `upper_fps` again. The gap
+// between both thresholds and `settle_time` keep the scale from oscillating.
+// While scaled down the scene renders into an image that is stretched over the
+// window, and picking doesn't see it. Not combined with RenderResolution or
+// OffscreenStream, which redirect the cameras themselves.
+#[derive(Resource, Clone)]
+pub struct DynamicResolution {
+    pub lower_fps: usize,
+    pub upper_fps: usize,
+    pub min_scale: f32,
+    pub step: f32,
+    pub settle_time: Duration,
+}
+
+impl Default for DynamicResolution {
+    fn default() -> Self {
+        Self {
+            lower_fps: 45,
+            upper_fps: 58,
+            min_scale: 0.5,
+            step: 0.1,
+            settle_time: Duration::from_secs(2),
+        }
+    }
+}
+
+// Fraction of the window's physical size bevy currently renders the scene at
+#[derive(Resource)]
+pub struct RenderScale {
+    pub scale: f32,
+    changed_at: Duration,
+}
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            changed_at: Duration::ZERO,
+        }
+    }
+}
+
+fn adjust_render_scale(
+    settings: Res<DynamicResolution>,
+    time: Res<Time<Real>>,
+    mut render_scale: ResMut<RenderScale>,
+) {
+    // the average frame rate is only refreshed once a second, give it time to
+    // reflect the last change
+    if time.elapsed() - render_scale.changed_at < settings.settle_time {
+        return;
+    }
+
+    let fps = AVERAGE_FRAME_RATE.load(Ordering::Relaxed);
+    if fps == 0 {
+        return;
+    }
+
+    let scale = if fps < settings.lower_fps {
+        (render_scale.scale - settings.step).max(settings.min_scale)
+    } else if fps >= settings.upper_fps {
+        (render_scale.scale + settings.step).min(1.0)
+    } else {
+        render_scale.scale
+    };
+
+    if scale != render_scale.scale {
+        bevy::log::info!(
+            "render scale {} -> {} at {} fps",
+            render_scale.scale,
+            scale,
+            fps
+        );
+        render_scale.scale = scale;
+        render_scale.changed_at = time.elapsed();
+    }
+}
+
+// The image the primary window's cameras render to while the scale is below
+// 1, and the sprite that stretches it over the window
+#[derive(Resource)]
+struct ScaledTarget {
+    image: Handle<Image>,
+    sprite: Entity,
+}
+
+// Draws the scaled image on the window
+#[derive(Component)]
+struct UpscaleCamera;
+
+// Keeps the upscaled sprite away from other 2d cameras and from
+// RenderResolution's blit
+const UPSCALE_LAYER: usize = 30;
+
+// Sizes the image to the render scale of the window's physical size and
+// stretches it over the window, like RenderResolution's blit. The swapchain
+// and the window's scale factor stay as they are, so only the 3d scene is
+// rendered at fewer pixels.
+fn apply_render_scale(
+    render_scale: Res<RenderScale>,
+    target: Res<ScaledTarget>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let size = (window.physical_size().as_vec2() * render_scale.scale)
+        .round()
+        .as_uvec2()
+        .max(UVec2::ONE);
+    if images
+        .get(&target.image)
+        .is_some_and(|image| image.size() != size)
+    {
+        if let Some(image) = images.get_mut(&target.image) {
+            image.resize(Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            });
+        }
+    }
+    if let Ok(mut sprite) = sprites.get_mut(target.sprite) {
+        if sprite.custom_size != Some(window.size()) {
+            sprite.custom_size = Some(window.size());
+        }
+    }
+}
+
+// Points the primary window's cameras at the image while the scale is below
+// 1 and back at the window at full scale, so picking, which only sees
+// cameras rendering to the window, keeps working until the frame rate drops
+fn redirect_cameras(
+    render_scale: Res<RenderScale>,
+    target: Res<ScaledTarget>,
+    mut cameras: Query<&mut Camera, Without<UpscaleCamera>>,
+    mut upscale: Query<&mut Camera, With<UpscaleCamera>>,
+) {
+    let scaled = render_scale.scale < 1.0;
+    for mut camera in cameras.iter_mut() {
+        let redirected =
+            matches!(&camera.target, RenderTarget::Image(image) if *image == target.image);
+        if scaled && matches!(camera.target, RenderTarget::Window(WindowRef::Primary)) {
+            camera.target = RenderTarget::Image(target.image.clone());
+        } else if !scaled && redirected {
+            camera.target = RenderTarget::Window(WindowRef::Primary);
+        }
+    }
+    for mut camera in upscale.iter_mut() {
+        if camera.is_active != scaled {
+            camera.is_active = scaled;
+        }
+    }
+}
+
+impl Plugin for DynamicResolution {
+    fn build(&self, app: &mut App) {
+        let image = render_resolution::target_image(UVec2::ONE, false);
+        let image = app.world_mut().resource_mut::<Assets<Image>>().add(image);
+        app.world_mut().spawn((
+            Camera2d,
+            Camera {
+                // after every camera that renders into the image
+                order: isize::MAX,
+                clear_color: ClearColorConfig::Custom(Color::NONE),
+                is_active: false,
+                ..default()
+            },
+            RenderLayers::layer(UPSCALE_LAYER),
+            UpscaleCamera,
+            Persistent,
+        ));
+        let sprite = app
+            .world_mut()
+            .spawn((
+                Sprite {
+                    image: image.clone(),
+                    ..default()
+                },
+                RenderLayers::layer(UPSCALE_LAYER),
+                Persistent,
+            ))
+            .id();
+
+        app.insert_resource(self.clone())
+            .insert_resource(ScaledTarget { image, sprite })
+            .init_resource::<RenderScale>()
+            .add_systems(
+                PostUpdate,
+                (adjust_render_scale, apply_render_scale, redirect_cameras).chain(),
+            );
+    }
+}
@@ -0,0 +1,226 @@
+use bevy::pbr::{DistanceFog, FogFalloff};
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    TextureDimension, TextureSampleType, TextureViewDescriptor, TextureViewDimension,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::State;
+
+use crate::assets;
+use crate::bridge::{BevyBridge, CommandError};
+use crate::camera;
+
+// Every environment knob in one struct so the frontend can read or apply them
+// in a single round-trip. Colors are sRGB components in 0..=1.
+#[derive(Resource, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SceneSettings {
+    pub clear_color: [f32; 4],
+    pub ambient_color: [f32; 3],
+    pub ambient_brightness: f32,
+    pub fog: FogSettings,
+}
+
+// Linear distance fog on every Camera3d, `start` and `end` in world units
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct FogSettings {
+    pub enabled: bool,
+    pub color: [f32; 4],
+    pub start: f32,
+    pub end: f32,
+}
+
+impl Default for SceneSettings {
+    fn default() -> Self {
+        Self {
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            ambient_color: [1.0, 1.0, 1.0],
+            ambient_brightness: 80.0,
+            fog: FogSettings::default(),
+        }
+    }
+}
+
+impl Default for FogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: [0.5, 0.5, 0.5, 1.0],
+            start: 5.0,
+            end: 20.0,
+        }
+    }
+}
+
+impl FogSettings {
+    fn distance_fog(&self) -> DistanceFog {
+        let [r, g, b, a] = self.color;
+        DistanceFog {
+            color: Color::srgba(r, g, b, a),
+            falloff: FogFalloff::Linear {
+                start: self.start,
+                end: self.end,
+            },
+            ..default()
+        }
+    }
+}
+
+// Owns ClearColor, AmbientLight and camera fog; edit SceneSettings instead of those
+pub struct EnvironmentPlugin;
+
+impl Plugin for EnvironmentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SceneSettings>()
+            .add_systems(PostUpdate, apply_scene_settings);
+    }
+}
+
+// Writes every setting in the same system so the frontend never sees a frame
+// with only part of an update applied
+fn apply_scene_settings(
+    mut commands: Commands,
+    settings: Res<SceneSettings>,
+    mut clear_color: ResMut<ClearColor>,
+    mut ambient_light: ResMut<AmbientLight>,
+    cameras: Query<(Entity, Ref<Camera3d>)>,
+) {
+    if settings.is_changed() {
+        let [r, g, b, a] = settings.clear_color;
+        clear_color.0 = Color::srgba(r, g, b, a);
+        let [r, g, b] = settings.ambient_color;
+        ambient_light.color = Color::srgb(r, g, b);
+        ambient_light.brightness = settings.ambient_brightness.max(0.0);
+    }
+
+    for (entity, camera_3d) in cameras.iter() {
+        if !settings.is_changed() && !camera_3d.is_added() {
+            continue;
+        }
+
+        if settings.fog.enabled {
+            commands.entity(entity).insert(settings.fog.distance_fog());
+        } else {
+            commands.entity(entity).remove::<DistanceFog>();
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_scene_settings(
+    bridge: State<'_, BevyBridge>,
+) -> Result<SceneSettings, CommandError> {
+    bridge
+        .run(|world| world.resource::<SceneSettings>().clone())
+        .await
+}
+
+#[tauri::command]
+pub async fn set_scene_settings(
+    bridge: State<'_, BevyBridge>,
+    settings: SceneSettings,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            world.insert_resource(settings);
+        })
+        .await
+}
+
+// Changes only the fog of SceneSettings, on every Camera3d like the rest of it.
+// Negative distances are clamped to zero, `start` has to stay below `end`.
+#[tauri::command]
+pub async fn set_fog(
+    bridge: State<'_, BevyBridge>,
+    color: [f32; 4],
+    start: f32,
+    end: f32,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    let (start, end) = (start.max(0.0), end.max(0.0));
+    if start >= end {
+        return Err(CommandError::InvalidArgument(format!(
+            "fog start {} has to be less than its end {}",
+            start, end
+        )));
+    }
+    bridge
+        .run(move |world| {
+            world.resource_mut::<SceneSettings>().fog = FogSettings {
+                enabled,
+                color,
+                start,
+                end,
+            };
+        })
+        .await
+}
+
+// Checks that `image` can be sampled as a cubemap, turning six faces stacked
+// vertically in a 2d image (as exported by most tools for skyboxes) into one
+fn prepare_cubemap(image: &mut Image, path: &str) -> Result<(), String> {
+    let descriptor = &image.texture_descriptor;
+    if descriptor.dimension != TextureDimension::D2 {
+        return Err(format!("{} is a {:?} texture, not a cubemap", path, descriptor.dimension));
+    }
+    let filterable = matches!(
+        descriptor.format.sample_type(None, None),
+        Some(TextureSampleType::Float { filterable: true })
+    );
+    if !filterable {
+        return Err(format!("{} has the unfilterable format {:?}", path, descriptor.format));
+    }
+    match descriptor.size.depth_or_array_layers {
+        6 => {}
+        1 if image.height() == image.width() * 6 => image.reinterpret_stacked_2d_as_array(6),
+        layers => {
+            return Err(format!(
+                "{} has {} layers, a cubemap needs 6 or faces stacked vertically",
+                path, layers
+            ))
+        }
+    }
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..default()
+    });
+    Ok(())
+}
+
+// Image based lighting for the main 3d camera from cubemaps in the assets
+// folder, e.g. the ktx2 files made by glTF-IBL-Sampler. `specular` defaults to
+// `diffuse`, which works but gives blurry reflections. Resolves once both maps
+// are loaded and applied.
+#[tauri::command]
+pub async fn set_environment_map(
+    bridge: State<'_, BevyBridge>,
+    diffuse: String,
+    specular: Option<String>,
+    intensity: Option<f32>,
+) -> Result<(), CommandError> {
+    let timeout = Duration::from_millis(assets::DEFAULT_TIMEOUT_MS);
+    let specular = specular.unwrap_or_else(|| diffuse.clone());
+    let diffuse_map = assets::load::<Image>(&bridge, diffuse.clone(), timeout).await?;
+    let specular_map = assets::load::<Image>(&bridge, specular.clone(), timeout).await?;
+    let intensity = intensity.unwrap_or(1000.0).max(0.0);
+
+    bridge
+        .run(move |world| {
+            let mut images = world.resource_mut::<Assets<Image>>();
+            for (handle, path) in [(&diffuse_map, &diffuse), (&specular_map, &specular)] {
+                let image = images
+                    .get_mut(handle)
+                    .ok_or_else(|| format!("{} was unloaded", path))?;
+                prepare_cubemap(image, path)?;
+            }
+            let entity = camera::main_camera(world)?;
+            world.entity_mut(entity).insert(EnvironmentMapLight {
+                diffuse_map,
+                specular_map,
+                intensity,
+                ..default()
+            });
+            Ok(())
+        })
+        .await?
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use bevy::render::renderer::{RenderDevice, RenderInstance};
+use bevy::render::{Render, RenderApp, RenderSet};
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Manager, State};
+use tokio::sync::oneshot;
+
+use crate::bridge::CommandError;
+use crate::tauri_plugin::UPDATES_PAUSED;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "status", rename_all = "kebab-case")]
+pub enum FrameCapture {
+    // RenderDoc captured the next rendered frame, open it from its UI
+    Captured,
+    // not running under RenderDoc; the counts are logged as well
+    NotAvailable {
+        reason: String,
+        stats: Option<CommandStats>,
+    },
+}
+
+// Live wgpu objects on bevy's instance, in place of a capture
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct CommandStats {
+    pub command_buffers: usize,
+    pub render_pipelines: usize,
+    pub compute_pipelines: usize,
+    pub bind_groups: usize,
+    pub buffers: usize,
+    pub textures: usize,
+}
+
+type CaptureReply = oneshot::Sender<FrameCapture>;
+
+// Shared by the `capture_frame` command and the render world
+#[derive(Resource, Clone, Default)]
+pub struct FrameCaptureQueue(Arc<Mutex<Vec<CaptureReply>>>);
+
+// Requests the frame being rendered is captured for
+#[derive(Resource, Default)]
+struct CapturingFrame(Vec<CaptureReply>);
+
+// wgpu starts its RenderDoc integration when bevy creates the device, if the
+// app was launched from RenderDoc or it was injected by then.
+// Register it with TauriPlugin::with_render_plugin.
+pub struct FrameCapturePlugin;
+
+impl Plugin for FrameCapturePlugin {
+    fn build(&self, app: &mut App) {
+        let queue = FrameCaptureQueue::default();
+        app.world()
+            .non_send_resource::<tauri::AppHandle>()
+            .manage(queue.clone());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(queue)
+            .init_resource::<CapturingFrame>()
+            .add_systems(Render, start_capture.in_set(RenderSet::ExtractCommands))
+            .add_systems(Render, stop_capture.in_set(RenderSet::Cleanup));
+    }
+}
+
+// Same libraries wgpu looks for, RenderDoc doesn't run on Apple platforms
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn renderdoc_loaded() -> bool {
+    use libloading::os::unix::{Library, RTLD_NOW};
+    const RTLD_NOLOAD: i32 = 0x4;
+    let name = if cfg!(target_os = "android") {
+        "libVkLayer_GLES_RenderDoc.so"
+    } else {
+        "librenderdoc.so"
+    };
+    // SAFETY: RTLD_NOLOAD only looks up an already loaded library, so no
+    // initializers run
+    unsafe { Library::open(Some(name), RTLD_NOW | RTLD_NOLOAD) }.is_ok()
+}
+
+#[cfg(windows)]
+fn renderdoc_loaded() -> bool {
+    libloading::os::windows::Library::open_already_loaded("renderdoc.dll").is_ok()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn renderdoc_loaded() -> bool {
+    false
+}
+
+fn command_stats(instance: &RenderInstance) -> Option<CommandStats> {
+    let report = instance.generate_report()?;
+    let hub = report.hub_report();
+    Some(CommandStats {
+        command_buffers: hub.command_buffers.num_kept_from_user,
+        render_pipelines: hub.render_pipelines.num_kept_from_user,
+        compute_pipelines: hub.compute_pipelines.num_kept_from_user,
+        bind_groups: hub.bind_groups.num_kept_from_user,
+        buffers: hub.buffers.num_kept_from_user,
+        textures: hub.textures.num_kept_from_user,
+    })
+}
+
+// Runs before the render world does any GPU work for the frame
+fn start_capture(
+    queue: Res<FrameCaptureQueue>,
+    mut capturing: ResMut<CapturingFrame>,
+    device: Res<RenderDevice>,
+    instance: Res<RenderInstance>,
+) {
+    let requests = std::mem::take(&mut *queue.0.lock().unwrap());
+    if requests.is_empty() {
+        return;
+    }
+    if !renderdoc_loaded() {
+        let stats = command_stats(&instance);
+        bevy::log::info!("frame capture not available, live wgpu objects: {:?}", stats);
+        for reply in requests {
+            let _ = reply.send(FrameCapture::NotAvailable {
+                reason: String::from("the app isn't running under RenderDoc"),
+                stats,
+            });
+        }
+        return;
+    }
+    if capturing.0.is_empty() {
+        device.wgpu_device().start_capture();
+    }
+    capturing.0.extend(requests);
+}
+
+// Runs once the frame has been submitted and presented
+fn stop_capture(mut capturing: ResMut<CapturingFrame>, device: Res<RenderDevice>) {
+    if capturing.0.is_empty() {
+        return;
+    }
+    device.wgpu_device().stop_capture();
+    bevy::log::info!("captured a frame");
+    for reply in capturing.0.drain(..) {
+        let _ = reply.send(FrameCapture::Captured);
+    }
+}
+
+// Captures the next rendered frame when the app runs under RenderDoc. Without
+// it, resolves with NotAvailable and counts of the live wgpu objects instead.
+#[tauri::command]
+pub async fn capture_frame(
+    queue: State<'_, FrameCaptureQueue>,
+) -> Result<FrameCapture, CommandError> {
+    if UPDATES_PAUSED.load(Ordering::Relaxed) {
+        return Err(CommandError::NotReady(String::from(
+            "bevy updates are paused",
+        )));
+    }
+    let (reply, rx) = oneshot::channel();
+    queue.0.lock().unwrap().push(reply);
+    match tokio::time::timeout(Duration::from_secs(5), rx).await {
+        Ok(capture) => capture
+            .map_err(|_| CommandError::NotReady(String::from("bevy app dropped the request"))),
+        Err(_) => Err(CommandError::Failed(String::from(
+            "no frame was rendered in time",
+        ))),
+    }
+}
@@ -0,0 +1,479 @@
+use bevy::prelude::*;
+use bevy::render::view::window::prepare_windows;
+use bevy::render::{Render, RenderApp, RenderSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+use crate::bridge::CommandError;
+
+// Waits out the rest of a frame in the runner. `target` is the frame duration
+// the runner paces to, which follows the monitor with refresh rate pacing.
+pub trait FrameLimiter: Send {
+    fn limit(&mut self, frame_start: Instant, target: Duration);
+}
+
+// Sleeps for the remaining time. Cheap on power, but the OS may wake the
+// thread a millisecond or more late.
+#[derive(Default)]
+pub struct SleepLimiter;
+
+impl FrameLimiter for SleepLimiter {
+    fn limit(&mut self, frame_start: Instant, target: Duration) {
+        let elapsed = frame_start.elapsed();
+        if elapsed < target {
+            std::thread::sleep(target - elapsed);
+        }
+    }
+}
+
+// Sleeps until `margin` before the deadline and busy-waits the rest, which
+// keeps a core awake in exchange for much lower pacing jitter
+#[allow(dead_code)]
+pub struct SpinLimiter {
+    pub margin: Duration,
+}
+
+impl Default for SpinLimiter {
+    fn default() -> Self {
+        Self {
+            margin: Duration::from_millis(2),
+        }
+    }
+}
+
+impl FrameLimiter for SpinLimiter {
+    fn limit(&mut self, frame_start: Instant, target: Duration) {
+        let deadline = frame_start + target;
+        if let Some(sleep) = deadline
+            .checked_duration_since(Instant::now())
+            .and_then(|remaining| remaining.checked_sub(self.margin))
+        {
+            std::thread::sleep(sleep);
+        }
+        while Instant::now() < deadline {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+// How long the render world waited for the next surface texture last frame,
+// in microseconds. With Fifo that is where the wait for vsync shows up on
+// Vulkan, DX12 and Metal, present() only queues the frame there.
+#[derive(Resource, Clone, Default)]
+pub struct SurfaceWait(Arc<AtomicU64>);
+
+impl SurfaceWait {
+    fn get(&self) -> Duration {
+        Duration::from_micros(self.0.load(Ordering::Relaxed))
+    }
+}
+
+// Starts the next update as late as it can while still making the next vsync,
+// so the frame shows input sampled just before it goes out. It measures how
+// long acquiring the surface texture blocks and sleeps that much longer at the
+// end of each iteration, on top of the rest of the frame, keeping `margin` of
+// the wait as headroom. A frame that misses its vsync halves the extra sleep.
+// Added with TauriPlugin::with_latency_pacing; without a blocking acquire
+// (Immediate, Mailbox, GL's blocking swap) it sleeps like SleepLimiter.
+pub struct LatencyPacer {
+    wait: SurfaceWait,
+    // the headroom it leaves for frames that take longer than the last one
+    pub margin: Duration,
+    // fraction of the difference between the wait and the margin applied per frame
+    pub gain: f32,
+    delay: Duration,
+    last_start: Option<Instant>,
+    // waits out the frame stretched by the delay
+    limiter: Box<dyn FrameLimiter>,
+}
+
+impl LatencyPacer {
+    pub fn new(wait: SurfaceWait) -> Self {
+        Self {
+            wait,
+            margin: Duration::from_millis(2),
+            gain: 0.25,
+            delay: Duration::ZERO,
+            last_start: None,
+            limiter: Box::new(SleepLimiter),
+        }
+    }
+
+    // Extra sleep for the next frame after one that waited `wait`
+    fn adjust(&mut self, frame_start: Instant, target: Duration) {
+        let interval = self.last_start.map(|last| frame_start - last);
+        self.last_start = Some(frame_start);
+        if interval.is_some_and(|interval| interval > target.mul_f32(1.5)) {
+            // the acquire then waits for the vsync after the missed one, which
+            // would push the delay up instead of down
+            self.delay /= 2;
+            return;
+        }
+        let wait = self.wait.get().as_secs_f32();
+        let error = (wait - self.margin.as_secs_f32()) * self.gain;
+        let delay = (self.delay.as_secs_f32() + error).clamp(0.0, target.as_secs_f32() * 0.9);
+        self.delay = Duration::from_secs_f32(delay);
+    }
+}
+
+impl FrameLimiter for LatencyPacer {
+    fn limit(&mut self, frame_start: Instant, target: Duration) {
+        self.adjust(frame_start, target);
+        self.limiter.limit(frame_start, target + self.delay);
+    }
+}
+
+#[derive(Resource)]
+struct SurfaceWaitStart(Option<Instant>);
+
+// Times prepare_windows, which acquires the surface textures, in the render world
+pub struct SurfaceWaitPlugin(pub SurfaceWait);
+
+impl Plugin for SurfaceWaitPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(self.0.clone())
+            .insert_resource(SurfaceWaitStart(None))
+            .add_systems(
+                Render,
+                (
+                    start_surface_wait.before(prepare_windows),
+                    end_surface_wait.after(prepare_windows),
+                )
+                    .in_set(RenderSet::ManageViews),
+            );
+    }
+}
+
+fn start_surface_wait(mut start: ResMut<SurfaceWaitStart>) {
+    start.0 = Some(Instant::now());
+}
+
+fn end_surface_wait(start: Res<SurfaceWaitStart>, wait: Res<SurfaceWait>) {
+    if let Some(start) = start.0 {
+        let micros = start.elapsed().as_micros() as u64;
+        wait.0.store(micros, Ordering::Relaxed);
+    }
+}
+
+// Extra sleeping for battery powered devices, see TauriPlugin::with_cpu_throttle.
+// It trades responsiveness for power: idle pacing drops the frame rate below
+// the refresh rate, and every millisecond of `min_yield` adds to the time
+// between input and the frame showing it.
+#[derive(Clone, Copy, Debug)]
+pub struct CpuThrottle {
+    // slept every iteration, even when the frame overran its target, so the
+    // main thread never runs updates back to back
+    pub min_yield: Duration,
+    // an update done within this fraction of the frame target counts as idle
+    pub idle_threshold: f32,
+    // idle frames are paced to the target times this, 2.0 halves the rate
+    pub idle_pacing: f32,
+}
+
+impl Default for CpuThrottle {
+    fn default() -> Self {
+        Self {
+            min_yield: Duration::from_millis(1),
+            idle_threshold: 0.25,
+            idle_pacing: 2.0,
+        }
+    }
+}
+
+impl CpuThrottle {
+    // Frame target for an iteration whose update took `work`
+    pub fn frame_target(&self, work: Duration, target: Duration) -> Duration {
+        if work.as_secs_f32() < target.as_secs_f32() * self.idle_threshold {
+            target.mul_f32(self.idle_pacing.max(1.0))
+        } else {
+            target
+        }
+    }
+
+    // Tops the time the frame limiter slept up to `min_yield`
+    pub fn yield_rest(&self, slept: Duration) {
+        if slept < self.min_yield {
+            std::thread::sleep(self.min_yield - slept);
+        }
+    }
+}
+
+// Ends a runner iteration: waits out `target` with the limiter, stretched and
+// topped up by the throttle when there is one
+pub fn end_frame(
+    limiter: &mut dyn FrameLimiter,
+    throttle: Option<CpuThrottle>,
+    frame_start: Instant,
+    target: Duration,
+) {
+    let work = frame_start.elapsed();
+    let target = throttle.map_or(target, |throttle| throttle.frame_target(work, target));
+    limiter.limit(frame_start, target);
+    if let Some(throttle) = throttle {
+        throttle.yield_rest(frame_start.elapsed() - work);
+    }
+}
+
+// Current throttle, shared with `set_cpu_throttle` so it applies from the next
+// runner iteration without a round trip to the world
+#[derive(Clone, Default)]
+pub struct CpuThrottleStore(pub Arc<Mutex<Option<CpuThrottle>>>);
+
+// Replaces the throttle, or turns it off when `min_yield_ms` is None. Missing
+// thresholds keep their defaults.
+#[tauri::command]
+pub fn set_cpu_throttle(
+    store: State<'_, CpuThrottleStore>,
+    min_yield_ms: Option<f32>,
+    idle_threshold: Option<f32>,
+    idle_pacing: Option<f32>,
+) -> Result<(), CommandError> {
+    let throttle = match min_yield_ms {
+        Some(min_yield_ms) => {
+            let defaults = CpuThrottle::default();
+            let throttle = CpuThrottle {
+                min_yield: Duration::from_secs_f32(min_yield_ms.max(0.0) / 1000.0),
+                idle_threshold: idle_threshold.unwrap_or(defaults.idle_threshold),
+                idle_pacing: idle_pacing.unwrap_or(defaults.idle_pacing),
+            };
+            if !(0.0..=1.0).contains(&throttle.idle_threshold) {
+                return Err(CommandError::InvalidArgument(format!(
+                    "idle threshold {} is outside 0..=1",
+                    throttle.idle_threshold
+                )));
+            }
+            if !(1.0..=8.0).contains(&throttle.idle_pacing) {
+                return Err(CommandError::InvalidArgument(format!(
+                    "idle pacing {} is outside 1..=8",
+                    throttle.idle_pacing
+                )));
+            }
+            Some(throttle)
+        }
+        None => None,
+    };
+    *store.0.lock().unwrap() = throttle;
+    Ok(())
+}
+
+// Cap on how often the runner renders and presents a frame, None renders every
+// update. Updates above the cap still run the main schedule, and FixedUpdate
+// keeps its own rate, so only rendering is slowed down.
+#[derive(Clone, Default)]
+pub struct RenderFpsCap(pub Arc<Mutex<Option<f64>>>);
+
+// Applies from the next runner iteration. None removes the cap; a cap above
+// the update rate has no effect.
+#[tauri::command]
+pub fn set_render_fps_cap(
+    cap: State<'_, RenderFpsCap>,
+    fps: Option<f64>,
+) -> Result<(), CommandError> {
+    if let Some(fps) = fps {
+        if !fps.is_finite() || fps <= 0.0 {
+            return Err(CommandError::InvalidArgument(format!(
+                "invalid render fps cap {}",
+                fps
+            )));
+        }
+    }
+    *cap.0.lock().unwrap() = fps;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_render_fps_cap(cap: State<'_, RenderFpsCap>) -> Option<f64> {
+    *cap.0.lock().unwrap()
+}
+
+// Read by the runner every iteration, unlike GameState::Paused these stop
+// the update itself. Without rendering the main schedule keeps running and
+// nothing is presented, without simulation the last state is rendered again
+// and only bridge callbacks run, so commands are still answered.
+pub static RENDER_ENABLED: AtomicBool = AtomicBool::new(true);
+pub static SIMULATION_ENABLED: AtomicBool = AtomicBool::new(true);
+
+#[tauri::command]
+pub fn set_render_enabled(enabled: bool) {
+    RENDER_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[tauri::command]
+pub fn get_render_enabled() -> bool {
+    RENDER_ENABLED.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+pub fn set_simulation_enabled(enabled: bool) {
+    SIMULATION_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[tauri::command]
+pub fn get_simulation_enabled() -> bool {
+    SIMULATION_ENABLED.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Records the targets it was asked to wait for instead of waiting
+    #[derive(Default)]
+    struct RecordingLimiter {
+        calls: Vec<(Instant, Duration)>,
+    }
+
+    impl FrameLimiter for RecordingLimiter {
+        fn limit(&mut self, frame_start: Instant, target: Duration) {
+            self.calls.push((frame_start, target));
+        }
+    }
+
+    #[test]
+    fn end_frame_passes_the_target_to_the_limiter() {
+        let mut limiter = RecordingLimiter::default();
+        let frame_start = Instant::now();
+        let target = Duration::from_millis(16);
+        end_frame(&mut limiter, None, frame_start, target);
+        assert_eq!(limiter.calls, vec![(frame_start, target)]);
+    }
+
+    #[test]
+    fn end_frame_stretches_idle_frames_with_a_throttle() {
+        let mut limiter = RecordingLimiter::default();
+        let throttle = CpuThrottle {
+            min_yield: Duration::ZERO,
+            ..CpuThrottle::default()
+        };
+        let frame_start = Instant::now();
+        let target = Duration::from_secs(1);
+        end_frame(&mut limiter, Some(throttle), frame_start, target);
+        assert_eq!(limiter.calls, vec![(frame_start, Duration::from_secs(2))]);
+    }
+
+    #[test]
+    fn end_frame_keeps_the_target_of_busy_frames() {
+        let mut limiter = RecordingLimiter::default();
+        let throttle = CpuThrottle {
+            min_yield: Duration::ZERO,
+            ..CpuThrottle::default()
+        };
+        let target = Duration::from_millis(4);
+        // the whole target already went to the update
+        let frame_start = Instant::now() - target;
+        end_frame(&mut limiter, Some(throttle), frame_start, target);
+        assert_eq!(limiter.calls, vec![(frame_start, target)]);
+    }
+
+    #[test]
+    fn sleep_limiter_waits_for_the_whole_target() {
+        for millis in [1, 5, 10] {
+            let target = Duration::from_millis(millis);
+            let frame_start = Instant::now();
+            SleepLimiter.limit(frame_start, target);
+            assert!(Instant::now() >= frame_start + target);
+        }
+    }
+
+    #[test]
+    fn sleep_limiter_returns_after_an_overrun() {
+        let target = Duration::from_secs(1);
+        let frame_start = Instant::now() - target * 2;
+        let before = Instant::now();
+        SleepLimiter.limit(frame_start, target);
+        assert!(before.elapsed() < target);
+    }
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    // Runs `adjust` for `frames` frames on time that waited `wait` for the surface
+    fn pace(pacer: &mut LatencyPacer, start: &mut Instant, wait: Duration, frames: u32) {
+        pacer
+            .wait
+            .0
+            .store(wait.as_micros() as u64, Ordering::Relaxed);
+        for _ in 0..frames {
+            *start += FRAME;
+            pacer.adjust(*start, FRAME);
+        }
+    }
+
+    fn assert_delay(pacer: &LatencyPacer, millis: f32) {
+        let delay = pacer.delay.as_secs_f32() * 1000.0;
+        assert!(
+            (delay - millis).abs() < 0.01,
+            "delay {}ms, expected {}ms",
+            delay,
+            millis
+        );
+    }
+
+    #[test]
+    fn latency_pacer_follows_the_surface_wait() {
+        let mut pacer = LatencyPacer::new(SurfaceWait::default());
+        let mut start = Instant::now();
+        // waiting 10ms with a 2ms margin adds a quarter of the 8ms per frame
+        pace(&mut pacer, &mut start, Duration::from_millis(10), 1);
+        assert_delay(&pacer, 2.0);
+        pace(&mut pacer, &mut start, Duration::from_millis(10), 2);
+        assert_delay(&pacer, 6.0);
+        // waiting less than the margin gives the sleep back
+        pace(&mut pacer, &mut start, Duration::ZERO, 2);
+        assert_delay(&pacer, 5.0);
+        // the margin itself keeps it where it is
+        let margin = pacer.margin;
+        pace(&mut pacer, &mut start, margin, 3);
+        assert_delay(&pacer, 5.0);
+    }
+
+    #[test]
+    fn latency_pacer_delay_stays_within_the_frame() {
+        let mut pacer = LatencyPacer::new(SurfaceWait::default());
+        let mut start = Instant::now();
+        pace(&mut pacer, &mut start, Duration::ZERO, 4);
+        assert_eq!(pacer.delay, Duration::ZERO);
+        pace(&mut pacer, &mut start, FRAME * 4, 20);
+        assert_delay(&pacer, FRAME.as_secs_f32() * 900.0);
+    }
+
+    impl FrameLimiter for Arc<Mutex<RecordingLimiter>> {
+        fn limit(&mut self, frame_start: Instant, target: Duration) {
+            self.lock().unwrap().limit(frame_start, target);
+        }
+    }
+
+    #[test]
+    fn latency_pacer_sleeps_the_delay_after_the_frame() {
+        let recorder = Arc::new(Mutex::new(RecordingLimiter::default()));
+        let mut pacer = LatencyPacer::new(SurfaceWait::default());
+        pacer.limiter = Box::new(recorder.clone());
+        let mut start = Instant::now();
+        pace(&mut pacer, &mut start, Duration::from_millis(10), 2);
+        assert_delay(&pacer, 4.0);
+
+        start += FRAME;
+        pacer.limit(start, FRAME);
+        // the wait hasn't changed, so the delay grew by another 2ms
+        let (frame_start, target) = recorder.lock().unwrap().calls[0];
+        assert_eq!(frame_start, start);
+        assert_eq!(target, FRAME + pacer.delay);
+        assert_delay(&pacer, 6.0);
+    }
+
+    #[test]
+    fn latency_pacer_halves_the_delay_after_a_missed_vsync() {
+        let mut pacer = LatencyPacer::new(SurfaceWait::default());
+        let mut start = Instant::now();
+        pace(&mut pacer, &mut start, Duration::from_millis(10), 2);
+        assert_delay(&pacer, 4.0);
+        start += FRAME;
+        pace(&mut pacer, &mut start, Duration::from_millis(10), 1);
+        assert_delay(&pacer, 2.0);
+    }
+}
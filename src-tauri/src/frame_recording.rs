@@ -0,0 +1,336 @@
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use bevy::time::TimeUpdateStrategy;
+use image::{ImageError, ImageFormat, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::io::{BufWriter, ErrorKind, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{Emitter, State};
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::tauri_plugin::UPDATES_PAUSED;
+
+// Where start_recording writes the frames
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordingOutput {
+    // frame_00000.png, frame_00001.png, ... in `directory`, created if missing
+    Png { directory: String },
+    // a video at `path` encoded by ffmpeg, which has to be on the PATH
+    Ffmpeg { path: String },
+}
+
+// Sent as "recording-progress" for every frame written
+#[derive(Serialize, Clone, Copy)]
+struct RecordingProgress {
+    written: u32,
+    frames: u32,
+}
+
+// Sent as "recording-finished" once every captured frame was written, or
+// writing them failed
+#[derive(Serialize, Clone)]
+struct RecordingFinished {
+    written: u32,
+    frames: u32,
+    error: Option<String>,
+}
+
+// The recording in progress
+#[derive(Resource)]
+struct Recording {
+    frames: u32,
+    captured: u32,
+    // dropping it, along with the clones held by screenshots still in flight,
+    // ends the writer thread
+    sender: Sender<(u32, Image)>,
+    // set by the writer thread when it can't write any more
+    failed: Arc<AtomicBool>,
+    // put back when the recording ends
+    time_strategy: TimeUpdateStrategy,
+}
+
+// Captures the primary window after every update while recording, with time
+// advancing exactly one frame per update so animations come out at the
+// recorded frame rate however long a frame takes to render and write
+pub struct FrameRecordingPlugin;
+
+impl Plugin for FrameRecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Last, capture_frame.run_if(resource_exists::<Recording>));
+    }
+}
+
+fn capture_frame(world: &mut World) {
+    let recording = world.resource::<Recording>();
+    if recording.captured >= recording.frames || recording.failed.load(Ordering::Relaxed) {
+        finish(world);
+        return;
+    }
+    let index = recording.captured;
+    let sender = recording.sender.clone();
+    world.spawn(Screenshot::primary_window()).observe(
+        move |trigger: Trigger<ScreenshotCaptured>| {
+            // the writer is gone once it failed
+            let _ = sender.send((index, trigger.event().0.clone()));
+        },
+    );
+    world.resource_mut::<Recording>().captured += 1;
+}
+
+// Stops capturing, the writer thread still writes the frames in flight.
+// Returns how many frames were captured.
+fn finish(world: &mut World) -> Option<u32> {
+    let recording = world.remove_resource::<Recording>()?;
+    world.insert_resource(recording.time_strategy);
+    info!("captured {} frames", recording.captured);
+    Some(recording.captured)
+}
+
+enum FrameWriter {
+    Png(PathBuf),
+    // ffmpeg is started with the size of the first frame
+    Ffmpeg {
+        path: String,
+        fps: u32,
+        encoder: Option<(Child, BufWriter<ChildStdin>, UVec2)>,
+    },
+}
+
+fn out_of_space(err: &std::io::Error) -> bool {
+    err.kind() == ErrorKind::StorageFull
+}
+
+impl FrameWriter {
+    fn write(&mut self, index: u32, frame: &RgbImage) -> Result<(), String> {
+        match self {
+            FrameWriter::Png(directory) => {
+                let path = directory.join(format!("frame_{:05}.png", index));
+                let result = std::fs::File::create(&path)
+                    .map_err(ImageError::IoError)
+                    .and_then(|file| {
+                        let mut file = BufWriter::new(file);
+                        frame.write_to(&mut file, ImageFormat::Png)?;
+                        file.flush().map_err(ImageError::IoError)
+                    });
+                result.map_err(|err| {
+                    // a truncated frame is worse than a missing one
+                    let _ = std::fs::remove_file(&path);
+                    match err {
+                        ImageError::IoError(err) if out_of_space(&err) => {
+                            format!("out of disk space writing {}", path.display())
+                        }
+                        err => format!("failed to write {}: {}", path.display(), err),
+                    }
+                })
+            }
+            FrameWriter::Ffmpeg { path, fps, encoder } => {
+                let size = UVec2::new(frame.width(), frame.height());
+                if encoder.is_none() {
+                    *encoder = Some(spawn_ffmpeg(path, *fps, size)?);
+                }
+                let (_, stdin, encoder_size) = encoder.as_mut().unwrap();
+                if size != *encoder_size {
+                    return Err(format!(
+                        "the window was resized from {} to {} while recording",
+                        encoder_size, size
+                    ));
+                }
+                stdin.write_all(frame.as_raw()).map_err(|err| {
+                    if out_of_space(&err) {
+                        format!("out of disk space writing {}", path)
+                    } else {
+                        // ffmpeg stopped reading, its exit status says why
+                        format!("ffmpeg stopped encoding: {}", err)
+                    }
+                })
+            }
+        }
+    }
+
+    // Waits for ffmpeg to write the end of the video
+    fn close(self) -> Result<(), String> {
+        let FrameWriter::Ffmpeg {
+            encoder: Some((mut child, stdin, _)),
+            ..
+        } = self
+        else {
+            return Ok(());
+        };
+        let flushed = stdin.into_inner().map(drop);
+        let status = child
+            .wait()
+            .map_err(|err| format!("failed to wait for ffmpeg: {}", err))?;
+        if !status.success() {
+            return Err(format!("ffmpeg exited with {}", status));
+        }
+        flushed.map_err(|err| format!("ffmpeg stopped encoding: {}", err.error()))
+    }
+}
+
+fn spawn_ffmpeg(
+    path: &str,
+    fps: u32,
+    size: UVec2,
+) -> Result<(Child, BufWriter<ChildStdin>, UVec2), String> {
+    let mut child = Command::new("ffmpeg")
+        .args([
+            "-loglevel",
+            "error",
+            "-y",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgb24",
+        ])
+        .args(["-s", &format!("{}x{}", size.x, size.y)])
+        .args(["-framerate", &fps.to_string(), "-i", "-"])
+        // the pixel format players support, which needs an even size
+        .args([
+            "-vf",
+            "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+            "-pix_fmt",
+            "yuv420p",
+        ])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to start ffmpeg: {}", err))?;
+    let stdin = BufWriter::new(child.stdin.take().unwrap());
+    Ok((child, stdin, size))
+}
+
+// Writes frames in the order they were captured until the recording ends or a
+// write fails, off the main thread so encoding doesn't hold up updates
+fn write_frames(
+    app: tauri::AppHandle,
+    mut writer: FrameWriter,
+    receiver: Receiver<(u32, Image)>,
+    frames: u32,
+    failed: Arc<AtomicBool>,
+) {
+    let mut written = 0;
+    let mut error = None;
+    for (index, image) in receiver {
+        if error.is_some() {
+            continue;
+        }
+        let result = image
+            .try_into_dynamic()
+            .map_err(|err| format!("unsupported frame: {}", err))
+            // the alpha channel holds brightness with HDR, not transparency
+            .and_then(|image| writer.write(index, &image.to_rgb8()));
+        match result {
+            Ok(()) => {
+                written += 1;
+                let _ = app.emit("recording-progress", RecordingProgress { written, frames });
+            }
+            Err(err) => {
+                warn!("recording stopped: {}", err);
+                failed.store(true, Ordering::Relaxed);
+                error = Some(err);
+            }
+        }
+    }
+    if let Err(err) = writer.close() {
+        warn!("recording failed: {}", err);
+        error.get_or_insert(err);
+    }
+    info!("wrote {} of {} frames", written, frames);
+    let finished = RecordingFinished {
+        written,
+        frames,
+        error,
+    };
+    if let Err(err) = app.emit("recording-finished", finished) {
+        warn!("failed to emit recording-finished: {}", err);
+    }
+}
+
+// Records the next `frames` updates at `fps`. Resolves once recording started;
+// "recording-progress" events follow each frame written and
+// "recording-finished" the last one, with the error that ended it early
+// (e.g. running out of disk space).
+#[tauri::command]
+pub async fn start_recording(
+    app: tauri::AppHandle,
+    bridge: State<'_, BevyBridge>,
+    fps: u32,
+    frames: u32,
+    output: RecordingOutput,
+) -> Result<(), CommandError> {
+    if !(1..=240).contains(&fps) {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid frame rate {}",
+            fps
+        )));
+    }
+    if frames == 0 {
+        return Err(CommandError::InvalidArgument(String::from(
+            "nothing to record, frames is 0",
+        )));
+    }
+    if UPDATES_PAUSED.load(Ordering::Relaxed) {
+        return Err(CommandError::NotReady(String::from(
+            "bevy updates are paused",
+        )));
+    }
+    let writer = match output {
+        RecordingOutput::Png { directory } => {
+            let directory = PathBuf::from(directory);
+            std::fs::create_dir_all(&directory)
+                .map_err(|err| format!("failed to create {}: {}", directory.display(), err))?;
+            FrameWriter::Png(directory)
+        }
+        RecordingOutput::Ffmpeg { path } => FrameWriter::Ffmpeg {
+            path,
+            fps,
+            encoder: None,
+        },
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let failed = Arc::new(AtomicBool::new(false));
+    bridge
+        .run({
+            let failed = failed.clone();
+            move |world| {
+                if world.contains_resource::<Recording>() {
+                    return Err(CommandError::Failed(String::from("already recording")));
+                }
+                let frame = Duration::from_secs_f64(1.0 / fps as f64);
+                let time_strategy = std::mem::replace(
+                    &mut *world.resource_mut::<TimeUpdateStrategy>(),
+                    TimeUpdateStrategy::ManualDuration(frame),
+                );
+                world.insert_resource(Recording {
+                    frames,
+                    captured: 0,
+                    sender,
+                    failed,
+                    time_strategy,
+                });
+                info!("recording {} frames at {} fps", frames, fps);
+                Ok(())
+            }
+        })
+        .await??;
+    std::thread::spawn(move || write_frames(app, writer, receiver, frames, failed));
+    Ok(())
+}
+
+// Ends the recording early, returns how many frames were captured. Those still
+// being written are followed by "recording-finished" as usual.
+#[tauri::command]
+pub async fn stop_recording(bridge: State<'_, BevyBridge>) -> Result<u32, CommandError> {
+    let captured = bridge
+        .run(finish)
+        .await?
+        .ok_or_else(|| String::from("not recording"))?;
+    Ok(captured)
+}
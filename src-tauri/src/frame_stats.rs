@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{RunEvent, WindowEvent};
+
+// Presented frames per window label. Each counter rolls over on its own
+// second, so it works whether the bevy runner or tauri's own loop drives it.
+static WINDOW_FRAMES: LazyLock<Mutex<HashMap<String, FrameCounter>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+struct FrameCounter {
+    frames: usize,
+    since: Instant,
+    fps: usize,
+}
+
+impl FrameCounter {
+    // a window that stopped presenting, e.g. while minimized, reports 0
+    fn current_fps(&self) -> usize {
+        if self.since.elapsed() < Duration::from_secs(2) {
+            self.fps
+        } else {
+            0
+        }
+    }
+}
+
+// Call once a frame has been presented to the window with `label`
+pub fn record_present(label: &str) {
+    let mut windows = WINDOW_FRAMES.lock().unwrap();
+    let counter = windows
+        .entry(label.to_string())
+        .or_insert_with(|| FrameCounter {
+            frames: 0,
+            since: Instant::now(),
+            fps: 0,
+        });
+    counter.frames += 1;
+    if counter.since.elapsed() >= Duration::from_secs(1) {
+        counter.fps = counter.frames;
+        counter.frames = 0;
+        counter.since = Instant::now();
+    }
+}
+
+// Drops the counters of destroyed windows
+pub fn handle_window_destroyed(event: &RunEvent) {
+    if let RunEvent::WindowEvent {
+        label,
+        event: WindowEvent::Destroyed,
+        ..
+    } = event
+    {
+        WINDOW_FRAMES.lock().unwrap().remove(label);
+    }
+}
+
+// Frames presented last second to the window with `label`, or to all windows
+// together without one
+#[tauri::command]
+pub fn get_average_frame_rate(label: Option<String>) -> usize {
+    let windows = WINDOW_FRAMES.lock().unwrap();
+    match label {
+        Some(label) => windows.get(&label).map_or(0, FrameCounter::current_fps),
+        None => windows.values().map(FrameCounter::current_fps).sum(),
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use std::sync::atomic::Ordering;
+use tauri::State;
+use tokio::sync::oneshot;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::tauri_plugin::UPDATES_PAUSED;
+
+struct FrameWaiter {
+    remaining: u32,
+    block_while_paused: bool,
+    reply: oneshot::Sender<Result<(), CommandError>>,
+}
+
+// Pending `wait_for_frame` calls, counted down once per update
+#[derive(Resource, Default)]
+pub struct FrameWaiters(Vec<FrameWaiter>);
+
+const PAUSED_ERROR: &str = "bevy updates are paused";
+
+// Runs in Last, so waiters resolve once every other system of the update is done
+pub fn notify_frame_waiters(mut waiters: ResMut<FrameWaiters>) {
+    let (done, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut waiters.0)
+        .into_iter()
+        .map(|mut waiter| {
+            waiter.remaining -= 1;
+            waiter
+        })
+        .partition(|waiter| waiter.remaining == 0);
+    waiters.0 = pending;
+
+    for waiter in done {
+        let _ = waiter.reply.send(Ok(()));
+    }
+}
+
+// Called by the runner while updates are paused or the simulation is disabled
+pub fn fail_paused_frame_waiters(world: &mut World) {
+    let mut waiters = world.resource_mut::<FrameWaiters>();
+    let (blocking, failed): (Vec<_>, Vec<_>) = std::mem::take(&mut waiters.0)
+        .into_iter()
+        .partition(|waiter| waiter.block_while_paused);
+    waiters.0 = blocking;
+
+    for waiter in failed {
+        let _ = waiter
+            .reply
+            .send(Err(CommandError::NotReady(String::from(PAUSED_ERROR))));
+    }
+}
+
+// Waits for `frames` more updates, failing while they are paused unless
+// `block_while_paused`
+pub fn add_frame_waiter(
+    world: &mut World,
+    frames: u32,
+    block_while_paused: bool,
+) -> oneshot::Receiver<Result<(), CommandError>> {
+    let (reply, rx) = oneshot::channel();
+    world.resource_mut::<FrameWaiters>().0.push(FrameWaiter {
+        remaining: frames.max(1),
+        block_while_paused,
+        reply,
+    });
+    rx
+}
+
+// Resolves after `frames` (default 1) more bevy updates have completed. Updates
+// stop while every window is minimized or hidden, or set_simulation_enabled
+// turned them off; by default that is an error, with `block_while_paused` the
+// call waits until updates resume instead.
+#[tauri::command]
+pub async fn wait_for_frame(
+    bridge: State<'_, BevyBridge>,
+    frames: Option<u32>,
+    block_while_paused: Option<bool>,
+) -> Result<(), CommandError> {
+    let frames = frames.unwrap_or(1);
+    let block_while_paused = block_while_paused.unwrap_or(false);
+    if !block_while_paused && UPDATES_PAUSED.load(Ordering::Relaxed) {
+        return Err(CommandError::NotReady(String::from(PAUSED_ERROR)));
+    }
+
+    let rx = bridge
+        .run(move |world| add_frame_waiter(world, frames, block_while_paused))
+        .await?;
+    rx.await
+        .map_err(|_| CommandError::NotReady(String::from("bevy app dropped the request")))?
+}
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+
+// Example screens the webview can switch between with `set_game_state`
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GameState {
+    #[default]
+    Menu,
+    Playing,
+    Paused,
+}
+
+impl GameState {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "menu" => Some(Self::Menu),
+            "playing" => Some(Self::Playing),
+            "paused" => Some(Self::Paused),
+            _ => None,
+        }
+    }
+}
+
+// Needs StatesPlugin, which has to be added before this one
+pub struct GameStatePlugin;
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .add_systems(OnEnter(GameState::Paused), pause_time)
+            .add_systems(OnExit(GameState::Paused), unpause_time)
+            .add_systems(Update, log_transitions);
+    }
+}
+
+// Stops animations and everything else driven by virtual time
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unpause_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn log_transitions(mut transitions: EventReader<StateTransitionEvent<GameState>>) {
+    for transition in transitions.read() {
+        info!(
+            "game state {:?} -> {:?}",
+            transition.exited, transition.entered
+        );
+    }
+}
+
+// Resolves once the next state is queued, the transition itself runs right
+// after PreUpdate of the same update
+#[tauri::command]
+pub async fn set_game_state(
+    bridge: State<'_, BevyBridge>,
+    name: String,
+) -> Result<(), CommandError> {
+    let state = GameState::from_name(&name)
+        .ok_or_else(|| CommandError::InvalidArgument(format!("unknown game state {}", name)))?;
+    bridge
+        .run(move |world| {
+            world.resource_mut::<NextState<GameState>>().set(state);
+        })
+        .await
+}
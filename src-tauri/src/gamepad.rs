@@ -0,0 +1,70 @@
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::prelude::*;
+use serde::Serialize;
+use tauri::{Emitter, State};
+
+use crate::bridge::{BevyBridge, CommandError};
+
+#[derive(Serialize, Clone)]
+pub struct GamepadPayload {
+    id: u64,
+    name: Option<String>,
+}
+
+// Emits `gamepad-connected` and `gamepad-disconnected`. Pads already plugged in
+// at startup are reported before the webview is likely listening, so the
+// frontend should ask for them with `get_gamepads` once it is.
+//
+// Needs the tauri::AppHandle inserted by TauriPlugin, so add it after that one
+pub struct GamepadEventsPlugin;
+
+impl Plugin for GamepadEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, report_gamepad_connections);
+    }
+}
+
+fn report_gamepad_connections(
+    mut connections: EventReader<GamepadConnectionEvent>,
+    names: Query<&Name>,
+    tauri_app: NonSend<tauri::AppHandle>,
+) {
+    for connection in connections.read() {
+        let (event, name) = match &connection.connection {
+            GamepadConnection::Connected { name, .. } => ("gamepad-connected", Some(name.clone())),
+            // the entity and its Name outlive the disconnect
+            GamepadConnection::Disconnected => (
+                "gamepad-disconnected",
+                names
+                    .get(connection.gamepad)
+                    .ok()
+                    .map(|name| name.to_string()),
+            ),
+        };
+        let payload = GamepadPayload {
+            id: connection.gamepad.to_bits(),
+            name,
+        };
+        if let Err(err) = tauri_app.emit(event, payload) {
+            warn!("failed to emit {}: {}", event, err);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_gamepads(
+    bridge: State<'_, BevyBridge>,
+) -> Result<Vec<GamepadPayload>, CommandError> {
+    bridge
+        .run(|world| {
+            world
+                .query_filtered::<(Entity, Option<&Name>), With<Gamepad>>()
+                .iter(world)
+                .map(|(entity, name)| GamepadPayload {
+                    id: entity.to_bits(),
+                    name: name.map(|name| name.to_string()),
+                })
+                .collect()
+        })
+        .await
+}
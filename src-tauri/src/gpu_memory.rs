@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use bevy::render::renderer::{RenderDevice, RenderInstance};
+use serde::Serialize;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+
+// GPU memory as far as wgpu can tell. Fields are None where the backend
+// doesn't report them; the allocator numbers currently only come from DX12.
+#[derive(Serialize, Clone, Copy, Default, Debug)]
+pub struct GpuMemoryStats {
+    // memory used by live allocations
+    pub allocated_bytes: Option<u64>,
+    // memory reserved from the driver, including unused parts of blocks
+    pub reserved_bytes: Option<u64>,
+    pub allocations: Option<usize>,
+    pub memory_blocks: Option<usize>,
+    // wgpu handles alive on bevy's instance
+    pub buffers: Option<usize>,
+    pub textures: Option<usize>,
+    pub texture_views: Option<usize>,
+    // estimate from the descriptors of the image assets, base mip level only
+    pub image_bytes: u64,
+}
+
+fn image_bytes(images: &Assets<Image>) -> u64 {
+    images
+        .iter()
+        .map(|(_, image)| {
+            let descriptor = &image.texture_descriptor;
+            let (block_width, block_height) = descriptor.format.block_dimensions();
+            let block_size = descriptor.format.block_copy_size(None).unwrap_or(0);
+            let size = descriptor.size;
+            size.width.div_ceil(block_width) as u64
+                * size.height.div_ceil(block_height) as u64
+                * size.depth_or_array_layers as u64
+                * block_size as u64
+        })
+        .sum()
+}
+
+#[tauri::command]
+pub async fn get_gpu_memory(bridge: State<'_, BevyBridge>) -> Result<GpuMemoryStats, CommandError> {
+    bridge
+        .run(|world| {
+            let device = world
+                .get_resource::<RenderDevice>()
+                .ok_or_else(|| String::from("the renderer isn't initialized"))?;
+            let mut stats = GpuMemoryStats::default();
+            if let Some(report) = device.wgpu_device().generate_allocator_report() {
+                stats.allocated_bytes = Some(report.total_allocated_bytes);
+                stats.reserved_bytes = Some(report.total_reserved_bytes);
+                stats.allocations = Some(report.allocations.len());
+                stats.memory_blocks = Some(report.blocks.len());
+            }
+            if let Some(report) = world
+                .get_resource::<RenderInstance>()
+                .and_then(|instance| instance.generate_report())
+            {
+                let hub = report.hub_report();
+                stats.buffers = Some(hub.buffers.num_kept_from_user);
+                stats.textures = Some(hub.textures.num_kept_from_user);
+                stats.texture_views = Some(hub.texture_views.num_kept_from_user);
+            }
+            if let Some(images) = world.get_resource::<Assets<Image>>() {
+                stats.image_bytes = image_bytes(images);
+            }
+            Ok(stats)
+        })
+        .await?
+}
@@ -0,0 +1,202 @@
+// the ShaderType derive emits a `check` fn that newer rustc reports as unused
+#![allow(dead_code)]
+
+use bevy::asset::load_internal_asset;
+use bevy::core_pipeline::core_3d::graph::{Core3d, Node3d};
+use bevy::core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
+use bevy::ecs::query::QueryItem;
+use bevy::image::BevyDefault;
+use bevy::prelude::*;
+use bevy::render::extract_component::{
+    ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+    UniformComponentPlugin,
+};
+use bevy::render::render_graph::{
+    NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+};
+use bevy::render::render_resource::binding_types::{sampler, texture_2d, uniform_buffer};
+use bevy::render::render_resource::*;
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::view::ViewTarget;
+use bevy::render::RenderApp;
+
+// Example post-processing pass for TauriPlugin::with_render_plugin. Cameras
+// with a Grayscale component are desaturated after tonemapping.
+pub struct GrayscalePlugin;
+
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct Grayscale {
+    // 0 keeps the original colors, 1 is fully gray
+    pub strength: f32,
+}
+
+impl Default for Grayscale {
+    fn default() -> Self {
+        Self { strength: 1.0 }
+    }
+}
+
+const GRAYSCALE_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x5e1b_6a3c_8d2f_4b71_9a0e_c4d7_2f86_1b39);
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct GrayscaleLabel;
+
+impl Plugin for GrayscalePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            GRAYSCALE_SHADER_HANDLE,
+            "grayscale.wgsl",
+            Shader::from_wgsl
+        );
+        app.add_plugins((
+            ExtractComponentPlugin::<Grayscale>::default(),
+            UniformComponentPlugin::<Grayscale>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<GrayscaleNode>>(Core3d, GrayscaleLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::Tonemapping,
+                    GrayscaleLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<GrayscalePipeline>();
+    }
+}
+
+#[derive(Default)]
+struct GrayscaleNode;
+
+impl ViewNode for GrayscaleNode {
+    type ViewQuery = (&'static ViewTarget, &'static DynamicUniformIndex<Grayscale>);
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, settings_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let grayscale_pipeline = world.resource::<GrayscalePipeline>();
+        let pipeline_id = if view_target.is_hdr() {
+            grayscale_pipeline.hdr_pipeline
+        } else {
+            grayscale_pipeline.sdr_pipeline
+        };
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(pipeline_id)
+        else {
+            return Ok(());
+        };
+        let Some(settings_binding) = world
+            .resource::<ComponentUniforms<Grayscale>>()
+            .uniforms()
+            .binding()
+        else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "grayscale_bind_group",
+            &grayscale_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &grayscale_pipeline.sampler,
+                settings_binding,
+            )),
+        );
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("grayscale_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
+
+// The pass runs before upscaling, so it writes the camera's main texture
+// format: Rgba16Float for HDR cameras, the sRGB default otherwise
+#[derive(Resource)]
+struct GrayscalePipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    sdr_pipeline: CachedRenderPipelineId,
+    hdr_pipeline: CachedRenderPipelineId,
+}
+
+impl FromWorld for GrayscalePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(
+            "grayscale_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<Grayscale>(true),
+                ),
+            ),
+        );
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let queue = |format: TextureFormat| {
+            pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                label: Some("grayscale_pipeline".into()),
+                layout: vec![layout.clone()],
+                vertex: fullscreen_shader_vertex_state(),
+                fragment: Some(FragmentState {
+                    shader: GRAYSCALE_SHADER_HANDLE,
+                    shader_defs: vec![],
+                    entry_point: "fragment".into(),
+                    targets: vec![Some(ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                push_constant_ranges: vec![],
+                zero_initialize_workgroup_memory: false,
+            })
+        };
+        let sdr_pipeline = queue(TextureFormat::bevy_default());
+        let hdr_pipeline = queue(ViewTarget::TEXTURE_FORMAT_HDR);
+
+        Self {
+            layout,
+            sampler,
+            sdr_pipeline,
+            hdr_pipeline,
+        }
+    }
+}
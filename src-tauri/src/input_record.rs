@@ -0,0 +1,260 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::mouse::{MouseButtonInput, MouseScrollUnit, MouseWheel};
+use bevy::input::touch::{ForceTouch, TouchInput, TouchPhase};
+use bevy::input::{ButtonState, InputSystem};
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::Duration;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+
+// An input event without its window, replay sends it to the primary window
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordedInput {
+    Keyboard {
+        key_code: KeyCode,
+        logical_key: Key,
+        state: ButtonState,
+        repeat: bool,
+    },
+    MouseButton {
+        button: MouseButton,
+        state: ButtonState,
+    },
+    CursorMoved {
+        position: Vec2,
+        delta: Option<Vec2>,
+    },
+    MouseWheel {
+        unit: MouseScrollUnit,
+        x: f32,
+        y: f32,
+    },
+    Touch {
+        phase: TouchPhase,
+        position: Vec2,
+        force: Option<ForceTouch>,
+        id: u64,
+    },
+}
+
+// One bevy update: how long it took in seconds and the input sent during it
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct RecordedFrame {
+    pub delta: f64,
+    pub events: Vec<RecordedInput>,
+}
+
+// The JSON written by stop_input_recording and read by --replay
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct InputRecording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        serde_json::from_str(&text)
+            .map_err(|err| format!("invalid input recording {}: {}", path.display(), err))
+    }
+}
+
+// The recording in progress, None while not recording
+#[derive(Resource, Default)]
+struct InputRecorder(Option<InputRecording>);
+
+// Frames left to replay, inserted for --replay
+#[derive(Resource)]
+pub struct InputReplay(VecDeque<RecordedFrame>);
+
+impl InputReplay {
+    pub fn new(recording: InputRecording) -> Self {
+        Self(recording.frames.into())
+    }
+}
+
+// Records the keyboard, mouse and touch events bevy receives, with the length
+// of every frame. Replaying feeds them back on the same frames and steps time
+// by the recorded frame lengths, so FixedUpdate runs the same number of steps
+// with the same input. Live input still arrives during a replay.
+pub struct InputRecordPlugin;
+
+impl Plugin for InputRecordPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputRecorder>()
+            .add_systems(Startup, start_replay.run_if(resource_exists::<InputReplay>))
+            .add_systems(
+                PreUpdate,
+                replay_input
+                    .before(InputSystem)
+                    .run_if(resource_exists::<InputReplay>),
+            )
+            .add_systems(Last, record_input.run_if(is_recording));
+    }
+}
+
+fn is_recording(recorder: Res<InputRecorder>) -> bool {
+    recorder.0.is_some()
+}
+
+fn record_input(
+    mut recorder: ResMut<InputRecorder>,
+    time: Res<Time<Real>>,
+    mut keyboard: EventReader<KeyboardInput>,
+    mut mouse_buttons: EventReader<MouseButtonInput>,
+    mut cursor: EventReader<CursorMoved>,
+    mut wheel: EventReader<MouseWheel>,
+    mut touches: EventReader<TouchInput>,
+) {
+    let mut events = Vec::new();
+    events.extend(keyboard.read().map(|event| RecordedInput::Keyboard {
+        key_code: event.key_code,
+        logical_key: event.logical_key.clone(),
+        state: event.state,
+        repeat: event.repeat,
+    }));
+    events.extend(mouse_buttons.read().map(|event| RecordedInput::MouseButton {
+        button: event.button,
+        state: event.state,
+    }));
+    events.extend(cursor.read().map(|event| RecordedInput::CursorMoved {
+        position: event.position,
+        delta: event.delta,
+    }));
+    events.extend(wheel.read().map(|event| RecordedInput::MouseWheel {
+        unit: event.unit,
+        x: event.x,
+        y: event.y,
+    }));
+    events.extend(touches.read().map(|event| RecordedInput::Touch {
+        phase: event.phase,
+        position: event.position,
+        force: event.force,
+        id: event.id,
+    }));
+    if let Some(recording) = &mut recorder.0 {
+        recording.frames.push(RecordedFrame {
+            delta: time.delta_secs_f64(),
+            events,
+        });
+    }
+}
+
+fn start_replay(replay: Res<InputReplay>, mut strategy: ResMut<TimeUpdateStrategy>) {
+    info!("replaying {} frames of input", replay.0.len());
+    if let Some(frame) = replay.0.front() {
+        *strategy = TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(frame.delta));
+    }
+}
+
+// Time was already advanced by this frame's recorded length in First, so this
+// sends its events and sets up the length of the next one
+#[allow(clippy::too_many_arguments)]
+fn replay_input(
+    mut commands: Commands,
+    mut replay: ResMut<InputReplay>,
+    mut strategy: ResMut<TimeUpdateStrategy>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut keyboard: EventWriter<KeyboardInput>,
+    mut mouse_buttons: EventWriter<MouseButtonInput>,
+    mut cursor: EventWriter<CursorMoved>,
+    mut wheel: EventWriter<MouseWheel>,
+    mut touches: EventWriter<TouchInput>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let Some(frame) = replay.0.pop_front() else {
+        info!("input replay finished");
+        *strategy = TimeUpdateStrategy::Automatic;
+        commands.remove_resource::<InputReplay>();
+        return;
+    };
+    for event in frame.events {
+        match event {
+            RecordedInput::Keyboard {
+                key_code,
+                logical_key,
+                state,
+                repeat,
+            } => {
+                keyboard.send(KeyboardInput {
+                    key_code,
+                    logical_key,
+                    state,
+                    repeat,
+                    window,
+                });
+            }
+            RecordedInput::MouseButton { button, state } => {
+                mouse_buttons.send(MouseButtonInput {
+                    button,
+                    state,
+                    window,
+                });
+            }
+            RecordedInput::CursorMoved { position, delta } => {
+                cursor.send(CursorMoved {
+                    window,
+                    position,
+                    delta,
+                });
+            }
+            RecordedInput::MouseWheel { unit, x, y } => {
+                wheel.send(MouseWheel { unit, x, y, window });
+            }
+            RecordedInput::Touch {
+                phase,
+                position,
+                force,
+                id,
+            } => {
+                touches.send(TouchInput {
+                    phase,
+                    position,
+                    window,
+                    force,
+                    id,
+                });
+            }
+        }
+    }
+    if let Some(next) = replay.0.front() {
+        *strategy = TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(next.delta));
+    }
+}
+
+// Off until this is called. Restarting drops the frames recorded so far.
+#[tauri::command]
+pub async fn start_input_recording(bridge: State<'_, BevyBridge>) -> Result<(), CommandError> {
+    bridge
+        .run(|world| {
+            world.resource_mut::<InputRecorder>().0 = Some(InputRecording::default());
+            info!("recording input");
+        })
+        .await
+}
+
+// Writes the recording to `path` as JSON for --replay, returns how many frames
+// it holds
+#[tauri::command]
+pub async fn stop_input_recording(
+    bridge: State<'_, BevyBridge>,
+    path: String,
+) -> Result<usize, CommandError> {
+    let recording = bridge
+        .run(|world| world.resource_mut::<InputRecorder>().0.take())
+        .await?
+        .ok_or_else(|| String::from("input isn't being recorded"))?;
+    let json = serde_json::to_string(&recording).map_err(|err| err.to_string())?;
+    std::fs::write(&path, json).map_err(|err| format!("failed to write {}: {}", path, err))?;
+    info!("wrote {} frames of input to {}", recording.frames.len(), path);
+    Ok(recording.frames.len())
+}
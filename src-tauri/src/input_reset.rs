@@ -0,0 +1,133 @@
+use bevy::input::keyboard::KeyboardFocusLost;
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+use bitflags::bitflags;
+
+bitflags! {
+    // Window events after which the runner releases everything held in bevy's
+    // input resources, all of them by default. Inputs are forwarded by hand
+    // rather than by bevy_winit, so a release that happens while the window
+    // doesn't receive events never arrives and the key stays pressed.
+    #[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct InputResetTriggers: u8 {
+        const FOCUS_LOST = 1;
+        const MINIMIZED = 1 << 1;
+        // every window minimized or hidden, see WindowOcclusion
+        const OCCLUDED = 1 << 2;
+    }
+}
+
+impl Default for InputResetTriggers {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+// Releases all keys, mouse buttons and touches if `trigger` is enabled
+pub fn reset_input_on(world: &mut World, trigger: InputResetTriggers) {
+    let enabled = world
+        .get_resource::<InputResetTriggers>()
+        .copied()
+        .unwrap_or_default();
+    if enabled.contains(trigger) {
+        reset_input(world);
+    }
+}
+
+pub fn reset_input(world: &mut World) {
+    // also makes bevy's keyboard_input_system release ButtonInput<Key>
+    world.send_event(KeyboardFocusLost);
+    if let Some(mut keys) = world.get_resource_mut::<ButtonInput<KeyCode>>() {
+        keys.release_all();
+    }
+    if let Some(mut buttons) = world.get_resource_mut::<ButtonInput<MouseButton>>() {
+        buttons.release_all();
+    }
+    if let Some(mut touches) = world.get_resource_mut::<Touches>() {
+        touches.release_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::input::touch::{touch_screen_input_system, TouchInput, TouchPhase};
+
+    fn world_with_pressed_input(enabled: InputResetTriggers) -> World {
+        let mut world = World::new();
+        world.init_resource::<Events<KeyboardFocusLost>>();
+        world.insert_resource(enabled);
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::KeyW);
+        world.insert_resource(keys);
+        let mut buttons = ButtonInput::<MouseButton>::default();
+        buttons.press(MouseButton::Left);
+        world.insert_resource(buttons);
+        world.init_resource::<Touches>();
+        world.init_resource::<Events<TouchInput>>();
+        let window = world.spawn_empty().id();
+        world.send_event(TouchInput {
+            phase: TouchPhase::Started,
+            position: Vec2::ZERO,
+            window,
+            force: None,
+            id: 0,
+        });
+        world.run_system_once(touch_screen_input_system).unwrap();
+        world
+    }
+
+    fn still_pressed(world: &World) -> (bool, bool, bool) {
+        (
+            world
+                .resource::<ButtonInput<KeyCode>>()
+                .pressed(KeyCode::KeyW),
+            world
+                .resource::<ButtonInput<MouseButton>>()
+                .pressed(MouseButton::Left),
+            world.resource::<Touches>().get_pressed(0).is_some(),
+        )
+    }
+
+    fn focus_lost_sent(world: &World) -> bool {
+        !world.resource::<Events<KeyboardFocusLost>>().is_empty()
+    }
+
+    #[test]
+    fn releases_input_only_for_enabled_triggers() {
+        for trigger in InputResetTriggers::all().iter() {
+            let mut world = world_with_pressed_input(trigger);
+            assert_eq!(still_pressed(&world), (true, true, true));
+            reset_input_on(&mut world, trigger);
+            assert_eq!(
+                still_pressed(&world),
+                (false, false, false),
+                "{:?}",
+                trigger
+            );
+            assert!(focus_lost_sent(&world), "{:?}", trigger);
+
+            let mut world = world_with_pressed_input(InputResetTriggers::all() - trigger);
+            reset_input_on(&mut world, trigger);
+            assert_eq!(still_pressed(&world), (true, true, true), "{:?}", trigger);
+            assert!(!focus_lost_sent(&world), "{:?}", trigger);
+        }
+    }
+
+    #[test]
+    fn all_triggers_are_enabled_without_the_resource() {
+        for trigger in InputResetTriggers::all().iter() {
+            let mut world = world_with_pressed_input(InputResetTriggers::empty());
+            world.remove_resource::<InputResetTriggers>();
+            reset_input_on(&mut world, trigger);
+            assert_eq!(
+                still_pressed(&world),
+                (false, false, false),
+                "{:?}",
+                trigger
+            );
+            assert!(focus_lost_sent(&world), "{:?}", trigger);
+        }
+    }
+}
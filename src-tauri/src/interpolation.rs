@@ -0,0 +1,98 @@
+use bevy::app::RunFixedMainLoopSystem;
+use bevy::prelude::*;
+
+// Renders the entity between its last two fixed-timestep transforms instead of
+// at the last one, so movement done in FixedUpdate doesn't judder when the
+// frame rate isn't a multiple of the fixed rate. The shown transform lags the
+// simulation by up to one step. Move these entities only from FixedUpdate,
+// writes from Update are overwritten by the blend.
+#[derive(Component, Default)]
+pub struct Interpolated;
+
+// Transforms after the last two fixed steps; `current` is the simulated state,
+// while Transform holds the blend between rendering and the next fixed loop
+#[derive(Component)]
+struct InterpolationState {
+    previous: Transform,
+    current: Transform,
+}
+
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            RunFixedMainLoop,
+            (
+                restore_transforms.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+                blend_transforms.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+            ),
+        )
+        .add_systems(FixedFirst, store_previous_transforms)
+        .add_systems(FixedLast, store_current_transforms);
+    }
+}
+
+// Puts the simulated transform back before the fixed steps continue from it
+fn restore_transforms(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, Option<&InterpolationState>), With<Interpolated>>,
+) {
+    for (entity, mut transform, state) in query.iter_mut() {
+        match state {
+            Some(state) => *transform = state.current,
+            None => {
+                commands.entity(entity).insert(InterpolationState {
+                    previous: *transform,
+                    current: *transform,
+                });
+            }
+        }
+    }
+}
+
+fn store_previous_transforms(
+    mut query: Query<(&Transform, &mut InterpolationState), With<Interpolated>>,
+) {
+    for (transform, mut state) in query.iter_mut() {
+        state.previous = *transform;
+    }
+}
+
+fn store_current_transforms(
+    mut query: Query<(&Transform, &mut InterpolationState), With<Interpolated>>,
+) {
+    for (transform, mut state) in query.iter_mut() {
+        state.current = *transform;
+    }
+}
+
+// Runs before PostUpdate, so the blend is what gets propagated and rendered
+fn blend_transforms(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &InterpolationState), With<Interpolated>>,
+) {
+    let alpha = time.overstep_fraction();
+    for (mut transform, state) in query.iter_mut() {
+        *transform = Transform {
+            translation: state
+                .previous
+                .translation
+                .lerp(state.current.translation, alpha),
+            rotation: state.previous.rotation.slerp(state.current.rotation, alpha),
+            scale: state.previous.scale.lerp(state.current.scale, alpha),
+        };
+    }
+}
+
+// Moves the entity without blending from where it was, e.g. when respawning
+#[allow(dead_code)]
+pub fn teleport(commands: &mut Commands, entity: Entity, transform: Transform) {
+    commands.entity(entity).insert((
+        transform,
+        InterpolationState {
+            previous: transform,
+            current: transform,
+        },
+    ));
+}
@@ -0,0 +1,141 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use tauri::State;
+use tokio::sync::oneshot;
+
+use crate::background;
+use crate::bridge::{BevyBridge, CommandError};
+use crate::camera;
+use crate::tauri_plugin::UPDATES_PAUSED;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LatencyReport {
+    // from the command call until the flashed frame was presented
+    pub latency_ms: f64,
+    // waiting for the next update to pick up the request
+    pub queued_ms: f64,
+    // bevy updates between picking up the request and the present
+    pub frames: u32,
+    pub present_mode: String,
+}
+
+struct Probe {
+    requested: Instant,
+    flashed: Instant,
+    updates: u32,
+    present_mode: String,
+    // the flashed camera and the clear color it gets back
+    camera: Entity,
+    clear_color: ClearColorConfig,
+    reply: oneshot::Sender<Result<LatencyReport, CommandError>>,
+}
+
+// The running `measure_latency` call, at most one at a time since they would
+// flash the same camera
+#[derive(Resource, Default)]
+pub struct LatencyProbe(Option<Probe>);
+
+// PipelinedRenderingPlugin renders a frame while the next update runs, so the
+// frame changed in one update has been presented once the following one returns
+const PRESENT_UPDATES: u32 = 2;
+
+const PAUSED_ERROR: &str = "bevy updates are paused";
+
+// Called by the runner after every update
+pub fn record_present(world: &mut World) {
+    let mut probe = world.resource_mut::<LatencyProbe>();
+    let Some(pending) = probe.0.as_mut() else {
+        return;
+    };
+    pending.updates += 1;
+    if pending.updates < PRESENT_UPDATES {
+        return;
+    }
+    let pending = probe.0.take().unwrap();
+    let presented = Instant::now();
+    let _ = pending.reply.send(Ok(LatencyReport {
+        latency_ms: (presented - pending.requested).as_secs_f64() * 1000.0,
+        queued_ms: (pending.flashed - pending.requested).as_secs_f64() * 1000.0,
+        frames: pending.updates,
+        present_mode: pending.present_mode,
+    }));
+    end_flash(world, pending.camera, pending.clear_color);
+}
+
+fn end_flash(world: &mut World, camera: Entity, clear_color: ClearColorConfig) {
+    if let Some(mut camera) = world.get_mut::<Camera>(camera) {
+        camera.clear_color = clear_color;
+    }
+    background::set_background_visibility(world, camera, Visibility::Inherited);
+}
+
+// Called by the runner while updates are paused
+pub fn fail_paused_probe(world: &mut World) {
+    if let Some(pending) = world.resource_mut::<LatencyProbe>().0.take() {
+        let _ = pending
+            .reply
+            .send(Err(CommandError::NotReady(String::from(PAUSED_ERROR))));
+        end_flash(world, pending.camera, pending.clear_color);
+    }
+}
+
+// Flashes the main camera's clear color for one frame, hiding its gradient
+// background, and measures the time until that frame was handed to the compositor. How long the compositor and display take on top
+// of that isn't visible to the app, so compare results between present modes
+// and pacing settings rather than reading them as absolute input lag.
+#[tauri::command]
+pub async fn measure_latency(bridge: State<'_, BevyBridge>) -> Result<LatencyReport, CommandError> {
+    let requested = Instant::now();
+    if UPDATES_PAUSED.load(Ordering::Relaxed) {
+        return Err(CommandError::NotReady(String::from(PAUSED_ERROR)));
+    }
+
+    let (reply, rx) = oneshot::channel();
+    bridge.send(move |world| {
+        if world.resource::<LatencyProbe>().0.is_some() {
+            let _ = reply.send(Err(CommandError::Failed(String::from(
+                "a latency measurement is already running",
+            ))));
+            return;
+        }
+        let camera = match camera::main_camera(world) {
+            Ok(camera) => camera,
+            Err(err) => {
+                let _ = reply.send(Err(err));
+                return;
+            }
+        };
+        let present_mode = world
+            .query_filtered::<&Window, With<PrimaryWindow>>()
+            .get_single(world)
+            .map(|window| format!("{:?}", window.present_mode))
+            .unwrap_or_default();
+        let clear_color = world.get::<Camera>(camera).unwrap().clear_color;
+        let current = match clear_color {
+            ClearColorConfig::Custom(color) => color,
+            ClearColorConfig::Default | ClearColorConfig::None => world.resource::<ClearColor>().0,
+        };
+        // whichever of white and black differs most from the current color
+        let flash = if current.luminance() > 0.5 {
+            Color::BLACK
+        } else {
+            Color::WHITE
+        };
+        world.get_mut::<Camera>(camera).unwrap().clear_color = ClearColorConfig::Custom(flash);
+        background::set_background_visibility(world, camera, Visibility::Hidden);
+        world.resource_mut::<LatencyProbe>().0 = Some(Probe {
+            requested,
+            flashed: Instant::now(),
+            updates: 0,
+            present_mode,
+            camera,
+            clear_color,
+            reply,
+        });
+    })?;
+    rx.await
+        .map_err(|_| CommandError::NotReady(String::from("bevy app dropped the request")))?
+}
@@ -0,0 +1,53 @@
+use bevy::log::tracing_subscriber::{reload, EnvFilter, Registry};
+use bevy::log::{BoxedLayer, Level, LogPlugin, DEFAULT_FILTER};
+use bevy::prelude::*;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+
+// Swaps the filter of the reloadable layer added by `log_plugin`
+#[derive(Resource)]
+pub struct LogFilterHandle(reload::Handle<EnvFilter, Registry>);
+
+// LogPlugin whose filter can be changed with `set_log_level`. LogPlugin's own
+// filter lets everything through and a reloadable EnvFilter in its custom
+// layer, starting from RUST_LOG or bevy's default, decides instead. RUST_LOG is
+// still read by LogPlugin too, so when set it caps what `set_log_level` enables.
+pub fn log_plugin() -> LogPlugin {
+    LogPlugin {
+        filter: String::new(),
+        level: Level::TRACE,
+        custom_layer: reloadable_filter,
+    }
+}
+
+fn reloadable_filter(app: &mut App) -> Option<BoxedLayer> {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("{},{}", Level::INFO, DEFAULT_FILTER)));
+    let (layer, handle) = reload::Layer::new(filter);
+    app.insert_resource(LogFilterHandle(handle));
+    Some(Box::new(layer))
+}
+
+// Takes an EnvFilter directive string like RUST_LOG, e.g. "debug" or
+// "info,bevy_render=debug,wgpu=warn"
+#[tauri::command]
+pub async fn set_log_level(
+    bridge: State<'_, BevyBridge>,
+    filter: String,
+) -> Result<(), CommandError> {
+    let parsed = EnvFilter::try_new(&filter).map_err(|err| {
+        CommandError::InvalidArgument(format!("invalid log filter {:?}: {}", filter, err))
+    })?;
+    bridge
+        .run(move |world| {
+            world
+                .resource::<LogFilterHandle>()
+                .0
+                .reload(parsed)
+                .map_err(|err| format!("failed to change the log filter: {}", err))?;
+            info!("log filter set to {}", filter);
+            Ok(())
+        })
+        .await?
+}
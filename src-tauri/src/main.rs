@@ -1,23 +1,265 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod adapters;
+mod adaptive_vsync;
+mod animation;
+mod assets;
+mod audio;
+mod background;
+mod batch;
 mod bevy;
+mod bridge;
+mod camera;
+mod child_window;
+mod clipboard;
+mod color_picker;
+mod debug_gizmos;
+mod diagnostics;
+mod dynamic_resolution;
+mod environment;
+mod frame_capture;
+mod frame_limiter;
+mod frame_recording;
+mod frame_stats;
+mod frame_sync;
+mod game_state;
+mod gamepad;
+mod gpu_memory;
+#[cfg(feature = "grayscale")]
+mod grayscale;
+mod input_record;
+mod input_reset;
+mod interpolation;
+mod latency;
+mod log_filter;
+mod manifest;
+#[cfg(desktop)]
+mod menu;
+mod mouselook;
+mod offscreen;
+mod one_shot;
+mod picking;
+mod power;
+mod quality;
+mod render_region;
+mod render_resolution;
+mod render_stats;
+mod safe_mode;
+mod scene;
+#[cfg(desktop)]
+mod shortcuts;
+mod sprites;
 mod wgpu;
 mod tauri_plugin;
+#[cfg(desktop)]
+mod tray;
+mod ui;
+mod watchdog;
+mod webview_region;
+mod window;
 
 pub fn generate_tauri_context() -> tauri::Context {
     tauri::generate_context!()
 }
 
+// The process exit code for how bevy exited, None for a clean exit
+fn exit_code(exit: &::bevy::app::AppExit) -> Option<i32> {
+    match exit {
+        ::bevy::app::AppExit::Success => None,
+        ::bevy::app::AppExit::Error(code) => Some(code.get().into()),
+    }
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = std::env::args().collect();
     let use_wgpu = args.contains(&String::from("--use-wgpu"));
+    let use_both = args.contains(&String::from("--both"));
+    let use_offscreen = args.contains(&String::from("--offscreen"));
+    let use_gl = args.contains(&String::from("--gl"));
+    let use_menu = args.contains(&String::from("--menu"));
+    let use_tray = args.contains(&String::from("--tray"));
+    let force_safe_mode = args.contains(&String::from("--safe-mode"));
+    let no_audio = args.contains(&String::from("--no-audio"));
+    let low_latency = args.contains(&String::from("--low-latency"));
+    let adaptive_vsync = args.contains(&String::from("--adaptive-vsync"));
+    let dynamic_resolution = args.contains(&String::from("--dynamic-resolution"));
+    let custom_titlebar = args.contains(&String::from("--custom-titlebar"));
+    let watchdog = args.contains(&String::from("--watchdog"));
+    let refresh_pacing = args.contains(&String::from("--refresh-pacing"));
+    let power_profile = args.contains(&String::from("--power-profile"));
+    let error_screen = args.contains(&String::from("--error-screen"));
+    // --camera x,y,z moves the demo camera, which keeps looking at the origin
+    let camera_pose = match args.iter().position(|arg| arg == "--camera") {
+        Some(index) => {
+            let value = args.get(index + 1).ok_or("--camera needs x,y,z")?;
+            camera::parse_camera_position(value)?
+        }
+        None => camera::CameraPose::default(),
+    };
+    // --resolution WIDTHxHEIGHT renders at a fixed size scaled to the window
+    let render_resolution = match args.iter().position(|arg| arg == "--resolution") {
+        Some(index) => {
+            let value = args.get(index + 1).ok_or("--resolution needs WIDTHxHEIGHT")?;
+            Some(render_resolution::RenderResolution::parse(value)?)
+        }
+        None => None,
+    };
+
+    // --manifest path (or BEVY_SCENE_MANIFEST) spawns entities from JSON instead of the demo scene
+    let scene_manifest = match args.iter().position(|arg| arg == "--manifest") {
+        Some(index) => Some(args.get(index + 1).ok_or("--manifest needs a path")?.into()),
+        None => std::env::var_os("BEVY_SCENE_MANIFEST").map(Into::into),
+    };
+
+    // --adapter NAME|INDEX (or BEVY_ADAPTER) picks the GPU, see list_adapters
+    let force_adapter = match args.iter().position(|arg| arg == "--adapter") {
+        Some(index) => Some(
+            args.get(index + 1)
+                .ok_or("--adapter needs a name or index")?
+                .clone(),
+        ),
+        None => std::env::var("BEVY_ADAPTER").ok(),
+    }
+    .map(|value| adapters::AdapterSelection::parse(&value));
+
+    // --webview-panel WIDTH puts the webview in a panel along the right edge
+    // instead of over the whole window
+    let webview_region = match args.iter().position(|arg| arg == "--webview-panel") {
+        Some(index) => {
+            let value = args.get(index + 1).ok_or("--webview-panel needs a width")?;
+            let width = value
+                .parse()
+                .map_err(|_| format!("invalid webview panel width {}", value))?;
+            Some(webview_region::WebviewRegion::right_panel(width))
+        }
+        None => None,
+    };
+
+    // --quality low|medium|high starts with a quality preset, see set_quality
+    let quality = match args.iter().position(|arg| arg == "--quality") {
+        Some(index) => {
+            let value = args
+                .get(index + 1)
+                .ok_or("--quality needs low, medium or high")?;
+            Some(
+                quality::QualityPreset::from_name(value)
+                    .ok_or_else(|| format!("unknown quality preset {}", value))?,
+            )
+        }
+        None => None,
+    };
+
+    // --min-size WIDTHxHEIGHT (logical pixels) keeps the window from shrinking further
+    let size_constraints = match args.iter().position(|arg| arg == "--min-size") {
+        Some(index) => {
+            let value = args.get(index + 1).ok_or("--min-size needs WIDTHxHEIGHT")?;
+            let (width, height) = value
+                .split_once('x')
+                .ok_or_else(|| format!("minimum size {} isn't WIDTHxHEIGHT", value))?;
+            let dimension = |text: &str| {
+                text.trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid minimum size {}", value))
+            };
+            Some(window::SizeConstraints::new(
+                Some(dimension(width)?),
+                Some(dimension(height)?),
+                None,
+                None,
+            )?)
+        }
+        None => None,
+    };
+
+    // --replay path feeds the input recorded with stop_input_recording back in
+    let replay = match args.iter().position(|arg| arg == "--replay") {
+        Some(index) => Some(args.get(index + 1).ok_or("--replay needs a path")?.into()),
+        None => None,
+    };
 
     if !use_wgpu {
-        bevy::setup_bevy();
+        let exit = bevy::setup_bevy(bevy::DemoOptions {
+            with_wgpu_window: use_both,
+            offscreen: use_offscreen,
+            force_gl: use_gl,
+            camera_pose,
+            render_resolution,
+            with_menu: use_menu,
+            close_to_tray: use_tray,
+            scene_manifest,
+            safe_mode: force_safe_mode,
+            no_audio,
+            low_latency,
+            adaptive_vsync,
+            force_adapter,
+            webview_region,
+            replay,
+            dynamic_resolution,
+            custom_titlebar,
+            quality,
+            watchdog,
+            size_constraints,
+            refresh_pacing,
+            power_profile,
+            error_screen,
+        });
+        // lets scripts tell a failed run from a normal exit
+        if let Some(code) = exit_code(&exit) {
+            std::process::exit(code);
+        }
     } else {
-        wgpu::setup_wgpu();
+        // --msaa N, --size WIDTHxHEIGHT (physical pixels) and --no-clear configure
+        // the triangle demo
+        let mut options = wgpu::TriangleOptions {
+            clear: !args.contains(&String::from("--no-clear")),
+            ..Default::default()
+        };
+        if let Some(index) = args.iter().position(|arg| arg == "--msaa") {
+            let value = args.get(index + 1).ok_or("--msaa needs a sample count")?;
+            options.sample_count = value
+                .parse()
+                .map_err(|_| format!("invalid MSAA sample count {}", value))?;
+        }
+        if let Some(index) = args.iter().position(|arg| arg == "--size") {
+            let value = args.get(index + 1).ok_or("--size needs WIDTHxHEIGHT")?;
+            let size = render_resolution::RenderResolution::parse(value)?;
+            options.size = Some((size.width, size.height));
+        }
+        // --surface-format NAME, e.g. rgba16float, replaces the sRGB format
+        let surface_format = match args.iter().position(|arg| arg == "--surface-format") {
+            Some(index) => {
+                let name = args
+                    .get(index + 1)
+                    .ok_or("--surface-format needs a format")?;
+                let format = wgpu::surface_format_from_name(name)
+                    .ok_or_else(|| format!("unknown surface format {}", name))?;
+                Some(format)
+            }
+            None => None,
+        };
+        wgpu::setup_wgpu(
+            ::wgpu::InstanceDescriptor::default(),
+            surface_format,
+            options,
+        );
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::bevy::app::AppExit;
+    use std::num::NonZeroU8;
+
+    #[test]
+    fn exit_code_follows_bevy() {
+        assert_eq!(exit_code(&AppExit::Success), None);
+        assert_eq!(exit_code(&AppExit::error()), Some(1));
+        let code = NonZeroU8::new(42).unwrap();
+        assert_eq!(exit_code(&AppExit::Error(code)), Some(42));
+        assert_eq!(exit_code(&AppExit::Error(NonZeroU8::MAX)), Some(255));
+    }
+}
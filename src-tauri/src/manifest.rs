@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::path::Path;
+
+// Entities to spawn at startup instead of the demo scene, read from JSON:
+//
+// { "entities": [
+//     { "type": "plane", "size": 10, "color": [0.3, 0.5, 0.3] },
+//     { "type": "cube", "name": "box", "position": [0, 0.5, 0], "color": [0.8, 0.2, 0.2] },
+//     { "type": "point_light", "intensity": 500000, "position": [0, 3, 0] }
+// ] }
+//
+// Colors are sRGB components in 0..=1, lights use the units of add_light.
+#[derive(Resource, Deserialize, Debug)]
+pub struct SceneManifest {
+    pub entities: Vec<ManifestEntity>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ManifestEntity {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub position: [f32; 3],
+    // Euler angles in degrees, applied in Y, X, Z order. Directional light
+    // shines along -Z, so rotate it to aim it.
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    #[serde(default = "one3")]
+    pub scale: [f32; 3],
+    // material color of meshes, light color of lights
+    #[serde(default = "one3")]
+    pub color: [f32; 3],
+    #[serde(flatten)]
+    pub kind: ManifestKind,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ManifestKind {
+    Cube {
+        #[serde(default = "one")]
+        size: f32,
+    },
+    Sphere {
+        #[serde(default = "half")]
+        radius: f32,
+    },
+    Plane {
+        #[serde(default = "one")]
+        size: f32,
+    },
+    PointLight {
+        intensity: f32,
+        range: Option<f32>,
+    },
+    DirectionalLight {
+        illuminance: f32,
+    },
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn half() -> f32 {
+    0.5
+}
+
+fn one3() -> [f32; 3] {
+    [1.0; 3]
+}
+
+impl SceneManifest {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        let manifest: Self = serde_json::from_str(&text)
+            .map_err(|err| format!("invalid scene manifest {}: {}", path.display(), err))?;
+        for (index, entity) in manifest.entities.iter().enumerate() {
+            entity.validate().map_err(|err| {
+                let name = entity.name.as_deref().unwrap_or("unnamed");
+                format!("{}: entity {} ({}): {}", path.display(), index, name, err)
+            })?;
+        }
+        Ok(manifest)
+    }
+}
+
+impl ManifestEntity {
+    fn validate(&self) -> Result<(), String> {
+        let (field, value) = match self.kind {
+            ManifestKind::Cube { size } | ManifestKind::Plane { size } => ("size", size),
+            ManifestKind::Sphere { radius } => ("radius", radius),
+            ManifestKind::PointLight { intensity, .. } => ("intensity", intensity),
+            ManifestKind::DirectionalLight { illuminance } => ("illuminance", illuminance),
+        };
+        let is_light = matches!(
+            self.kind,
+            ManifestKind::PointLight { .. } | ManifestKind::DirectionalLight { .. }
+        );
+        if !value.is_finite() || value < 0.0 || (value == 0.0 && !is_light) {
+            return Err(format!("{} {} is out of range", field, value));
+        }
+        let mut values = self
+            .position
+            .iter()
+            .chain(&self.rotation)
+            .chain(&self.scale);
+        if !values.all(|value| value.is_finite()) {
+            return Err(String::from("position, rotation and scale must be finite"));
+        }
+        Ok(())
+    }
+
+    fn transform(&self) -> Transform {
+        let [x, y, z] = self.rotation.map(f32::to_radians);
+        Transform {
+            translation: Vec3::from(self.position),
+            rotation: Quat::from_euler(EulerRot::YXZ, y, x, z),
+            scale: Vec3::from(self.scale),
+        }
+    }
+}
+
+pub fn spawn_manifest(
+    mut commands: Commands,
+    manifest: Res<SceneManifest>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for entity in &manifest.entities {
+        let [r, g, b] = entity.color;
+        let color = Color::srgb(r, g, b);
+        let mut spawned = commands.spawn(entity.transform());
+        if let Some(name) = &entity.name {
+            spawned.insert(Name::new(name.clone()));
+        }
+        let mesh = match entity.kind {
+            ManifestKind::Cube { size } => Some(meshes.add(Cuboid::from_length(size))),
+            ManifestKind::Sphere { radius } => Some(meshes.add(Sphere::new(radius))),
+            ManifestKind::Plane { size } => {
+                Some(meshes.add(Plane3d::default().mesh().size(size, size)))
+            }
+            ManifestKind::PointLight { intensity, range } => {
+                spawned.insert(PointLight {
+                    color,
+                    intensity,
+                    range: range.unwrap_or(PointLight::default().range),
+                    ..default()
+                });
+                None
+            }
+            ManifestKind::DirectionalLight { illuminance } => {
+                spawned.insert(DirectionalLight {
+                    color,
+                    illuminance,
+                    ..default()
+                });
+                None
+            }
+        };
+        if let Some(mesh) = mesh {
+            spawned.insert((Mesh3d(mesh), MeshMaterial3d(materials.add(color))));
+        }
+    }
+}
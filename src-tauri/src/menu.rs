@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use tauri::menu::{Menu, MenuBuilder, SubmenuBuilder};
+
+// Sent for every clicked menu item, whether or not it has a binding
+#[derive(Event, Debug, Clone)]
+pub struct MenuItemTriggered {
+    pub id: String,
+}
+
+pub type MenuFactory = dyn Fn(&tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> + Send + Sync;
+type MenuBinding = dyn Fn(&mut World) + Send + Sync;
+
+// Bevy events sent when the menu item with the key is clicked, see
+// TauriPlugin::with_menu_event
+#[derive(Resource, Default)]
+pub struct MenuBindings(HashMap<String, Vec<Box<MenuBinding>>>);
+
+impl MenuBindings {
+    pub fn bind<E: Event + Clone>(&mut self, id: &str, event: E) {
+        self.0
+            .entry(id.to_string())
+            .or_default()
+            .push(Box::new(move |world: &mut World| {
+                world.send_event(event.clone());
+            }));
+    }
+}
+
+// Menu events arrive through the tao event loop, which the bevy runner pumps
+// like any other tauri event
+pub fn handle_menu_event(world: &mut World, id: &str) {
+    world.send_event(MenuItemTriggered { id: id.to_string() });
+    let Some(bindings) = world.remove_resource::<MenuBindings>() else {
+        return;
+    };
+    for send in bindings.0.get(id).into_iter().flatten() {
+        send(world);
+    }
+    world.insert_resource(bindings);
+}
+
+// File > New and File > Quit, with the ids "new" and "quit"
+pub fn example_menu(app: &tauri::AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let file = SubmenuBuilder::new(app, "File")
+        .text("new", "New")
+        .separator()
+        .text("quit", "Quit")
+        .build()?;
+    MenuBuilder::new(app).item(&file).build()
+}
@@ -0,0 +1,94 @@
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::window::{CursorGrabMode, PrimaryWindow};
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+
+// Radians the camera turns per pixel of mouse motion
+const SENSITIVITY: f32 = 0.003;
+// keeps the camera from flipping over when looking straight up or down
+const MAX_PITCH: f32 = 1.54;
+
+// Turns the main camera with raw mouse motion while the pointer is locked.
+// tauri doesn't forward tao's DeviceEvent::MouseMotion, so the webview reads
+// the deltas with the Pointer Lock API and sends them with `send_mouse_motion`.
+pub struct MouseLookPlugin;
+
+impl Plugin for MouseLookPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, mouse_look);
+    }
+}
+
+fn pointer_locked(windows: &Query<&Window, With<PrimaryWindow>>) -> bool {
+    windows
+        .get_single()
+        .is_ok_and(|window| window.cursor_options.grab_mode == CursorGrabMode::Locked)
+}
+
+fn mouse_look(
+    mut motion: EventReader<MouseMotion>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&Camera, &mut Transform), With<Camera3d>>,
+) {
+    let delta: Vec2 = motion.read().map(|motion| motion.delta).sum();
+    if delta == Vec2::ZERO || !pointer_locked(&windows) {
+        return;
+    }
+    let Some((_, mut transform)) = cameras.iter_mut().min_by_key(|(camera, _)| camera.order)
+    else {
+        return;
+    };
+    let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+    let yaw = yaw - delta.x * SENSITIVITY;
+    let pitch = (pitch - delta.y * SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+    transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+}
+
+// Relative mouse motion, movementX/Y of the webview's pointer events. Unlike
+// cursor positions it isn't clamped to the window and keeps coming while the
+// pointer is locked, with unadjustedMovement without OS acceleration. Queued
+// without waiting for the world, the webview calls this for every pointer move.
+#[tauri::command]
+pub fn send_mouse_motion(
+    bridge: State<'_, BevyBridge>,
+    dx: f32,
+    dy: f32,
+) -> Result<(), CommandError> {
+    if !dx.is_finite() || !dy.is_finite() {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid mouse motion {}, {}",
+            dx, dy
+        )));
+    }
+    bridge.send(move |world| {
+        world.send_event(MouseMotion {
+            delta: Vec2::new(dx, dy),
+        });
+    })
+}
+
+// Mirrors the webview's pointer lock into bevy's window, so systems can check
+// CursorGrabMode::Locked like they would with bevy_winit
+#[tauri::command]
+pub async fn set_pointer_locked(
+    bridge: State<'_, BevyBridge>,
+    locked: bool,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let mut windows = world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+            let mut window = windows
+                .get_single_mut(world)
+                .map_err(|_| CommandError::EntityNotFound(String::from("no primary window")))?;
+            window.cursor_options.grab_mode = if locked {
+                CursorGrabMode::Locked
+            } else {
+                CursorGrabMode::None
+            };
+            window.cursor_options.visible = !locked;
+            Ok(())
+        })
+        .await?
+}
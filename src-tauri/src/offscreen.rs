@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::gpu_readback::{Readback, ReadbackComplete};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::renderer::RenderDevice;
+use bevy::window::{PrimaryWindow, WindowRef};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::Manager;
+
+use crate::bridge::CommandError;
+
+// Renders the primary window's cameras into an Image instead of the window
+// surface and streams it to the webview, which draws it on a canvas. This
+// avoids compositing bevy's surface under the webview, at the cost of copying
+// every frame through IPC: width * height * 4 bytes, about 3.7MB for 1280x720,
+// so 30 fps already moves over 100MB/s while the shared surface moves nothing.
+//
+// Enable it with TauriPlugin::with_offscreen_stream.
+#[derive(Clone, Copy)]
+pub struct OffscreenStream {
+    pub fps: u32,
+}
+
+// Channels from subscribe_offscreen_frames, dropped once sending fails.
+// Managed by TauriPlugin up front so the webview can subscribe before the
+// renderer exists.
+#[derive(Default)]
+pub struct OffscreenSubscribers(Mutex<Vec<Channel<InvokeResponseBody>>>);
+
+#[derive(Resource)]
+struct OffscreenTarget {
+    image: Handle<Image>,
+    readback: Entity,
+    interval: Duration,
+    last_readback: Duration,
+    // size the pending readback was scheduled with
+    readback_size: UVec2,
+}
+
+const FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+impl Plugin for OffscreenStream {
+    fn build(&self, app: &mut App) {
+        let mut image = Image::new_fill(
+            Extent3d::default(),
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            FORMAT,
+            RenderAssetUsages::default(),
+        );
+        image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_SRC
+            | TextureUsages::COPY_DST
+            | TextureUsages::RENDER_ATTACHMENT;
+        let image = app.world_mut().resource_mut::<Assets<Image>>().add(image);
+        let readback = app.world_mut().spawn_empty().observe(send_frame).id();
+
+        app.insert_resource(OffscreenTarget {
+            image,
+            readback,
+            interval: Duration::from_secs_f64(1.0 / self.fps.max(1) as f64),
+            last_readback: Duration::ZERO,
+            readback_size: UVec2::ZERO,
+        })
+        .add_systems(
+            PostUpdate,
+            (redirect_cameras, resize_target, schedule_readback),
+        );
+    }
+}
+
+fn redirect_cameras(target: Res<OffscreenTarget>, mut cameras: Query<&mut Camera>) {
+    for mut camera in cameras.iter_mut() {
+        if matches!(camera.target, RenderTarget::Window(WindowRef::Primary)) {
+            camera.target = RenderTarget::Image(target.image.clone());
+        }
+    }
+}
+
+// Follows the window size
+fn resize_target(
+    target: Res<OffscreenTarget>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+    let size = window.physical_size().max(UVec2::ONE);
+    if images
+        .get(&target.image)
+        .is_some_and(|image| image.size() != size)
+    {
+        if let Some(image) = images.get_mut(&target.image) {
+            image.resize(Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            });
+        }
+    }
+}
+
+// Readback copies the image every frame while the component exists, so it is
+// only kept for the one frame that is due
+fn schedule_readback(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut target: ResMut<OffscreenTarget>,
+    images: Res<Assets<Image>>,
+    readbacks: Query<(), With<Readback>>,
+) {
+    if readbacks.contains(target.readback) {
+        commands.entity(target.readback).remove::<Readback>();
+        return;
+    }
+    if time.elapsed() - target.last_readback < target.interval {
+        return;
+    }
+    let Some(image) = images.get(&target.image) else {
+        return;
+    };
+
+    target.last_readback = time.elapsed();
+    target.readback_size = image.size();
+    commands
+        .entity(target.readback)
+        .insert(Readback::texture(target.image.clone()));
+}
+
+// Frames are sent as an 8 byte header with the width and height as
+// little-endian u32, followed by tightly packed sRGB RGBA rows
+fn send_frame(
+    trigger: Trigger<ReadbackComplete>,
+    target: Res<OffscreenTarget>,
+    tauri_app: NonSend<tauri::AppHandle>,
+) {
+    let UVec2 {
+        x: width,
+        y: height,
+    } = target.readback_size;
+    let row_size = width as usize * 4;
+    // readbacks of more than one row pad every row to the copy alignment
+    let padded_row_size = if height > 1 {
+        RenderDevice::align_copy_bytes_per_row(row_size)
+    } else {
+        row_size
+    };
+    if trigger.0.len() < padded_row_size * (height as usize - 1) + row_size {
+        // the image was resized while the readback was in flight
+        return;
+    }
+
+    let mut frame = Vec::with_capacity(8 + row_size * height as usize);
+    frame.extend_from_slice(&width.to_le_bytes());
+    frame.extend_from_slice(&height.to_le_bytes());
+    for row in trigger.0.chunks(padded_row_size).take(height as usize) {
+        frame.extend_from_slice(&row[..row_size]);
+    }
+
+    let subscribers = tauri_app.state::<OffscreenSubscribers>();
+    subscribers
+        .0
+        .lock()
+        .unwrap()
+        .retain(|channel| channel.send(InvokeResponseBody::Raw(frame.clone())).is_ok());
+}
+
+#[tauri::command]
+pub fn subscribe_offscreen_frames(
+    app: tauri::AppHandle,
+    channel: Channel<InvokeResponseBody>,
+) -> Result<(), CommandError> {
+    let subscribers = app
+        .try_state::<OffscreenSubscribers>()
+        .ok_or_else(|| String::from("offscreen streaming is not enabled"))?;
+    subscribers.0.lock().unwrap().push(channel);
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+
+// One-shot systems registered with TauriPlugin::with_system, by name
+#[derive(Resource, Default)]
+pub struct NamedSystems(pub HashMap<String, SystemId>);
+
+// Runs the system registered as `name` once, at the start of the next update
+// like the other commands. Resolves once it ran; commands it queued are
+// applied by then too.
+#[tauri::command]
+pub async fn run_system(bridge: State<'_, BevyBridge>, name: String) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let system = world
+                .get_resource::<NamedSystems>()
+                .and_then(|systems| systems.0.get(&name).copied())
+                .ok_or_else(|| {
+                    CommandError::InvalidArgument(format!("no system registered as {}", name))
+                })?;
+            world
+                .run_system(system)
+                .map_err(|err| CommandError::Failed(format!("{}: {}", name, err)))
+        })
+        .await?
+}
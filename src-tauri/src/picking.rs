@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use bitflags::bitflags;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::Emitter;
+
+bitflags! {
+    // Picking backends TauriPlugin adds once the renderer exists, all by
+    // default. Mesh picking ray casts against every pickable mesh each frame,
+    // leave it out when only UI or sprites need to be picked.
+    #[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct PickingBackends: u8 {
+        const MESH = 1;
+        const SPRITE = 1 << 1;
+        const UI = 1 << 2;
+    }
+}
+
+impl Default for PickingBackends {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+// Reports the entity under the pointer to the webview as `entity-hovered`.
+// Needs the tauri::AppHandle inserted by TauriPlugin, so add it after that one.
+pub struct HoverPlugin {
+    // how long the hovered entity has to stay the same before it is reported
+    pub debounce: Duration,
+    // material color swapped onto the hovered mesh, if any
+    pub highlight: Option<Color>,
+}
+
+impl Default for HoverPlugin {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(50),
+            highlight: Some(Color::srgb(1.0, 0.9, 0.2)),
+        }
+    }
+}
+
+impl Plugin for HoverPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HoverState {
+            debounce: self.debounce,
+            current: None,
+            changed_at: Duration::ZERO,
+            reported: None,
+        })
+        .add_systems(Update, (track_hover, report_hover).chain());
+
+        if let Some(color) = self.highlight {
+            app.insert_resource(HoverHighlight {
+                color,
+                material: None,
+            })
+            .add_systems(Update, highlight_hover);
+        }
+    }
+}
+
+#[derive(Resource)]
+struct HoverState {
+    debounce: Duration,
+    current: Option<Entity>,
+    changed_at: Duration,
+    reported: Option<Entity>,
+}
+
+#[derive(Resource)]
+struct HoverHighlight {
+    color: Color,
+    material: Option<Handle<StandardMaterial>>,
+}
+
+// Material the hovered entity had before the highlight was applied
+#[derive(Component)]
+struct OriginalMaterial(Handle<StandardMaterial>);
+
+// Sent as `entity-hovered` and `entity-selected`, None when there is none
+#[derive(Serialize, Clone)]
+struct EntityPayload {
+    entity: Option<u64>,
+    name: Option<String>,
+}
+
+fn track_hover(
+    mut overs: EventReader<Pointer<Over>>,
+    mut outs: EventReader<Pointer<Out>>,
+    time: Res<Time<Real>>,
+    mut state: ResMut<HoverState>,
+) {
+    let previous = state.current;
+
+    // within one frame an entity is either left or entered, never both, so
+    // handling all outs before the overs keeps moves between entities right
+    for out in outs.read() {
+        if state.current == Some(out.target) {
+            state.current = None;
+        }
+    }
+    for over in overs.read() {
+        state.current = Some(over.target);
+    }
+
+    if state.current != previous {
+        state.changed_at = time.elapsed();
+    }
+}
+
+fn report_hover(
+    time: Res<Time<Real>>,
+    mut state: ResMut<HoverState>,
+    names: Query<&Name>,
+    tauri_app: NonSend<tauri::AppHandle>,
+) {
+    if state.current == state.reported || time.elapsed() - state.changed_at < state.debounce {
+        return;
+    }
+
+    let payload = EntityPayload {
+        entity: state.current.map(Entity::to_bits),
+        name: state
+            .current
+            .and_then(|entity| names.get(entity).ok())
+            .map(|name| name.to_string()),
+    };
+    if let Err(err) = tauri_app.emit("entity-hovered", payload) {
+        warn!("failed to emit entity-hovered: {}", err);
+    }
+    state.reported = state.current;
+}
+
+fn highlight_hover(
+    mut commands: Commands,
+    mut overs: EventReader<Pointer<Over>>,
+    mut outs: EventReader<Pointer<Out>>,
+    mut highlight: ResMut<HoverHighlight>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: Query<(
+        &mut MeshMaterial3d<StandardMaterial>,
+        Option<&OriginalMaterial>,
+    )>,
+) {
+    for out in outs.read() {
+        if let Ok((mut material, Some(original))) = meshes.get_mut(out.target) {
+            material.0 = original.0.clone();
+            commands.entity(out.target).remove::<OriginalMaterial>();
+        }
+    }
+
+    for over in overs.read() {
+        let Ok((mut material, original)) = meshes.get_mut(over.target) else {
+            continue;
+        };
+        // another pointer may already hover it, keep the material saved back then
+        if original.is_none() {
+            commands
+                .entity(over.target)
+                .insert(OriginalMaterial(material.0.clone()));
+        }
+        let color = highlight.color;
+        material.0 = highlight
+            .material
+            .get_or_insert_with(|| materials.add(color))
+            .clone();
+    }
+}
+
+// Material shown on `entity` once the hover highlight is gone
+pub fn base_material(world: &World, entity: Entity) -> Option<Handle<StandardMaterial>> {
+    match world.get::<OriginalMaterial>(entity) {
+        Some(original) => Some(original.0.clone()),
+        None => world
+            .get::<MeshMaterial3d<StandardMaterial>>(entity)
+            .map(|material| material.0.clone()),
+    }
+}
+
+// Replaces the material of `entity` without losing its hover highlight
+pub fn set_base_material(world: &mut World, entity: Entity, material: Handle<StandardMaterial>) {
+    if let Some(mut original) = world.get_mut::<OriginalMaterial>(entity) {
+        original.0 = material;
+    } else if let Some(mut current) = world.get_mut::<MeshMaterial3d<StandardMaterial>>(entity) {
+        current.0 = material;
+    }
+}
+
+// Marks entities picked with a click, see SelectionPlugin
+#[derive(Component)]
+pub struct Selected;
+
+// Clicking an entity selects it alone, clicking it again clears the selection.
+// Each change is reported to the webview as `entity-selected`, so it needs the
+// tauri::AppHandle inserted by TauriPlugin; add it after that one.
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, select_on_click);
+    }
+}
+
+fn select_on_click(
+    mut commands: Commands,
+    mut clicks: EventReader<Pointer<Click>>,
+    selected: Query<Entity, With<Selected>>,
+    names: Query<&Name>,
+    tauri_app: NonSend<tauri::AppHandle>,
+) {
+    // only the last click of the frame counts
+    let Some(click) = clicks
+        .read()
+        .filter(|click| click.button == PointerButton::Primary)
+        .last()
+    else {
+        return;
+    };
+    let was_selected = selected.contains(click.target);
+    for entity in selected.iter() {
+        commands.entity(entity).remove::<Selected>();
+    }
+    if !was_selected {
+        commands.entity(click.target).insert(Selected);
+    }
+
+    let target = (!was_selected).then_some(click.target);
+    let payload = EntityPayload {
+        entity: target.map(Entity::to_bits),
+        name: target
+            .and_then(|entity| names.get(entity).ok())
+            .map(|name| name.to_string()),
+    };
+    if let Err(err) = tauri_app.emit("entity-selected", payload) {
+        warn!("failed to emit entity-selected: {}", err);
+    }
+}
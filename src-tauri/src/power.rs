@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::bridge::CommandError;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    // not reported by the platform or a desktop without a battery, treated as AC
+    Unknown,
+}
+
+// Set with set_power_mode, Auto follows the detected source
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerMode {
+    #[default]
+    Auto,
+    Ac,
+    Battery,
+}
+
+// Frame rate caps by power source, see TauriPlugin::with_power_profile. A cap
+// above the refresh rate has no effect, the runner never paces faster.
+#[derive(Clone, Copy, Debug)]
+pub struct PowerProfile {
+    // None paces to the refresh rate as without a profile
+    pub ac_fps: Option<f64>,
+    pub battery_fps: Option<f64>,
+    // how often the power source is checked
+    pub poll_interval: Duration,
+}
+
+impl Default for PowerProfile {
+    fn default() -> Self {
+        Self {
+            ac_fps: None,
+            battery_fps: Some(30.0),
+            poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+// What the runner paces to and why. Kept up to date as a resource in the
+// main world, returned by get_power_status and sent as "power-changed".
+#[derive(Resource, Serialize, Clone, Copy, PartialEq, Debug)]
+pub struct PowerStatus {
+    pub source: PowerSource,
+    pub mode: PowerMode,
+    pub on_battery: bool,
+    pub fps_cap: Option<f64>,
+}
+
+impl PowerStatus {
+    // The frame duration the runner paces to instead of `target`
+    pub fn frame_target(&self, target: Duration) -> Duration {
+        match self.fps_cap {
+            Some(fps) => target.max(Duration::from_secs_f64(1.0 / fps)),
+            None => target,
+        }
+    }
+}
+
+struct PowerState {
+    profile: PowerProfile,
+    source: PowerSource,
+    mode: PowerMode,
+}
+
+impl PowerState {
+    fn status(&self) -> PowerStatus {
+        let on_battery = match self.mode {
+            PowerMode::Auto => self.source == PowerSource::Battery,
+            PowerMode::Ac => false,
+            PowerMode::Battery => true,
+        };
+        PowerStatus {
+            source: self.source,
+            mode: self.mode,
+            on_battery,
+            fps_cap: if on_battery {
+                self.profile.battery_fps
+            } else {
+                self.profile.ac_fps
+            },
+        }
+    }
+}
+
+// Shared by the detection thread, the runner and set_power_mode
+#[derive(Clone)]
+pub struct PowerStore(Arc<Mutex<PowerState>>);
+
+impl PowerStore {
+    pub fn status(&self) -> PowerStatus {
+        self.0.lock().unwrap().status()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn detect_power_source() -> PowerSource {
+    let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+        return PowerSource::Unknown;
+    };
+    let mut battery = false;
+    for supply in supplies.flatten() {
+        let read = |name: &str| {
+            std::fs::read_to_string(supply.path().join(name))
+                .map(|value| value.trim().to_string())
+                .unwrap_or_default()
+        };
+        match read("type").as_str() {
+            "Mains" | "USB" if read("online") == "1" => return PowerSource::Ac,
+            // scope Device is the battery of a mouse or gamepad
+            "Battery" if read("scope") != "Device" => battery = true,
+            _ => {}
+        }
+    }
+    if battery {
+        PowerSource::Battery
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+#[cfg(windows)]
+fn detect_power_source() -> PowerSource {
+    // only ac_line_status is read
+    #[allow(dead_code)]
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+    let mut status = SystemPowerStatus::default();
+    // SAFETY: the struct matches SYSTEM_POWER_STATUS, which the call only writes
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return PowerSource::Unknown;
+    }
+    match status.ac_line_status {
+        0 => PowerSource::Battery,
+        1 => PowerSource::Ac,
+        _ => PowerSource::Unknown,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn detect_power_source() -> PowerSource {
+    let Ok(output) = std::process::Command::new("pmset")
+        .args(["-g", "ps"])
+        .output()
+    else {
+        return PowerSource::Unknown;
+    };
+    let output = String::from_utf8_lossy(&output.stdout);
+    if output.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else if output.contains("'AC Power'") {
+        PowerSource::Ac
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+// Mobile platforms need a plugin of their own to read the battery state
+#[cfg(not(any(target_os = "linux", windows, target_os = "macos")))]
+fn detect_power_source() -> PowerSource {
+    PowerSource::Unknown
+}
+
+// Manages a PowerStore and starts a thread that checks the power source every
+// `poll_interval`, emitting "power-changed" when it changes
+pub fn spawn(app_handle: &tauri::AppHandle, profile: PowerProfile) -> PowerStore {
+    let store = PowerStore(Arc::new(Mutex::new(PowerState {
+        profile,
+        source: detect_power_source(),
+        mode: PowerMode::Auto,
+    })));
+    bevy::log::info!("power source {:?}", store.status().source);
+    app_handle.manage(store.clone());
+    let watched = store.clone();
+    let app_handle = app_handle.clone();
+    std::thread::Builder::new()
+        .name(String::from("power-source"))
+        .spawn(move || loop {
+            std::thread::sleep(profile.poll_interval);
+            let source = detect_power_source();
+            let status = {
+                let mut state = watched.0.lock().unwrap();
+                if state.source == source {
+                    continue;
+                }
+                state.source = source;
+                state.status()
+            };
+            bevy::log::info!("power source changed to {:?}", source);
+            let _ = app_handle.emit("power-changed", status);
+        })
+        .expect("failed to spawn the power source thread");
+    store
+}
+
+fn power_store(app: &tauri::AppHandle) -> Result<tauri::State<'_, PowerStore>, CommandError> {
+    app.try_state::<PowerStore>().ok_or_else(|| {
+        CommandError::Failed(String::from(
+            "no power profile, see TauriPlugin::with_power_profile",
+        ))
+    })
+}
+
+#[tauri::command]
+pub fn get_power_status(app: tauri::AppHandle) -> Result<PowerStatus, CommandError> {
+    Ok(power_store(&app)?.status())
+}
+
+// Overrides the detected power source, or follows it again with Auto. Applies
+// from the next runner iteration.
+#[tauri::command]
+pub fn set_power_mode(app: tauri::AppHandle, mode: PowerMode) -> Result<PowerStatus, CommandError> {
+    let store = power_store(&app)?;
+    let status = {
+        let mut state = store.0.lock().unwrap();
+        state.mode = mode;
+        state.status()
+    };
+    let _ = app.emit("power-changed", status);
+    Ok(status)
+}
@@ -0,0 +1,222 @@
+use bevy::core_pipeline::bloom::Bloom;
+use bevy::core_pipeline::core_3d::CORE_3D_DEPTH_FORMAT;
+use bevy::core_pipeline::prepass::DepthPrepass;
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureUsages;
+use bevy::render::renderer::RenderAdapter;
+use bevy::render::view::{Msaa, ViewTarget};
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::diagnostics;
+
+// Camera settings applied to every Camera3d, trading performance for fidelity
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum QualityPreset {
+    Low,
+    Medium,
+    High,
+}
+
+impl QualityPreset {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "low" => Some(Self::Low),
+            "medium" => Some(Self::Medium),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    fn hdr(self) -> bool {
+        self == Self::High
+    }
+
+    fn tonemapping(self) -> Tonemapping {
+        match self {
+            Self::Low => Tonemapping::Reinhard,
+            Self::Medium | Self::High => Tonemapping::TonyMcMapface,
+        }
+    }
+
+    fn msaa(self) -> Msaa {
+        match self {
+            Self::Low => Msaa::Off,
+            Self::Medium | Self::High => Msaa::Sample4,
+        }
+    }
+}
+
+// HDR cameras render to an intermediate texture before tonemapping to the
+// surface, which needs the adapter to support rendering to that format. The
+// surface has to offer a format with the range to show it, an 8 bit sRGB one
+// only gets the tonemapped colors.
+fn hdr_supported(
+    adapter: Option<&RenderAdapter>,
+    app: &tauri::AppHandle,
+) -> Result<(), CommandError> {
+    let adapter_supported = adapter.is_some_and(|adapter| {
+        adapter
+            .get_texture_format_features(ViewTarget::TEXTURE_FORMAT_HDR)
+            .allowed_usages
+            .contains(TextureUsages::RENDER_ATTACHMENT)
+    });
+    if !adapter_supported {
+        return Err(CommandError::InvalidArgument(String::from(
+            "HDR is not supported by the adapter",
+        )));
+    }
+    if !diagnostics::surface_info(app)?.hdr {
+        return Err(CommandError::InvalidArgument(String::from(
+            "the window surface has no HDR format",
+        )));
+    }
+    Ok(())
+}
+
+pub fn apply_quality(
+    mut commands: Commands,
+    preset: Res<QualityPreset>,
+    adapter: Option<Res<RenderAdapter>>,
+    tauri_app: NonSend<tauri::AppHandle>,
+    mut cameras: Query<(Entity, &mut Camera, Ref<Camera3d>)>,
+) {
+    if !preset.is_changed() && !cameras.iter().any(|(.., camera_3d)| camera_3d.is_added()) {
+        return;
+    }
+    let mut hdr = preset.hdr();
+    if hdr {
+        if let Err(err) = hdr_supported(adapter.as_deref(), &tauri_app) {
+            if preset.is_changed() {
+                warn!("{}, {:?} quality falls back to SDR", err, *preset);
+            }
+            hdr = false;
+        }
+    }
+
+    for (entity, mut camera, camera_3d) in cameras.iter_mut() {
+        if !preset.is_changed() && !camera_3d.is_added() {
+            continue;
+        }
+
+        camera.hdr = hdr;
+        let mut entity = commands.entity(entity);
+        entity.insert((preset.tonemapping(), preset.msaa()));
+        if hdr {
+            entity.insert(Bloom::NATURAL);
+        } else {
+            entity.remove::<Bloom>();
+        }
+    }
+}
+
+// Inserted by TauriPlugin::with_depth_prepass. Every Camera3d renders depth in
+// a prepass, which SSAO and edge detection effects read. Opaque meshes are
+// drawn twice, so this costs noticeably on weak hardware.
+#[derive(Resource)]
+pub struct DepthPrepassEnabled;
+
+// The prepass depth texture is multisampled along with the camera, so the
+// adapter has to support the camera's sample count for the depth format.
+// Runs after apply_quality, which may have just changed the camera's Msaa.
+pub fn apply_depth_prepass(
+    mut commands: Commands,
+    adapter: Option<Res<RenderAdapter>>,
+    cameras: Query<(Entity, Ref<Msaa>, Has<DepthPrepass>), With<Camera3d>>,
+) {
+    for (entity, msaa, has_prepass) in cameras.iter() {
+        if has_prepass && !msaa.is_changed() {
+            continue;
+        }
+
+        let supported = adapter.as_ref().is_none_or(|adapter| {
+            adapter
+                .get_texture_format_features(CORE_3D_DEPTH_FORMAT)
+                .flags
+                .sample_count_supported(msaa.samples())
+        });
+        let mut entity = commands.entity(entity);
+        if !supported {
+            warn!(
+                "{:?} depth is not supported by the adapter with {:?}, the depth prepass disables MSAA",
+                CORE_3D_DEPTH_FORMAT, *msaa
+            );
+            entity.insert(Msaa::Off);
+        }
+        if !has_prepass {
+            entity.insert(DepthPrepass);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn set_quality(
+    bridge: State<'_, BevyBridge>,
+    preset: String,
+) -> Result<(), CommandError> {
+    let preset = QualityPreset::from_name(&preset).ok_or_else(|| {
+        CommandError::InvalidArgument(format!("unknown quality preset {}", preset))
+    })?;
+    bridge
+        .run(move |world| {
+            if preset.hdr() {
+                hdr_supported(
+                    world.get_resource::<RenderAdapter>(),
+                    world.non_send_resource::<tauri::AppHandle>(),
+                )?;
+            }
+            world.insert_resource(preset);
+            Ok(())
+        })
+        .await?
+}
+
+// Bloom on the camera named `camera`, which needs HDR. Changing the quality
+// preset afterwards resets both.
+#[tauri::command]
+pub async fn set_bloom(
+    bridge: State<'_, BevyBridge>,
+    camera: String,
+    enabled: bool,
+    intensity: f32,
+) -> Result<(), CommandError> {
+    if !intensity.is_finite() || intensity < 0.0 {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid bloom intensity {}",
+            intensity
+        )));
+    }
+    bridge
+        .run(move |world| {
+            if enabled {
+                hdr_supported(
+                    world.get_resource::<RenderAdapter>(),
+                    world.non_send_resource::<tauri::AppHandle>(),
+                )?;
+            }
+            let entity = world
+                .query_filtered::<(Entity, &Name), With<Camera>>()
+                .iter(world)
+                .find(|(_, name)| name.as_str() == camera)
+                .map(|(entity, _)| entity)
+                .ok_or_else(|| {
+                    CommandError::EntityNotFound(format!("no camera named {}", camera))
+                })?;
+
+            let mut entity = world.entity_mut(entity);
+            if enabled {
+                if let Some(mut camera) = entity.get_mut::<Camera>() {
+                    camera.hdr = true;
+                }
+                entity.insert(Bloom {
+                    intensity,
+                    ..Bloom::NATURAL
+                });
+            } else {
+                entity.remove::<Bloom>();
+            }
+            Ok(())
+        })
+        .await?
+}
@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use bevy::render::camera::{CameraUpdateSystem, SubCameraView, Viewport};
+use bevy::window::PrimaryWindow;
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::tauri_plugin::RenderWindow;
+
+// Where the main camera renders, in logical pixels from the top left of the
+// primary window, e.g. an element's getBoundingClientRect()
+#[derive(Resource, Clone, Copy, Debug)]
+struct RenderRegion(Rect);
+
+// Clips the main camera to the RenderRegion like a scissor rectangle: the
+// camera still sees what it would across the whole window, but only the part
+// inside the region is drawn, so nothing is stretched and the pixels line up
+// with the element. The rest of the window keeps the clear color, see-through
+// with set_webview_transparent. Other cameras, e.g. the sprite overlay, still
+// cover the whole window.
+pub struct RenderRegionPlugin;
+
+impl Plugin for RenderRegionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, clip_main_camera.before(CameraUpdateSystem));
+    }
+}
+
+// The clipped camera and the physical rectangle and window size it was
+// clipped to, an empty rectangle when the region is off the window
+type Clipped = (Entity, URect, UVec2);
+
+// The part of `region` on a window `size` physical pixels large, in physical
+// pixels. `scale` is the tauri window's scale factor, the one the webview lays
+// out the element with.
+fn physical_rect(region: Rect, scale: f32, size: UVec2) -> URect {
+    let min = (region.min * scale).round().max(Vec2::ZERO).as_uvec2();
+    let max = (region.max * scale).round().max(Vec2::ZERO).as_uvec2();
+    URect::from_corners(min, max).intersect(URect::from_corners(UVec2::ZERO, size))
+}
+
+// Runs every frame, so a resized window or a changed scale factor moves the
+// clip without a new set_render_region
+fn clip_main_camera(
+    region: Option<Res<RenderRegion>>,
+    tauri_app: NonSend<tauri::AppHandle>,
+    render_window: Res<RenderWindow>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(Entity, &mut Camera), With<Camera3d>>,
+    mut clipped: Local<Option<Clipped>>,
+) {
+    let main = cameras
+        .iter()
+        .min_by_key(|(_, camera)| camera.order)
+        .map(|(entity, _)| entity);
+    let scale_factor = || {
+        render_window
+            .get(&tauri_app)
+            .and_then(|window| window.scale_factor().ok())
+    };
+    let target = match (region, main, windows.get_single()) {
+        (Some(region), Some(camera), Ok(window)) => scale_factor().map(|scale| {
+            let size = window.physical_size();
+            (camera, physical_rect(region.0, scale as f32, size), size)
+        }),
+        _ => None,
+    };
+    if target == *clipped {
+        return;
+    }
+    // the main camera changed or the region was cleared
+    if let Some((entity, ..)) = *clipped {
+        if target.is_none_or(|(camera, ..)| camera != entity) {
+            if let Ok((_, mut camera)) = cameras.get_mut(entity) {
+                camera.viewport = None;
+                camera.sub_camera_view = None;
+                camera.is_active = true;
+            }
+        }
+    }
+    *clipped = target;
+    let Some((entity, rect, window_size)) = target else {
+        return;
+    };
+    let Ok((_, mut camera)) = cameras.get_mut(entity) else {
+        return;
+    };
+    // a region scrolled out of the window leaves nothing to draw
+    camera.is_active = !rect.is_empty();
+    if rect.is_empty() {
+        return;
+    }
+    camera.viewport = Some(Viewport {
+        physical_position: rect.min,
+        physical_size: rect.size(),
+        ..default()
+    });
+    camera.sub_camera_view = Some(SubCameraView {
+        full_size: window_size,
+        offset: rect.min.as_vec2(),
+        size: rect.size(),
+    });
+}
+
+// Renders the main camera only inside `x`, `y`, `width`, `height` logical
+// pixels of the window, see RenderRegionPlugin. Call it again whenever the
+// element moves or resizes, e.g. on scroll and from a ResizeObserver.
+#[tauri::command]
+pub async fn set_render_region(
+    bridge: State<'_, BevyBridge>,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+) -> Result<(), CommandError> {
+    let finite = [x, y, width, height].iter().all(|value| value.is_finite());
+    if !finite || width < 0.0 || height < 0.0 {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid render region {}, {} {}x{}",
+            x, y, width, height
+        )));
+    }
+    let region = RenderRegion(Rect::new(x, y, x + width, y + height));
+    bridge.run(move |world| world.insert_resource(region)).await
+}
+
+// Renders the main camera across the whole window again
+#[tauri::command]
+pub async fn clear_render_region(bridge: State<'_, BevyBridge>) -> Result<(), CommandError> {
+    bridge
+        .run(|world| {
+            world.remove_resource::<RenderRegion>();
+        })
+        .await
+}
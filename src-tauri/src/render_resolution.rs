@@ -0,0 +1,127 @@
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+use bevy::render::view::RenderLayers;
+use bevy::window::WindowRef;
+
+use crate::scene::Persistent;
+
+// Renders the primary window's cameras into an image of a fixed size, which a
+// 2d camera scales to fit the window with black bars on the sides that don't
+// match its aspect ratio. Unlike DynamicResolution the size never changes.
+// Pointer positions stay in window coordinates, so picking and pick_color_at
+// don't see the scaled frame, and it can't be combined with OffscreenStream.
+//
+// Enable it with TauriPlugin::with_render_resolution.
+#[derive(Clone, Copy)]
+pub struct RenderResolution {
+    pub width: u32,
+    pub height: u32,
+    // sample the image without filtering, for pixel art
+    pub nearest: bool,
+}
+
+impl RenderResolution {
+    // Parses the `WIDTHxHEIGHT` value of `--resolution`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (width, height) = value
+            .split_once('x')
+            .ok_or_else(|| format!("resolution {} isn't WIDTHxHEIGHT", value))?;
+        let dimension = |text: &str| match text.trim().parse::<u32>() {
+            Ok(dimension) if dimension > 0 => Ok(dimension),
+            _ => Err(format!("invalid resolution {}", value)),
+        };
+        Ok(Self {
+            width: dimension(width)?,
+            height: dimension(height)?,
+            nearest: true,
+        })
+    }
+}
+
+#[derive(Resource)]
+struct ResolutionTarget(Handle<Image>);
+
+// Shows the fixed size image on the window
+#[derive(Component)]
+struct BlitCamera;
+
+// Keeps the blit sprite away from other 2d cameras
+const BLIT_LAYER: usize = 31;
+
+// An image cameras can render into and a sprite can show, also used by
+// DynamicResolution
+pub fn target_image(size: UVec2, nearest: bool) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: size.x.max(1),
+            height: size.y.max(1),
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT;
+    if nearest {
+        image.sampler = ImageSampler::nearest();
+    }
+    image
+}
+
+impl Plugin for RenderResolution {
+    fn build(&self, app: &mut App) {
+        let image = target_image(UVec2::new(self.width, self.height), self.nearest);
+        let resolution = image.size().as_vec2();
+        let image = app.world_mut().resource_mut::<Assets<Image>>().add(image);
+
+        app.world_mut().spawn((
+            Camera2d,
+            Camera {
+                // after every camera that renders into the image, which all
+                // keep their own order, e.g. the sprite overlay's
+                order: isize::MAX,
+                clear_color: ClearColorConfig::Custom(Color::BLACK),
+                ..default()
+            },
+            // always shows the whole image, leaving bars where the aspect ratio differs
+            OrthographicProjection {
+                scaling_mode: ScalingMode::AutoMin {
+                    min_width: resolution.x,
+                    min_height: resolution.y,
+                },
+                ..OrthographicProjection::default_2d()
+            },
+            RenderLayers::layer(BLIT_LAYER),
+            BlitCamera,
+            Persistent,
+        ));
+        app.world_mut().spawn((
+            Sprite {
+                image: image.clone(),
+                custom_size: Some(resolution),
+                ..default()
+            },
+            RenderLayers::layer(BLIT_LAYER),
+            Persistent,
+        ));
+
+        app.insert_resource(ResolutionTarget(image))
+            .add_systems(PostUpdate, redirect_cameras);
+    }
+}
+
+fn redirect_cameras(
+    target: Res<ResolutionTarget>,
+    mut cameras: Query<&mut Camera, Without<BlitCamera>>,
+) {
+    for mut camera in cameras.iter_mut() {
+        if matches!(camera.target, RenderTarget::Window(WindowRef::Primary)) {
+            camera.target = RenderTarget::Image(target.0.clone());
+        }
+    }
+}
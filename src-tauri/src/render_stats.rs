@@ -0,0 +1,67 @@
+use bevy::prelude::*;
+use bevy::render::view::VisibilitySystems;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tauri::{Manager, State};
+
+// Counts over every entity that takes part in visibility checks, i.e. has a
+// ViewVisibility. All zero until the first frame has been checked.
+#[derive(Serialize, Clone, Copy, Default, Debug)]
+pub struct RenderStats {
+    pub total_entities: usize,
+    // seen by at least one camera
+    pub visible_entities: usize,
+    // Visibility::Hidden or a hidden parent
+    pub hidden_entities: usize,
+    // visible in the hierarchy but outside every camera frustum
+    pub culled_entities: usize,
+    pub cameras: usize,
+}
+
+// Last counts, shared with `get_render_stats` so it answers without a round
+// trip to the world
+#[derive(Resource, Clone, Default)]
+pub struct RenderStatsStore(Arc<Mutex<RenderStats>>);
+
+// Needs the tauri::AppHandle inserted by TauriPlugin, so add it after that one
+pub struct RenderStatsPlugin;
+
+impl Plugin for RenderStatsPlugin {
+    fn build(&self, app: &mut App) {
+        let store = RenderStatsStore::default();
+        app.world()
+            .non_send_resource::<tauri::AppHandle>()
+            .manage(store.clone());
+        app.insert_resource(store).add_systems(
+            PostUpdate,
+            collect_render_stats.after(VisibilitySystems::CheckVisibility),
+        );
+    }
+}
+
+fn collect_render_stats(
+    store: Res<RenderStatsStore>,
+    entities: Query<(&ViewVisibility, &InheritedVisibility)>,
+    cameras: Query<&Camera>,
+) {
+    let mut stats = RenderStats {
+        cameras: cameras.iter().filter(|camera| camera.is_active).count(),
+        ..default()
+    };
+    for (view_visibility, inherited_visibility) in entities.iter() {
+        stats.total_entities += 1;
+        if view_visibility.get() {
+            stats.visible_entities += 1;
+        } else if inherited_visibility.get() {
+            stats.culled_entities += 1;
+        } else {
+            stats.hidden_entities += 1;
+        }
+    }
+    *store.0.lock().unwrap() = stats;
+}
+
+#[tauri::command]
+pub fn get_render_stats(store: State<'_, RenderStatsStore>) -> RenderStats {
+    *store.0.lock().unwrap()
+}
@@ -0,0 +1,28 @@
+use bevy::log::warn;
+use std::path::PathBuf;
+
+// File that exists while the bevy app runs. Found at launch, it means the last
+// run crashed or was killed, e.g. by a GPU driver reset, rather than exiting.
+pub struct RunSentinel {
+    path: PathBuf,
+}
+
+impl RunSentinel {
+    // Creates the sentinel, returns whether the last run left one behind
+    pub fn create() -> (Self, bool) {
+        let path = std::env::temp_dir().join(concat!(env!("CARGO_PKG_NAME"), ".running"));
+        let crashed = path.exists();
+        if let Err(err) = std::fs::write(&path, std::process::id().to_string()) {
+            warn!("failed to write {}: {}", path.display(), err);
+        }
+        (Self { path }, crashed)
+    }
+
+    // Called once the app returns, also with an error code, not on drop, so a
+    // panic or a kill starts the next run in safe mode
+    pub fn remove(self) {
+        if let Err(err) = std::fs::remove_file(&self.path) {
+            warn!("failed to remove {}: {}", self.path.display(), err);
+        }
+    }
+}
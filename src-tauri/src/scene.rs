@@ -0,0 +1,395 @@
+use bevy::ecs::entity::EntityHashMap;
+use bevy::ecs::system::SystemId;
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectSerializer;
+use bevy::reflect::TypeRegistry;
+use bevy::scene::ron;
+use bevy::scene::serde::SceneDeserializer;
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::camera::{self, CameraPose};
+use crate::environment::SceneSettings;
+use crate::picking;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum LightKind {
+    Point,
+    Directional,
+    Spot,
+}
+
+pub fn light_entity(world: &World, id: u64) -> Result<Entity, CommandError> {
+    Entity::try_from_bits(id)
+        .ok()
+        .filter(|&entity| {
+            world.get_entity(entity).is_ok_and(|entity| {
+                entity.contains::<PointLight>()
+                    || entity.contains::<DirectionalLight>()
+                    || entity.contains::<SpotLight>()
+            })
+        })
+        .ok_or_else(|| CommandError::EntityNotFound(format!("no light with id {}", id)))
+}
+
+pub fn entity_by_id(world: &World, id: u64) -> Result<Entity, CommandError> {
+    Entity::try_from_bits(id)
+        .ok()
+        .filter(|&entity| world.get_entity(entity).is_ok())
+        .ok_or_else(|| CommandError::EntityNotFound(format!("no entity with id {}", id)))
+}
+
+// Resolves `target` as an entity id first and by `Name` otherwise
+pub fn resolve_target(world: &mut World, target: &str) -> Option<Entity> {
+    let by_id = target
+        .parse::<u64>()
+        .ok()
+        .and_then(|bits| Entity::try_from_bits(bits).ok())
+        .filter(|&entity| world.get_entity(entity).is_ok());
+    by_id.or_else(|| {
+        world
+            .query::<(Entity, &Name)>()
+            .iter(world)
+            .find(|(_, name)| name.as_str() == target)
+            .map(|(entity, _)| entity)
+    })
+}
+
+// The world side of the mutation commands below, shared with batch::commit_batch
+
+pub fn spawn_light(
+    world: &mut World,
+    kind: LightKind,
+    position: Vec3,
+    intensity: f32,
+    color: [f32; 3],
+) -> u64 {
+    let color = Color::srgb(color[0], color[1], color[2]);
+    let intensity = intensity.max(0.0);
+    let transform = Transform::from_translation(position).looking_at(Vec3::ZERO, Vec3::Y);
+
+    let mut entity = world.spawn(transform);
+    match kind {
+        LightKind::Point => entity.insert(PointLight {
+            color,
+            intensity,
+            ..default()
+        }),
+        LightKind::Directional => entity.insert(DirectionalLight {
+            color,
+            illuminance: intensity,
+            ..default()
+        }),
+        LightKind::Spot => entity.insert(SpotLight {
+            color,
+            intensity,
+            ..default()
+        }),
+    };
+    entity.id().to_bits()
+}
+
+pub fn despawn_light(world: &mut World, id: u64) -> Result<(), CommandError> {
+    let entity = light_entity(world, id)?;
+    world.entity_mut(entity).despawn_recursive();
+    Ok(())
+}
+
+pub fn configure_point_light(
+    world: &mut World,
+    target: &str,
+    intensity: f32,
+    range: f32,
+) -> Result<(), CommandError> {
+    let entity = resolve_target(world, target)
+        .ok_or_else(|| CommandError::EntityNotFound(format!("no entity {}", target)))?;
+    let mut light = world
+        .get_mut::<PointLight>(entity)
+        .ok_or_else(|| CommandError::InvalidArgument(format!("{} is not a point light", target)))?;
+    light.intensity = intensity.max(0.0);
+    light.range = range.max(0.0);
+    Ok(())
+}
+
+pub fn recolor_entity(
+    world: &mut World,
+    entity_id: u64,
+    color: [f32; 3],
+) -> Result<(), CommandError> {
+    let entity = entity_by_id(world, entity_id)?;
+    let handle = picking::base_material(world, entity)
+        .ok_or_else(|| format!("entity {} has no StandardMaterial", entity_id))?;
+    let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+    let mut material = materials
+        .get(&handle)
+        .cloned()
+        .ok_or_else(|| format!("material of entity {} is not loaded", entity_id))?;
+    let alpha = material.base_color.alpha();
+    material.base_color = Color::srgba(color[0], color[1], color[2], alpha);
+    let material = materials.add(material);
+    picking::set_base_material(world, entity, material);
+    Ok(())
+}
+
+pub fn write_transform(
+    world: &mut World,
+    entity_id: u64,
+    pos: [f32; 3],
+    rot: [f32; 3],
+    scale: [f32; 3],
+) -> Result<(), CommandError> {
+    if !pos.iter().chain(&rot).chain(&scale).all(|value| value.is_finite()) {
+        return Err(CommandError::InvalidArgument(String::from(
+            "position, rotation and scale must be finite",
+        )));
+    }
+    let [x, y, z] = rot.map(f32::to_radians);
+    let entity = entity_by_id(world, entity_id)?;
+    let mut transform = world
+        .get_mut::<Transform>(entity)
+        .ok_or_else(|| format!("entity {} has no Transform", entity_id))?;
+    *transform = Transform {
+        translation: Vec3::from_array(pos),
+        rotation: Quat::from_euler(EulerRot::YXZ, y, x, z),
+        scale: Vec3::from_array(scale),
+    };
+    Ok(())
+}
+
+// Point and spot lights use `intensity` as luminous power in lumens (the demo
+// light is 500_000), directional lights use it as illuminance in lux.
+// Directional and spot lights are aimed at the origin.
+#[tauri::command]
+pub async fn add_light(
+    bridge: State<'_, BevyBridge>,
+    kind: LightKind,
+    x: f32,
+    y: f32,
+    z: f32,
+    intensity: f32,
+    color: [f32; 3],
+) -> Result<u64, CommandError> {
+    bridge
+        .run(move |world| spawn_light(world, kind, Vec3::new(x, y, z), intensity, color))
+        .await
+}
+
+#[tauri::command]
+pub async fn remove_light(bridge: State<'_, BevyBridge>, id: u64) -> Result<(), CommandError> {
+    bridge.run(move |world| despawn_light(world, id)).await?
+}
+
+// `target` is an entity id or name, negative values are clamped to zero
+#[tauri::command]
+pub async fn set_point_light(
+    bridge: State<'_, BevyBridge>,
+    target: String,
+    intensity: f32,
+    range: f32,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| configure_point_light(world, &target, intensity, range))
+        .await?
+}
+
+// Recolors the StandardMaterial of a mesh, keeping its alpha. The material is
+// copied first, so other meshes sharing it keep their color. r, g and b are
+// sRGB components in 0..=1.
+#[tauri::command]
+pub async fn set_entity_color(
+    bridge: State<'_, BevyBridge>,
+    entity_id: u64,
+    r: f32,
+    g: f32,
+    b: f32,
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| recolor_entity(world, entity_id, [r, g, b]))
+        .await?
+}
+
+// Local transform of an entity, relative to its parent. `rotation` is in
+// degrees, applied in Y, X, Z order like in scene manifests.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct TransformData {
+    pub position: [f32; 3],
+    pub rotation: [f32; 3],
+    pub scale: [f32; 3],
+}
+
+#[tauri::command]
+pub async fn get_transform(
+    bridge: State<'_, BevyBridge>,
+    entity_id: u64,
+) -> Result<TransformData, CommandError> {
+    bridge
+        .run(move |world| {
+            let entity = entity_by_id(world, entity_id)?;
+            let transform = world
+                .get::<Transform>(entity)
+                .ok_or_else(|| format!("entity {} has no Transform", entity_id))?;
+            let (y, x, z) = transform.rotation.to_euler(EulerRot::YXZ);
+            Ok(TransformData {
+                position: transform.translation.to_array(),
+                rotation: [x, y, z].map(f32::to_degrees),
+                scale: transform.scale.to_array(),
+            })
+        })
+        .await?
+}
+
+// Takes the values of get_transform
+#[tauri::command]
+pub async fn set_transform(
+    bridge: State<'_, BevyBridge>,
+    entity_id: u64,
+    pos: [f32; 3],
+    rot: [f32; 3],
+    scale: [f32; 3],
+) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| write_transform(world, entity_id, pos, rot, scale))
+        .await?
+}
+
+// Drops components that are reflected but can't be serialized, asset handles
+// being the common case, so the rest of the entity can still be saved
+fn retain_serializable(scene: &mut DynamicScene, registry: &TypeRegistry) {
+    for entity in &mut scene.entities {
+        entity.components.retain(|component| {
+            ron::to_string(&TypedReflectSerializer::new(component.as_ref(), registry)).is_ok()
+        });
+    }
+}
+
+// Saves every entity with a Transform that isn't Persistent (windows,
+// pointers, observers and cameras are left out) to a `.scene.ron` file.
+// Components that aren't reflected or can't be serialized are skipped, which
+// includes mesh and material handles.
+#[tauri::command]
+pub async fn save_scene(bridge: State<'_, BevyBridge>, path: String) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let entities: Vec<Entity> = world
+                .query_filtered::<Entity, (With<Transform>, Without<Persistent>)>()
+                .iter(world)
+                .collect();
+            let mut scene = DynamicSceneBuilder::from_world(world)
+                .extract_entities(entities.into_iter())
+                .build();
+
+            let registry = world.resource::<AppTypeRegistry>().read();
+            retain_serializable(&mut scene, &registry);
+            let serialized = scene.serialize(&registry).map_err(|err| err.to_string())?;
+            std::fs::write(&path, serialized)
+                .map_err(|err| CommandError::Failed(format!("{}: {}", path, err)))
+        })
+        .await?
+}
+
+// Replaces the entities `save_scene` would save with the ones in the file,
+// keeping Persistent ones like the cameras
+#[tauri::command]
+pub async fn load_scene(bridge: State<'_, BevyBridge>, path: String) -> Result<(), CommandError> {
+    let serialized = std::fs::read_to_string(&path).map_err(|err| format!("{}: {}", path, err))?;
+
+    bridge
+        .run(move |world| {
+            let scene = {
+                let registry = world.resource::<AppTypeRegistry>().read();
+                let mut deserializer =
+                    ron::de::Deserializer::from_str(&serialized).map_err(|err| err.to_string())?;
+                SceneDeserializer {
+                    type_registry: &registry,
+                }
+                .deserialize(&mut deserializer)
+                .map_err(|err| err.to_string())?
+            };
+
+            let roots: Vec<Entity> = world
+                .query_filtered::<Entity, (With<Transform>, Without<Parent>, Without<Persistent>)>()
+                .iter(world)
+                .collect();
+            for entity in roots {
+                world.entity_mut(entity).despawn_recursive();
+            }
+
+            scene
+                .write_to_world(world, &mut EntityHashMap::default())
+                .map_err(|err| CommandError::Failed(err.to_string()))
+        })
+        .await?
+}
+
+// Kept by reset_scene and load_scene and not saved by save_scene, for
+// entities that aren't part of the scene like cameras
+#[derive(Component)]
+pub struct Persistent;
+
+// What reset_scene restores: the system spawning the scene at startup, and the
+// scene settings once it ran
+#[derive(Resource)]
+pub struct SceneSetup {
+    system: SystemId,
+    settings: Option<SceneSettings>,
+}
+
+impl SceneSetup {
+    pub fn new(system: SystemId) -> Self {
+        Self {
+            system,
+            settings: None,
+        }
+    }
+}
+
+// Startup system spawning the scene with the system in SceneSetup
+pub fn run_scene_setup(world: &mut World) {
+    let system = world.resource::<SceneSetup>().system;
+    if let Err(err) = world.run_system(system) {
+        error!("failed to set up the scene: {}", err);
+    }
+    let settings = world.resource::<SceneSettings>().clone();
+    world.resource_mut::<SceneSetup>().settings = Some(settings);
+}
+
+// Despawns every root entity with a Transform or Node that isn't Persistent,
+// so runtime lights, loaded scenes and UI text go too, then spawns the scene
+// again. Assets only those entities held on to are freed with their handles.
+fn reset(world: &mut World) -> Result<usize, CommandError> {
+    let roots: Vec<Entity> = world
+        .query_filtered::<Entity, (
+            Or<(With<Transform>, With<Node>)>,
+            Without<Parent>,
+            Without<Persistent>,
+        )>()
+        .iter(world)
+        .collect();
+    for &entity in &roots {
+        world.entity_mut(entity).despawn_recursive();
+    }
+
+    if let Ok(camera) = camera::main_camera(world) {
+        let transform = world.resource::<CameraPose>().transform();
+        world.entity_mut(camera).insert(transform);
+    }
+    let setup = world.resource::<SceneSetup>();
+    let system = setup.system;
+    if let Some(settings) = setup.settings.clone() {
+        world.insert_resource(settings);
+    }
+    world
+        .run_system(system)
+        .map_err(|err| format!("failed to set up the scene: {}", err))?;
+    info!("scene reset, despawned {} entities", roots.len());
+    Ok(roots.len())
+}
+
+// Puts the scene, the main camera's pose and SceneSettings back the way they
+// were after startup. Returns how many root entities were despawned.
+#[tauri::command]
+pub async fn reset_scene(bridge: State<'_, BevyBridge>) -> Result<usize, CommandError> {
+    bridge.run(reset).await?
+}
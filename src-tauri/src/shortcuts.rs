@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::bridge::{BevyBridge, CommandError};
+
+// Sent when an accelerator registered with `register_shortcut` is pressed
+#[derive(Event, Debug, Clone)]
+pub struct ShortcutTriggered {
+    pub name: String,
+}
+
+// Shortcuts are registered with the OS, so they fire whichever window has focus.
+// The hotkey events arrive through the tao event loop, which the bevy runner
+// keeps pumping, and are forwarded to the world through the bridge.
+//
+// Needs the tauri::AppHandle inserted by TauriPlugin, so add it after that one
+pub struct ShortcutPlugin;
+
+impl Plugin for ShortcutPlugin {
+    fn build(&self, app: &mut App) {
+        let tauri_app = app.world().non_send_resource::<tauri::AppHandle>();
+        if let Err(err) = tauri_app.plugin(tauri_plugin_global_shortcut::Builder::new().build()) {
+            error!("failed to initialize global shortcuts: {}", err);
+        }
+        app.add_event::<ShortcutTriggered>();
+    }
+}
+
+#[tauri::command]
+pub fn register_shortcut(
+    app: tauri::AppHandle,
+    accelerator: String,
+    event_name: String,
+) -> Result<(), CommandError> {
+    let shortcut: Shortcut = accelerator.parse().map_err(|err| {
+        CommandError::InvalidArgument(format!("invalid accelerator {}: {}", accelerator, err))
+    })?;
+    if app.global_shortcut().is_registered(shortcut) {
+        return Err(CommandError::InvalidArgument(format!(
+            "{} is already registered",
+            accelerator
+        )));
+    }
+
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, _, event| {
+            if event.state != ShortcutState::Pressed {
+                return;
+            }
+            let name = event_name.clone();
+            let bridge = app.state::<BevyBridge>();
+            if let Err(err) = bridge.send(move |world| {
+                world.send_event(ShortcutTriggered { name });
+            }) {
+                warn!("dropped shortcut {}: {}", event_name, err);
+            }
+        })
+        .map_err(|err| CommandError::Failed(format!("failed to register {}: {}", accelerator, err)))
+}
+
+#[tauri::command]
+pub fn unregister_shortcut(app: tauri::AppHandle, accelerator: String) -> Result<(), CommandError> {
+    let shortcut: Shortcut = accelerator.parse().map_err(|err| {
+        CommandError::InvalidArgument(format!("invalid accelerator {}: {}", accelerator, err))
+    })?;
+    app.global_shortcut().unregister(shortcut).map_err(|err| {
+        CommandError::Failed(format!("failed to unregister {}: {}", accelerator, err))
+    })
+}
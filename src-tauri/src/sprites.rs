@@ -0,0 +1,225 @@
+use bevy::core_pipeline::tonemapping::Tonemapping;
+use bevy::prelude::*;
+use bevy::render::camera::CameraUpdateSystem;
+use bevy::render::view::{RenderLayers, VisibilitySystems};
+use bevy::transform::TransformSystem;
+use std::time::Duration;
+use tauri::State;
+
+use crate::assets;
+use crate::bridge::{BevyBridge, CommandError};
+use crate::scene::{self, Persistent};
+
+// Keeps overlay sprites out of the 3d cameras and 3d gizmos out of the overlay
+const OVERLAY_LAYER: usize = 2;
+
+// Draws the overlay sprites over the main camera's picture, and bevy UI over both
+const OVERLAY_ORDER: isize = 1;
+
+#[derive(Component)]
+struct OverlayCamera;
+
+// Sprites drawn by the overlay camera, the only ones `remove_sprite` removes
+#[derive(Component)]
+pub struct OverlaySprite;
+
+// Where the sprite's center goes, in logical pixels from the top left of the window
+#[derive(Component, Clone, Copy)]
+pub struct ScreenPosition(pub Vec2);
+
+// Moves the sprite to where `entity` is on screen, ScreenPosition is then an
+// offset from there. Hidden while the entity is behind the camera and
+// despawned along with it.
+#[derive(Component, Clone, Copy)]
+pub struct AnchoredTo(pub Entity);
+
+pub struct SpriteOverlayPlugin;
+
+impl Plugin for SpriteOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_overlay_camera).add_systems(
+            PostUpdate,
+            (
+                match_main_camera,
+                place_sprites
+                    .after(TransformSystem::TransformPropagate)
+                    .after(CameraUpdateSystem)
+                    .before(VisibilitySystems::VisibilityPropagate),
+            ),
+        );
+    }
+}
+
+fn spawn_overlay_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: OVERLAY_ORDER,
+            // keeps the 3d picture underneath
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        // the 3d camera already tonemapped the texture both draw to
+        Tonemapping::None,
+        RenderLayers::layer(OVERLAY_LAYER),
+        OverlayCamera,
+        Persistent,
+    ));
+}
+
+// Cameras on the same target only share their texture with the same HDR and
+// MSAA settings, which the quality preset may change on the main camera
+#[allow(clippy::type_complexity)]
+fn match_main_camera(
+    main_cameras: Query<(&Camera, &Msaa), With<Camera3d>>,
+    mut overlay: Query<(&mut Camera, &mut Msaa), (With<OverlayCamera>, Without<Camera3d>)>,
+) {
+    let Some((main, main_msaa)) = main_cameras.iter().min_by_key(|(camera, _)| camera.order)
+    else {
+        return;
+    };
+    for (mut camera, mut msaa) in overlay.iter_mut() {
+        if camera.hdr != main.hdr {
+            camera.hdr = main.hdr;
+        }
+        if *msaa != *main_msaa {
+            *msaa = *main_msaa;
+        }
+    }
+}
+
+// Runs once transforms are propagated, so sprites anchored to an entity follow
+// it without lagging a frame behind
+#[allow(clippy::type_complexity)]
+fn place_sprites(
+    mut commands: Commands,
+    overlay: Query<&Camera, With<OverlayCamera>>,
+    main_cameras: Query<(&Camera, &GlobalTransform), (With<Camera3d>, Without<OverlaySprite>)>,
+    targets: Query<&GlobalTransform, Without<OverlaySprite>>,
+    mut sprites: Query<
+        (
+            Entity,
+            &ScreenPosition,
+            Option<&AnchoredTo>,
+            &mut Transform,
+            &mut GlobalTransform,
+            &mut Visibility,
+        ),
+        With<OverlaySprite>,
+    >,
+) {
+    let Some(size) = overlay
+        .get_single()
+        .ok()
+        .and_then(|camera| camera.logical_viewport_size())
+    else {
+        return;
+    };
+    let main_camera = main_cameras.iter().min_by_key(|(camera, _)| camera.order);
+
+    for (entity, position, anchor, mut transform, mut global, mut visibility) in &mut sprites {
+        let mut screen = position.0;
+        if let Some(anchor) = anchor {
+            let Ok(target) = targets.get(anchor.0) else {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            };
+            let projected = main_camera.and_then(|(camera, camera_transform)| {
+                camera
+                    .world_to_viewport(camera_transform, target.translation())
+                    .ok()
+            });
+            let Some(projected) = projected else {
+                *visibility = Visibility::Hidden;
+                continue;
+            };
+            screen += projected;
+            *visibility = Visibility::Inherited;
+        }
+        // the 2d camera looks at the center of the window with y up
+        transform.translation.x = screen.x - size.x / 2.0;
+        transform.translation.y = size.y / 2.0 - screen.y;
+        *global = GlobalTransform::from(*transform);
+    }
+}
+
+// A red bar above `target`, the demo of anchored sprites
+pub fn health_bar(target: Entity) -> impl Bundle {
+    (
+        Sprite::from_color(Color::srgb(0.9, 0.2, 0.2), Vec2::new(60.0, 6.0)),
+        ScreenPosition(Vec2::new(0.0, -40.0)),
+        AnchoredTo(target),
+        RenderLayers::layer(OVERLAY_LAYER),
+        OverlaySprite,
+    )
+}
+
+// Draws the image at `path` in the assets folder over the 3d scene, centered at
+// `x`, `y` logical pixels from the top left of the window. With `follow`, a
+// Name or entity id, the sprite tracks that entity on screen and `x`, `y` are
+// an offset from it. Returns the sprite's id.
+#[tauri::command]
+pub async fn spawn_sprite(
+    bridge: State<'_, BevyBridge>,
+    path: String,
+    x: f32,
+    y: f32,
+    scale: f32,
+    follow: Option<String>,
+) -> Result<u64, CommandError> {
+    if !x.is_finite() || !y.is_finite() {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid position {}, {}",
+            x, y
+        )));
+    }
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid scale {}",
+            scale
+        )));
+    }
+    let timeout = Duration::from_millis(assets::DEFAULT_TIMEOUT_MS);
+    let image = assets::load::<Image>(&bridge, path, timeout).await?;
+
+    bridge
+        .run(move |world| {
+            let anchor =
+                match follow {
+                    Some(name) => Some(scene::resolve_target(world, &name).ok_or_else(|| {
+                        CommandError::EntityNotFound(format!("no entity {}", name))
+                    })?),
+                    None => None,
+                };
+            let mut sprite = world.spawn((
+                Sprite::from_image(image),
+                Transform::from_scale(Vec3::splat(scale)),
+                ScreenPosition(Vec2::new(x, y)),
+                RenderLayers::layer(OVERLAY_LAYER),
+                OverlaySprite,
+            ));
+            if let Some(anchor) = anchor {
+                sprite.insert(AnchoredTo(anchor));
+            }
+            Ok(sprite.id().to_bits())
+        })
+        .await?
+}
+
+#[tauri::command]
+pub async fn remove_sprite(bridge: State<'_, BevyBridge>, id: u64) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let entity = Entity::try_from_bits(id)
+                .ok()
+                .filter(|&entity| {
+                    world
+                        .get_entity(entity)
+                        .is_ok_and(|entity| entity.contains::<OverlaySprite>())
+                })
+                .ok_or_else(|| CommandError::EntityNotFound(format!("no sprite with id {}", id)))?;
+            world.entity_mut(entity).despawn_recursive();
+            Ok(())
+        })
+        .await?
+}
@@ -1,106 +1,1035 @@
 use bevy::app::Plugin;
 use bevy::app::PluginsState;
 
-use bevy::ecs::system::SystemState;
+use bevy::ecs::schedule::{InternedScheduleLabel, ScheduleLabel};
+use bevy::ecs::system::{RunSystemOnce, SystemId, SystemState};
 use bevy::prelude::*;
 use bevy::render::renderer::*;
 use bevy::render::settings::{RenderCreation, WgpuSettings};
-use bevy::render::RenderPlugin;
+use bevy::render::render_resource::TextureFormat;
+use bevy::render::view::ExtractedWindows;
+use bevy::render::pipelined_rendering::RenderExtractApp;
+use bevy::render::{Render, RenderApp, RenderPlugin, RenderSet};
 use bevy::tasks::tick_global_task_pools_on_main_thread;
 use bevy::window::{
-    RawHandleWrapper, RawHandleWrapperHolder, WindowResized, WindowResolution,
-    WindowScaleFactorChanged, WindowWrapper,
+    PrimaryWindow, RawHandleWrapper, RawHandleWrapperHolder, WindowCreated, WindowFocused,
+    WindowResizeConstraints, WindowResized, WindowScaleFactorChanged, WindowWrapper,
 };
-use std::cell::{RefCell, RefMut};
+use std::cell::{Cell, RefCell, RefMut};
 use std::rc::Rc;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
+use serde::Serialize;
 use std::time::{Duration, Instant};
-use tauri::{async_runtime::block_on, Manager};
-use tauri::{ RunEvent, WebviewWindow};
+use tauri::{async_runtime::block_on, Emitter, Manager};
+use tauri::{webview::PageLoadEvent, RunEvent, WebviewWindow};
+use wgpu::rwh::{HasDisplayHandle, HasWindowHandle};
 use wgpu::RequestAdapterOptions;
 
+use crate::adapters::{self, AdapterList, AdapterSelection};
+use crate::adaptive_vsync::{AdaptiveVsync, AdaptiveVsyncState};
+use crate::bridge::{self, apply_world_callbacks};
+use crate::diagnostics::{self, RendererStatus, SurfaceInfo};
+use crate::dynamic_resolution::DynamicResolution;
+use crate::frame_limiter::{
+    self, CpuThrottle, CpuThrottleStore, FrameLimiter, LatencyPacer, RenderFpsCap, SleepLimiter,
+    SurfaceWait, SurfaceWaitPlugin, RENDER_ENABLED, SIMULATION_ENABLED,
+};
+use crate::frame_stats;
+use crate::offscreen::{OffscreenStream, OffscreenSubscribers};
+use crate::one_shot::NamedSystems;
+use crate::frame_sync::{fail_paused_frame_waiters, notify_frame_waiters, FrameWaiters};
+use crate::latency::{self, LatencyProbe};
+#[cfg(desktop)]
+use crate::menu::{self, MenuBindings, MenuFactory, MenuItemTriggered};
+#[cfg(desktop)]
+use crate::tray;
+use crate::input_reset::{reset_input_on, InputResetTriggers};
+use crate::picking::PickingBackends;
+use crate::power::{self, PowerProfile, PowerStatus, PowerStore};
+use crate::quality::{apply_depth_prepass, apply_quality, DepthPrepassEnabled, QualityPreset};
+use crate::render_resolution::RenderResolution;
+use crate::watchdog::{self, RunnerPhase};
+use crate::webview_region::{self, WebviewRegion};
+use crate::window::SizeConstraints;
+
+// Bevy's renderer for the surface made by create_renderer
 struct CustomRendererPlugin {
-    webview_window: WebviewWindow,
+    // taken in build, since RenderCreation can't be cloned
+    render_creation: Mutex<Option<RenderCreation>>,
 }
 
+// Settings for bevy's wgpu instance, set with TauriPlugin::with_backends and
+// with_instance_descriptor
+#[derive(Resource, Default)]
+struct RendererInstance(wgpu::InstanceDescriptor);
+
+// Inserted by with_safe_mode
+#[derive(Resource)]
+struct SafeMode;
+
+// Inserted by without_audio
+#[derive(Resource)]
+struct AudioDisabled;
+
+// Inserted by with_renderer_error_screen
+#[derive(Resource)]
+struct RendererErrorScreen;
+
+// Inserted when the renderer failed to start while keeping the app open for
+// RendererErrorScreen
+#[derive(Resource)]
+struct RendererFailed;
+
+// Inserted by with_adapter
+#[derive(Resource)]
+struct ForcedAdapter(AdapterSelection);
+
 impl Plugin for CustomRendererPlugin {
     fn build(&self, app: &mut App) {
-        let instance = wgpu::Instance::default();
-        let surface = instance.create_surface(&self.webview_window).unwrap();
+        let render_creation = self
+            .render_creation
+            .lock()
+            .unwrap()
+            .take()
+            .expect("CustomRendererPlugin is only built once");
+        app.add_plugins(RenderPlugin {
+            render_creation,
+            ..default()
+        });
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(Render, log_surface_format.after(RenderSet::ManageViews));
+        }
+    }
+}
+
+// Bevy's wgpu device and queue for wgpu code of your own, e.g. compute passes
+// on bevy's buffers, managed by tauri once the renderer exists; see
+// shared_renderer. Inside bevy they are the RenderDevice and RenderQueue
+// resources of either world.
+//
+// Nothing orders this work with bevy's frames. With pipelined rendering bevy
+// submits from the render thread while the main world updates, so a write
+// submitted here shows up in some later frame, not a particular one. Don't
+// write to textures or buffers bevy renders with, it may be using them in a
+// frame in flight; give bevy a copy instead. Waiting with
+// device.poll(Maintain::Wait) blocks until bevy's work is done as well, do
+// that off the main thread.
+#[allow(dead_code)]
+#[derive(Clone)]
+pub struct SharedRenderer {
+    pub device: RenderDevice,
+    pub queue: RenderQueue,
+    pub adapter: RenderAdapter,
+}
 
-        let (device, queue, adapter_info, adapter) = block_on(initialize_renderer(
+// None until the renderer is created on RunEvent::Ready
+#[allow(dead_code)]
+pub fn shared_renderer(app: &tauri::AppHandle) -> Option<SharedRenderer> {
+    app.try_state::<SharedRenderer>()
+        .map(|renderer| renderer.inner().clone())
+}
+
+// Sets up wgpu for the webview window. Fails rather than panics when there is
+// no surface or no adapter for it, so the app can exit with an error code.
+// `status` records how far it got either way.
+fn create_renderer(
+    window: &WebviewWindow,
+    mut descriptor: wgpu::InstanceDescriptor,
+    force_fallback_adapter: bool,
+    force_adapter: Option<AdapterSelection>,
+    status: &mut RendererStatus,
+) -> Result<RenderCreation, String> {
+    // WGPU_BACKEND=gl etc. overrides the configured backends, like in wgpu's examples
+    if let Some(backends) = wgpu::util::backend_bits_from_env() {
+        descriptor.backends = backends;
+    }
+    bevy::log::info!("creating the wgpu instance with {:?}", descriptor);
+    let backends = descriptor.backends;
+    let instance = wgpu::Instance::new(descriptor);
+    let surface = instance
+        .create_surface(window)
+        .map_err(|err| format!("failed to create the window surface: {}", err))?;
+    status.surface = true;
+    let available = instance.enumerate_adapters(backends);
+    let mut adapter_list = AdapterList::new(&available, &surface);
+    let listed: Vec<_> = available.iter().map(|adapter| adapter.get_info()).collect();
+
+    let mut options = RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::default(),
+        force_fallback_adapter,
+        compatible_surface: Some(&surface),
+    };
+    let (device, queue, adapter_info, adapter) = if let Some(selection) = force_adapter {
+        let index = adapter_list.find(&selection)?;
+        if force_fallback_adapter {
+            bevy::log::warn!("rendering with the forced adapter, not a fallback one");
+        }
+        status.found_adapter(&listed[index]);
+        block_on(adapters::initialize_renderer(
+            available.into_iter().nth(index).unwrap(),
+        ))?
+    } else {
+        let mut adapter = block_on(instance.request_adapter(&options));
+        if adapter.is_none() && force_fallback_adapter {
+            bevy::log::warn!("no fallback adapter can render to the window, trying the others");
+            options.force_fallback_adapter = false;
+            adapter = block_on(instance.request_adapter(&options));
+        }
+        // initialize_renderer panics without an adapter
+        let Some(adapter) = adapter else {
+            return Err(String::from(
+                "no GPU adapter can render to the window surface",
+            ));
+        };
+        status.found_adapter(&adapter.get_info());
+        block_on(initialize_renderer(
             &instance,
             &WgpuSettings::default(),
-            &RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            },
-        ));
+            &options,
+        ))
+    };
+    status.device = true;
+    // request_adapter returns an adapter of its own, found in the list by its info
+    for (listed, info) in listed.iter().zip(adapter_list.0.iter_mut()) {
+        info.selected = *listed == **adapter_info;
+    }
 
-        app.add_plugins(RenderPlugin {
-            render_creation: RenderCreation::Manual(
-                device,
-                queue,
-                adapter_info,
-                adapter,
-                RenderInstance(Arc::new(WgpuWrapper::new(instance))),
-            ),
-            ..default()
-        });
+    bevy::log::info!(
+        "rendering with {:?} on {}",
+        adapter_info.backend,
+        adapter_info.name
+    );
+
+    let capabilities = surface.get_capabilities(&adapter);
+    diagnostics::store_surface_info(window, SurfaceInfo::new(&capabilities));
+    window.manage(adapter_list);
+    drop(surface);
+    window.manage(SharedRenderer {
+        device: device.clone(),
+        queue: queue.clone(),
+        adapter: adapter.clone(),
+    });
+
+    Ok(RenderCreation::Manual(
+        device,
+        queue,
+        adapter_info,
+        adapter,
+        RenderInstance(Arc::new(WgpuWrapper::new(instance))),
+    ))
+}
+
+// Bevy picks the surface format itself, an sRGB one when the surface offers it,
+// so colors come out right. Logged since a linear fallback looks too dark.
+// There is no override like the triangle demo's: bevy_render configures the
+// surface in create_surfaces from formats it chooses, and WindowSurfaces, which
+// holds the configuration, is private.
+fn log_surface_format(windows: Res<ExtractedWindows>, mut logged: Local<Option<TextureFormat>>) {
+    let format = windows
+        .primary
+        .and_then(|primary| windows.get(&primary))
+        .and_then(|window| window.swap_chain_texture_format);
+    if let Some(chosen) = format.filter(|&chosen| Some(chosen) != *logged) {
+        bevy::log::info!("bevy renders to {:?}", chosen);
+        *logged = format;
+    }
+}
+
+// Marks windows that WindowCreated was sent for, so recreating the surface
+// doesn't announce them again
+#[derive(Component)]
+struct WindowCreatedSent;
+
+type WindowHandleData = (
+    Entity,
+    Option<&'static RawHandleWrapperHolder>,
+    Has<WindowCreatedSent>,
+);
+
+// Label of the tauri window bevy renders to, given to TauriPlugin::new. Also
+// managed by tauri, for commands that act on that window.
+#[derive(Resource, Clone)]
+pub struct RenderWindow(pub String);
+
+impl RenderWindow {
+    pub fn get(&self, app: &tauri::AppHandle) -> Option<WebviewWindow> {
+        app.get_webview_window(&self.0)
     }
 }
 
+// Also sends the WindowCreated that bevy_winit would send, since some plugins
+// set themselves up from it
 fn create_window_handle(
-    mut commands: Commands,
-    query: Query<(Entity, Option<&'static RawHandleWrapperHolder>)>,
+    commands: Commands,
+    query: Query<WindowHandleData, With<PrimaryWindow>>,
     tauri_app: NonSend<tauri::AppHandle>,
+    render_window: Res<RenderWindow>,
+    window_created: EventWriter<WindowCreated>,
 ) {
-    let tauri_window = tauri_app.get_webview_window("main").unwrap();
-    let window_wrapper = WindowWrapper::new(tauri_window);
+    let Some(tauri_window) = render_window.get(&tauri_app) else {
+        bevy::log::error!("no window labeled {} to render to", render_window.0);
+        return;
+    };
+    attach_window_handle(
+        &WindowWrapper::new(tauri_window),
+        commands,
+        query,
+        window_created,
+    );
+}
 
-    for (entity, handle_holder) in query.iter() {
-        if let Ok(handle_wrapper) = RawHandleWrapper::new(&window_wrapper) {
+// Hands the primary window the handles of any window, not only a tauri one
+fn attach_window_handle<W: HasWindowHandle + HasDisplayHandle + 'static>(
+    window_wrapper: &WindowWrapper<W>,
+    mut commands: Commands,
+    query: Query<WindowHandleData, With<PrimaryWindow>>,
+    mut window_created: EventWriter<WindowCreated>,
+) {
+    for (entity, handle_holder, created_sent) in query.iter() {
+        if let Ok(handle_wrapper) = RawHandleWrapper::new(window_wrapper) {
             commands.entity(entity).insert(handle_wrapper.clone());
 
             if let Some(handle_holder) = handle_holder {
                 *handle_holder.0.lock().unwrap() = Some(handle_wrapper);
             }
+            if !created_sent {
+                commands.entity(entity).insert(WindowCreatedSent);
+                window_created.send(WindowCreated { window: entity });
+            }
         }
     }
 }
 
+// Drops the window surfaces and builds new ones from fresh window handles, e.g.
+// after a display hotplug or GPU reset left the old ones unusable. Removing the
+// RawHandleWrapper makes bevy destroy the surface during extraction, the next
+// frame creates a new one with the stored RenderInstance, configured from the
+// Window like the first one, so size, format and present mode stay the same.
+pub fn recreate_surface(world: &mut World) {
+    release_surface(world);
+    if let Err(err) = world.run_system_once(create_window_handle) {
+        bevy::log::error!("failed to recreate the window handle: {}", err);
+    }
+}
+
+// Drops the surface of the primary window without creating a new one, cameras
+// rendering to it are skipped until recreate_surface. Windows opened with
+// child_window::open_window keep theirs.
+pub fn release_surface(world: &mut World) {
+    let windows: Vec<Entity> = world
+        .query_filtered::<Entity, (With<RawHandleWrapper>, With<PrimaryWindow>)>()
+        .iter(world)
+        .collect();
+    for entity in windows {
+        world.entity_mut(entity).remove::<RawHandleWrapper>();
+    }
+    let mut holders = world.query_filtered::<&RawHandleWrapperHolder, With<PrimaryWindow>>();
+    for holder in holders.iter(world) {
+        *holder.0.lock().unwrap() = None;
+    }
+}
+
 pub struct TauriPlugin {
+    window_label: String,
     setup: Box<dyn Fn() -> tauri::App + Send + Sync>,
+    dynamic_resolution: Option<DynamicResolution>,
+    render_resolution: bool,
+    quality: Option<QualityPreset>,
+    event_handler: Option<Arc<TauriEventHandler>>,
+    on_frame: Mutex<Option<Box<FrameCallback>>>,
+    match_refresh_rate: bool,
+    depth_prepass: bool,
+    offscreen_stream: Option<OffscreenStream>,
+    frame_limiter: Mutex<Option<Box<dyn FrameLimiter>>>,
+    cpu_throttle: Option<CpuThrottle>,
+    render_plugins: Mutex<Vec<Box<RenderPluginHook>>>,
+    watchdog_timeout: Option<Duration>,
+    picking_backends: PickingBackends,
+    instance: Mutex<RendererInstance>,
+    size_constraints: Option<SizeConstraints>,
+    input_reset: InputResetTriggers,
+    close_to_tray: bool,
+    safe_mode: bool,
+    audio: bool,
+    force_adapter: Option<AdapterSelection>,
+    power_profile: Option<PowerProfile>,
+    renderer_error_screen: bool,
+    webview_region: Option<WebviewRegion>,
+    timed_schedules: Vec<TimedSchedule>,
+    adaptive_vsync: Option<AdaptiveVsync>,
+    systems: Mutex<Vec<(String, Box<SystemRegistration>)>>,
+    #[cfg(desktop)]
+    menu: Option<Box<MenuFactory>>,
+    #[cfg(desktop)]
+    menu_bindings: Mutex<MenuBindings>,
+}
+
+type TauriEventHandler = dyn Fn(&tauri::AppHandle, &RunEvent) + Send + Sync;
+type FrameCallback = dyn FnMut(&mut App) + Send;
+type RenderPluginHook = dyn FnOnce(&mut App) + Send + Sync;
+type SystemRegistration = dyn FnOnce(&mut World) -> SystemId + Send + Sync;
+
+// Plugins that need the render app, held until it exists on RunEvent::Ready
+#[derive(Resource)]
+struct PendingRenderPlugins(Vec<Box<RenderPluginHook>>);
+
+// Most runs a timed schedule catches up on in one runner iteration, time
+// beyond that is dropped after a long stall instead of running it in a burst
+const MAX_TIMED_CATCH_UP: u32 = 4;
+
+// A schedule the runner runs every `interval`, see with_timed_schedule
+#[derive(Clone)]
+struct TimedSchedule {
+    label: InternedScheduleLabel,
+    interval: Duration,
+    // time since the last run that hasn't been covered by one
+    accumulated: Duration,
+}
+
+impl TimedSchedule {
+    fn tick(&mut self, world: &mut World, elapsed: Duration) {
+        self.accumulated += elapsed;
+        let mut runs = 0;
+        while self.accumulated >= self.interval {
+            if runs == MAX_TIMED_CATCH_UP {
+                self.accumulated = Duration::ZERO;
+                break;
+            }
+            if let Err(err) = world.try_run_schedule(self.label) {
+                bevy::log::error!("failed to run a timed schedule: {}", err);
+            }
+            self.accumulated -= self.interval;
+            runs += 1;
+        }
+    }
 }
 
+// Everything the runner needs from the plugin configuration
+struct RunnerConfig {
+    event_handler: Option<Arc<TauriEventHandler>>,
+    on_frame: Option<Box<FrameCallback>>,
+    match_refresh_rate: bool,
+    frame_limiter: Box<dyn FrameLimiter>,
+    cpu_throttle: CpuThrottleStore,
+    render_fps_cap: RenderFpsCap,
+    watchdog_timeout: Option<Duration>,
+    close_to_tray: bool,
+    size_constraints: Option<SizeConstraints>,
+    window_label: String,
+    timed_schedules: Vec<TimedSchedule>,
+    webview_reloaded: Arc<AtomicBool>,
+    adaptive_vsync: Option<AdaptiveVsync>,
+    power: Option<PowerStore>,
+}
+
+// Inserted by with_close_to_tray, closing bevy's window then keeps the app running
+#[derive(Resource)]
+struct CloseToTray;
+
 impl TauriPlugin {
-    pub fn new<F>(setup: F) -> Self
+    // Bevy renders to the webview window labeled `window_label`, which `setup`
+    // has to create, e.g. "main" from tauri.conf.json. One configured with
+    // "create": false is created by the plugin instead.
+    pub fn new<F>(window_label: &str, setup: F) -> Self
     where
         F: Fn() -> tauri::App + Send + Sync + 'static,
     {
         Self {
+            window_label: window_label.to_string(),
             setup: Box::new(setup),
+            dynamic_resolution: None,
+            render_resolution: false,
+            quality: None,
+            event_handler: None,
+            on_frame: Mutex::new(None),
+            match_refresh_rate: false,
+            depth_prepass: false,
+            offscreen_stream: None,
+            frame_limiter: Mutex::new(None),
+            cpu_throttle: None,
+            render_plugins: Mutex::new(Vec::new()),
+            watchdog_timeout: None,
+            picking_backends: PickingBackends::default(),
+            instance: Mutex::new(RendererInstance::default()),
+            size_constraints: None,
+            input_reset: InputResetTriggers::default(),
+            close_to_tray: false,
+            safe_mode: false,
+            audio: true,
+            force_adapter: None,
+            power_profile: None,
+            renderer_error_screen: false,
+            webview_region: None,
+            timed_schedules: Vec::new(),
+            adaptive_vsync: None,
+            systems: Mutex::new(Vec::new()),
+            #[cfg(desktop)]
+            menu: None,
+            #[cfg(desktop)]
+            menu_bindings: Mutex::new(MenuBindings::default()),
         }
     }
+
+    // Scale the render resolution down when the frame rate drops, see DynamicResolution
+    pub fn with_dynamic_resolution(mut self, settings: DynamicResolution) -> Self {
+        self.dynamic_resolution = Some(settings);
+        self
+    }
+
+    // Camera quality applied to every Camera3d, can be switched later with set_quality
+    pub fn with_quality(mut self, preset: QualityPreset) -> Self {
+        self.quality = Some(preset);
+        self
+    }
+
+    // Called with every tauri event before bevy handles it, e.g. to drive
+    // another render path in a second window
+    pub fn with_event_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&tauri::AppHandle, &RunEvent) + Send + Sync + 'static,
+    {
+        self.event_handler = Some(Arc::new(handler));
+        self
+    }
+
+    // How the runner waits out the rest of each frame, SleepLimiter by default
+    #[allow(dead_code)]
+    pub fn with_frame_limiter<L: FrameLimiter + 'static>(self, limiter: L) -> Self {
+        *self.frame_limiter.lock().unwrap() = Some(Box::new(limiter));
+        self
+    }
+
+    // Paces frames with a LatencyPacer for lower input latency under vsync,
+    // in place of the frame limiter
+    pub fn with_latency_pacing(self) -> Self {
+        let wait = SurfaceWait::default();
+        self.with_frame_limiter(LatencyPacer::new(wait.clone()))
+            .with_render_plugin(SurfaceWaitPlugin(wait))
+    }
+
+    // Sleep more than the frame pacing needs, to keep bevy from saturating a
+    // core on battery powered devices, see CpuThrottle. set_cpu_throttle
+    // changes it at runtime.
+    #[allow(dead_code)]
+    pub fn with_cpu_throttle(mut self, throttle: CpuThrottle) -> Self {
+        self.cpu_throttle = Some(throttle);
+        self
+    }
+
+    // Caps the frame rate by power source, lower on battery, see PowerProfile.
+    // Platforms that don't report it count as AC; set_power_mode overrides it.
+    pub fn with_power_profile(mut self, profile: PowerProfile) -> Self {
+        self.power_profile = Some(profile);
+        self
+    }
+
+    // Stream frames to the webview instead of rendering to the window surface,
+    // see OffscreenStream
+    pub fn with_offscreen_stream(mut self, stream: OffscreenStream) -> Self {
+        self.offscreen_stream = Some(stream);
+        self.with_render_plugin(stream)
+    }
+
+    // Render at a fixed size scaled to fit the window, see RenderResolution
+    pub fn with_render_resolution(mut self, resolution: RenderResolution) -> Self {
+        self.render_resolution = true;
+        self.with_render_plugin(resolution)
+    }
+
+    // Add a DepthPrepass to every Camera3d, see DepthPrepassEnabled for the cost
+    #[allow(dead_code)]
+    pub fn with_depth_prepass(mut self) -> Self {
+        self.depth_prepass = true;
+        self
+    }
+
+    // Pace updates to the refresh rate of the monitor showing bevy's window
+    // instead of a fixed 60Hz, so a Fifo swapchain isn't fed frames at a beating rate
+    pub fn with_refresh_rate_pacing(mut self) -> Self {
+        self.match_refresh_rate = true;
+        self
+    }
+
+    // Native menu set up once tauri is built. Clicked items are sent to bevy
+    // as MenuItemTriggered, plus whatever with_menu_event bound to their id.
+    #[cfg(desktop)]
+    pub fn with_menu<F>(mut self, menu: F) -> Self
+    where
+        F: Fn(&tauri::AppHandle) -> tauri::Result<tauri::menu::Menu<tauri::Wry>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.menu = Some(Box::new(menu));
+        self
+    }
+
+    // Sends `event` whenever the menu item `id` is clicked. The event type has
+    // to be added to the app, e.g. AppExit is by default.
+    #[cfg(desktop)]
+    pub fn with_menu_event<E: Event + Clone>(self, id: &str, event: E) -> Self {
+        self.menu_bindings.lock().unwrap().bind(id, event);
+        self
+    }
+
+    // Keep running when bevy's window is closed and add a tray icon whose Show item
+    // opens it again. Bevy keeps updating without a surface in between.
+    #[cfg(desktop)]
+    pub fn with_close_to_tray(mut self) -> Self {
+        self.close_to_tray = true;
+        self
+    }
+
+    // Window events that release all held keys, mouse buttons and touches,
+    // all of them by default
+    #[allow(dead_code)]
+    pub fn with_input_reset(mut self, triggers: InputResetTriggers) -> Self {
+        self.input_reset = triggers;
+        self
+    }
+
+    // Initial size limits of bevy's window, can be changed later with set_size_constraints
+    pub fn with_size_constraints(mut self, constraints: SizeConstraints) -> Self {
+        self.size_constraints = Some(constraints);
+        self
+    }
+
+    // Restrict the graphics backends, e.g. Backends::GL for old GPUs without
+    // working Vulkan or DX12 drivers. The WGPU_BACKEND variable overrides it.
+    pub fn with_backends(mut self, backends: wgpu::Backends) -> Self {
+        self.instance.get_mut().unwrap().0.backends = backends;
+        self
+    }
+
+    // Starts the renderer the way most likely to work on a broken driver: GL on
+    // a fallback (software) adapter when there is one, without pipelined rendering
+    pub fn with_safe_mode(mut self) -> Self {
+        self.safe_mode = true;
+        self.with_backends(wgpu::Backends::GL)
+    }
+
+    // Leaves out bevy's AudioPlugin, which opens the default output device at
+    // startup and can stall or fail on CI runners and VMs without one. Nothing
+    // else here needs it, but AudioPlayer entities then stay silent.
+    pub fn without_audio(mut self) -> Self {
+        self.audio = false;
+        self
+    }
+
+    // Renders with the adapter `selection` picks among those list_adapters
+    // returns, e.g. the GPU the display is connected to on a hybrid graphics
+    // laptop, instead of the one wgpu's default power preference prefers.
+    // Starting fails when it matches none that can render to the window.
+    pub fn with_adapter(mut self, selection: AdapterSelection) -> Self {
+        self.force_adapter = Some(selection);
+        self
+    }
+
+    // Keeps the app open when the renderer fails to start instead of exiting
+    // with an error, so the webview can show why from get_renderer_status or
+    // the "renderer-failed" event rather than a blank window. Bevy doesn't
+    // update then, and the app exits with an error once the window is closed.
+    pub fn with_renderer_error_screen(mut self) -> Self {
+        self.renderer_error_screen = true;
+        self
+    }
+
+    // Puts the webview in `region` of bevy's window instead of over all of it,
+    // kept there as the window resizes, e.g. HTML panels beside the 3D view.
+    // The plugin creates the window for it, so its config needs "create": false,
+    // e.g. set with Context::config_mut before building the tauri app. Desktop
+    // only, see webview_region::create_window.
+    pub fn with_webview_region(mut self, region: WebviewRegion) -> Self {
+        self.webview_region = Some(region);
+        self
+    }
+
+    // Replaces the whole instance descriptor, e.g. to use the DXC shader
+    // compiler on DX12 or to turn on validation. Its backends are overridden by
+    // WGPU_BACKEND like those of with_backends.
+    #[allow(dead_code)]
+    pub fn with_instance_descriptor(mut self, descriptor: wgpu::InstanceDescriptor) -> Self {
+        *self.instance.get_mut().unwrap() = RendererInstance(descriptor);
+        self
+    }
+
+    // Which picking backends to add, all of them by default
+    #[allow(dead_code)]
+    pub fn with_picking_backends(mut self, backends: PickingBackends) -> Self {
+        self.picking_backends = backends;
+        self
+    }
+
+    // Warn and emit "bevy-stalled" when no runner iteration completes within
+    // `timeout`, see watchdog::spawn
+    pub fn with_watchdog(mut self, timeout: Duration) -> Self {
+        self.watchdog_timeout = Some(timeout);
+        self
+    }
+
+    // Adds `plugin` once the renderer exists, after the core pipeline, e.g. a
+    // post-processing pass that adds its node to the Core3d render graph
+    #[allow(dead_code)]
+    pub fn with_render_plugin<P: Plugin>(self, plugin: P) -> Self {
+        self.render_plugins
+            .lock()
+            .unwrap()
+            .push(Box::new(move |app: &mut App| {
+                app.add_plugins(plugin);
+            }));
+        self
+    }
+
+    // Called once per runner iteration after `app.update()`, e.g. to poll the
+    // network. It runs on the main thread between frames, so anything slow in
+    // here stalls rendering and the tauri event loop.
+    #[allow(dead_code)]
+    pub fn with_on_frame<F>(self, on_frame: F) -> Self
+    where
+        F: FnMut(&mut App) + Send + 'static,
+    {
+        *self.on_frame.lock().unwrap() = Some(Box::new(on_frame));
+        self
+    }
+
+    // Runs `label` every `interval` of real time, e.g. 100ms for a 10Hz network
+    // or AI tick, with systems added to it like to any other schedule. The
+    // runner runs it on the main world before an update, skipping the time
+    // bevy's windows are hidden or the simulation is disabled. Unlike
+    // FixedUpdate it doesn't follow virtual time, so pausing the game doesn't stop it.
+    #[allow(dead_code)]
+    pub fn with_timed_schedule(mut self, label: impl ScheduleLabel, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "a timed schedule needs a non-zero interval");
+        self.timed_schedules.push(TimedSchedule {
+            label: label.intern(),
+            interval,
+            accumulated: Duration::ZERO,
+        });
+        self
+    }
+
+    // Switches bevy's window between an uncapped present mode and Fifo by
+    // focus and frame rate, see AdaptiveVsync
+    pub fn with_adaptive_vsync(mut self, config: AdaptiveVsync) -> Self {
+        self.adaptive_vsync = Some(config);
+        self
+    }
+
+    // Registers `system` as a one-shot system the webview runs by `name` with
+    // the run_system command, for prewired actions that don't need a command
+    // of their own. A later registration under the same name replaces it.
+    pub fn with_system<S, M>(self, name: &str, system: S) -> Self
+    where
+        S: IntoSystem<(), (), M> + Send + Sync + 'static,
+    {
+        self.systems.lock().unwrap().push((
+            name.to_string(),
+            Box::new(move |world: &mut World| world.register_system(system)),
+        ));
+        self
+    }
 }
 
 impl Plugin for TauriPlugin {
     fn build(&self, app: &mut App) {
         let tauri_app = (self.setup)();
+        let render_window = RenderWindow(self.window_label.clone());
+        if render_window.get(tauri_app.handle()).is_none() {
+            let created =
+                webview_region::create_window(&tauri_app, &self.window_label, self.webview_region);
+            if let Err(err) = created {
+                bevy::log::error!("{}", err);
+            }
+        } else if self.webview_region.is_some() {
+            bevy::log::warn!(
+                "the {} window was created with its webview covering it, see with_webview_region",
+                self.window_label
+            );
+        }
+        let Some(window) = render_window.get(tauri_app.handle()) else {
+            let labels: Vec<String> = tauri_app.webview_windows().into_keys().collect();
+            panic!(
+                "TauriPlugin: no webview window labeled {:?} to render to, the app has {:?}",
+                render_window.0, labels
+            );
+        };
+        tauri_app.manage(render_window.clone());
+        app.insert_resource(render_window);
+
+        let (bevy_bridge, receiver) = bridge::channel();
+        tauri_app.manage(bevy_bridge);
+        if self.offscreen_stream.is_some() {
+            tauri_app.manage(OffscreenSubscribers::default());
+        }
+        app.insert_resource(receiver);
+
+        if let Some(constraints) = &self.size_constraints {
+            if let Err(err) = constraints.apply_to(&window) {
+                bevy::log::warn!("failed to constrain the window size: {}", err);
+            }
+        }
+        #[cfg(desktop)]
+        {
+            if let Some(menu) = &self.menu {
+                let menu = menu(tauri_app.handle()).and_then(|menu| tauri_app.set_menu(menu));
+                if let Err(err) = menu {
+                    bevy::log::error!("failed to set the menu: {}", err);
+                }
+            }
+            app.add_event::<MenuItemTriggered>();
+            app.insert_resource(std::mem::take(&mut *self.menu_bindings.lock().unwrap()));
+            if self.close_to_tray {
+                if let Err(err) = tray::create_tray(&tauri_app) {
+                    bevy::log::error!("failed to create the tray icon: {}", err);
+                }
+            }
+        }
+        if self.close_to_tray {
+            app.insert_resource(CloseToTray);
+        }
+        if let Some(region) = self.webview_region {
+            app.insert_resource(region);
+        }
+
+        // resizes are clamped to these, so replace bevy's defaults even when unconstrained
+        let constraints = self.size_constraints.unwrap_or_default();
+        let size = window.inner_size().ok().zip(window.scale_factor().ok());
+        let world = app.world_mut();
+        for mut bevy_window in world.query::<&mut Window>().iter_mut(world) {
+            bevy_window.resize_constraints = constraints.resize_constraints();
+            // bevy's default size until the first resize event arrives
+            if let Some((size, scale_factor)) = size {
+                resize_window(&mut bevy_window, size, scale_factor as f32);
+            }
+        }
+        app.add_systems(PreUpdate, apply_world_callbacks);
+
+        if let Some(settings) = &self.dynamic_resolution {
+            if self.offscreen_stream.is_some() || self.render_resolution {
+                bevy::log::warn!(
+                    "dynamic resolution is off with a render resolution or offscreen stream"
+                );
+            } else {
+                app.add_plugins(settings.clone());
+            }
+        }
 
+        if let Some(preset) = self.quality {
+            app.insert_resource(preset);
+        }
+        app.add_systems(
+            PostUpdate,
+            apply_quality.run_if(resource_exists::<QualityPreset>),
+        );
+        if self.depth_prepass {
+            app.insert_resource(DepthPrepassEnabled);
+        }
+        app.add_systems(
+            PostUpdate,
+            apply_depth_prepass
+                .after(apply_quality)
+                .run_if(resource_exists::<DepthPrepassEnabled>),
+        );
+
+        app.init_resource::<WindowOcclusion>();
+        app.init_resource::<FrameWaiters>();
+        app.init_resource::<LatencyProbe>();
+        app.add_systems(Last, notify_frame_waiters);
+        app.init_resource::<RefreshRate>();
+        app.insert_resource(self.picking_backends);
+        app.insert_resource(self.input_reset);
+        app.insert_resource(std::mem::take(&mut *self.instance.lock().unwrap()));
+        if self.safe_mode {
+            app.insert_resource(SafeMode);
+        }
+        if !self.audio {
+            app.insert_resource(AudioDisabled);
+        }
+        if self.renderer_error_screen {
+            app.insert_resource(RendererErrorScreen);
+        }
+        if let Some(selection) = self.force_adapter.clone() {
+            app.insert_resource(ForcedAdapter(selection));
+        }
+        let mut systems = NamedSystems::default();
+        for (name, register) in std::mem::take(&mut *self.systems.lock().unwrap()) {
+            systems.0.insert(name, register(app.world_mut()));
+        }
+        app.insert_resource(systems);
+        app.insert_resource(PendingRenderPlugins(std::mem::take(
+            &mut self.render_plugins.lock().unwrap(),
+        )));
+        let cpu_throttle = CpuThrottleStore::default();
+        *cpu_throttle.0.lock().unwrap() = self.cpu_throttle;
+        tauri_app.manage(cpu_throttle.clone());
+        let render_fps_cap = RenderFpsCap::default();
+        tauri_app.manage(render_fps_cap.clone());
+        let power = self
+            .power_profile
+            .map(|profile| power::spawn(tauri_app.handle(), profile));
+        let webview_reloaded = watch_webview_reloads(&tauri_app, &self.window_label);
         app.add_systems(Startup, create_window_handle);
         app.insert_non_send_resource(tauri_app.handle().clone());
         app.insert_non_send_resource(tauri_app);
-        app.set_runner(run_tauri_app);
+        let config = RunnerConfig {
+            event_handler: self.event_handler.clone(),
+            on_frame: self.on_frame.lock().unwrap().take(),
+            match_refresh_rate: self.match_refresh_rate,
+            frame_limiter: self
+                .frame_limiter
+                .lock()
+                .unwrap()
+                .take()
+                .unwrap_or_else(|| Box::new(SleepLimiter)),
+            cpu_throttle,
+            render_fps_cap,
+            watchdog_timeout: self.watchdog_timeout,
+            close_to_tray: self.close_to_tray,
+            size_constraints: self.size_constraints,
+            window_label: self.window_label.clone(),
+            timed_schedules: self.timed_schedules.clone(),
+            webview_reloaded,
+            adaptive_vsync: self.adaptive_vsync,
+            power,
+        };
+        for schedule in &config.timed_schedules {
+            app.init_schedule(schedule.label);
+        }
+        app.set_runner(move |app| run_tauri_app(app, config));
     }
 }
 
+// Set when the webview of bevy's window loads a page again, after a reload,
+// a navigation or the web content process restarting. Page loads are the only
+// sign of it tauri gives, so a tauri plugin added at runtime watches them.
+fn watch_webview_reloads(tauri_app: &tauri::App, window_label: &str) -> Arc<AtomicBool> {
+    let reloaded = Arc::new(AtomicBool::new(false));
+    let flag = reloaded.clone();
+    let label = window_label.to_string();
+    let mut loaded = false;
+    let plugin = tauri::plugin::Builder::<tauri::Wry>::new("bevy-webview-reload")
+        .on_page_load(move |webview, payload| {
+            if webview.label() != label || payload.event() != PageLoadEvent::Finished {
+                return;
+            }
+            // the first load is the page the window was created with
+            if std::mem::replace(&mut loaded, true) {
+                flag.store(true, Ordering::Relaxed);
+            }
+        })
+        .build();
+    if let Err(err) = tauri_app.handle().plugin(plugin) {
+        bevy::log::warn!("webview reloads won't rebuild the surface: {}", err);
+    }
+    reloaded
+}
+
 pub static AVERAGE_FRAME_RATE: AtomicUsize = AtomicUsize::new(0);
+// Mirrors WindowOcclusion for commands, which can't reach the world while paused
+pub static UPDATES_PAUSED: AtomicBool = AtomicBool::new(false);
+
+// Set while no window is visible, bevy stops updating and rendering until one is
+#[derive(Resource, Default, PartialEq)]
+pub struct WindowOcclusion {
+    pub occluded: bool,
+}
 
-fn run_tauri_app(app: App) -> AppExit {
+// tauri doesn't forward tao's occlusion event, so minimized or hidden windows
+// are the closest thing available. Windows covered by others still count as visible.
+fn windows_occluded(tauri_app: &tauri::App) -> bool {
+    tauri_app.webview_windows().values().all(|window| {
+        window.is_minimized().unwrap_or(false) || !window.is_visible().unwrap_or(true)
+    })
+}
+
+const DEFAULT_REFRESH_RATE: f64 = 60.0;
+
+// Rate in Hz the runner paces bevy updates to
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct RefreshRate(pub f64);
+
+impl Default for RefreshRate {
+    fn default() -> Self {
+        Self(DEFAULT_REFRESH_RATE)
+    }
+}
+
+// A window straddling two monitors is paced to the one holding its center,
+// current_monitor picks by the larger overlap on some platforms only
+fn monitor_at_window_center(window: &WebviewWindow) -> Option<tauri::Monitor> {
+    let position = window.outer_position().ok()?;
+    let size = window.outer_size().ok()?;
+    let center_x = position.x as i64 + size.width as i64 / 2;
+    let center_y = position.y as i64 + size.height as i64 / 2;
+    window
+        .available_monitors()
+        .ok()?
+        .into_iter()
+        .find(|monitor| {
+            let origin = monitor.position();
+            let extent = monitor.size();
+            (origin.x as i64..origin.x as i64 + extent.width as i64).contains(&center_x)
+                && (origin.y as i64..origin.y as i64 + extent.height as i64).contains(&center_y)
+        })
+}
+
+// tauri's Monitor doesn't report a refresh rate, so look the display up by its center
+#[cfg(desktop)]
+pub fn monitor_refresh_rate(monitor: &tauri::Monitor) -> Option<f64> {
+    let position = monitor.position();
+    let size = monitor.size();
+    let mut x = position.x as f64 + size.width as f64 / 2.0;
+    let mut y = position.y as f64 + size.height as f64 / 2.0;
+    // display-info uses points rather than pixels on macOS
+    if cfg!(target_os = "macos") {
+        x /= monitor.scale_factor();
+        y /= monitor.scale_factor();
+    }
+    let display = display_info::DisplayInfo::from_point(x as i32, y as i32).ok()?;
+    (display.frequency > 0.0).then_some(display.frequency as f64)
+}
+
+#[cfg(mobile)]
+pub fn monitor_refresh_rate(_monitor: &tauri::Monitor) -> Option<f64> {
+    None
+}
+
+// Why the app is shutting down, sent to the webview as "app-exiting"
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum ExitReason {
+    // bevy's window is being closed
+    WindowClosed,
+    // tauri's exit was requested, e.g. with AppHandle::exit
+    ExitRequested,
+    // bevy sent AppExit::Success
+    AppExit,
+    // bevy sent AppExit::Error
+    Error,
+}
+
+#[derive(Serialize, Clone)]
+struct ExitingPayload {
+    reason: ExitReason,
+    code: i32,
+}
+
+static EXIT_NOTIFIED: AtomicBool = AtomicBool::new(false);
+
+// Sent once, while the webview still exists so it can save its state
+fn notify_exiting(app_handle: &tauri::AppHandle, reason: ExitReason, code: i32) {
+    if EXIT_NOTIFIED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    bevy::log::info!("app exiting with code {}", code);
+    if let Err(err) = app_handle.emit("app-exiting", ExitingPayload { reason, code }) {
+        bevy::log::warn!("failed to emit app-exiting: {}", err);
+    }
+}
+
+fn run_tauri_app(app: App, mut config: RunnerConfig) -> AppExit {
     let app = Rc::new(RefCell::new(app));
     let mut tauri_app = app
         .borrow_mut()
@@ -108,81 +1037,495 @@ fn run_tauri_app(app: App) -> AppExit {
         .remove_non_send_resource::<tauri::App>()
         .unwrap();
 
-    let target_frame_duration = Duration::from_secs_f64(1.0 / 60.0); // 60Hz
+    let mut target_frame_duration = Duration::from_secs_f64(1.0 / DEFAULT_REFRESH_RATE);
     let mut frame_count = 0;
     let mut last_second = Instant::now();
+    // monitors are told apart by position, checked once a second and whenever
+    // bevy's window moves
+    let mut paced_monitor = None;
+    let mut check_monitor = config.match_refresh_rate;
+    let main_moved = Rc::new(Cell::new(false));
+    let heartbeat = config
+        .watchdog_timeout
+        .map(|timeout| watchdog::spawn(tauri_app.handle().clone(), timeout));
+    let enter = |phase| {
+        if let Some(heartbeat) = &heartbeat {
+            heartbeat.enter(phase);
+        }
+    };
+
+    let mut exiting = None;
+    // bevy's window was closed while closing to the tray
+    let mut main_closed = false;
+    // closing it is held back until bevy let go of its surface
+    let close_main = Rc::new(Cell::new(false));
+    let mut destroy_main = false;
+    // a frame was already rendered for a resize during this iteration
+    let redrawn = Rc::new(Cell::new(false));
+    // earliest time the next frame is rendered under a render fps cap
+    let mut next_present = Instant::now();
+    // when the timed schedules last caught up
+    let mut last_tick = Instant::now();
+    let mut adaptive_vsync = config.adaptive_vsync.map(AdaptiveVsyncState::new);
 
     loop {
         let frame_start = Instant::now();
+        enter(RunnerPhase::TauriEvents);
 
         let app_clone = app.clone();
+        let event_handler = config.event_handler.clone();
+        let moved = main_moved.clone();
+        let close = close_main.clone();
+        let drawn = redrawn.clone();
+        let close_to_tray = config.close_to_tray;
+        let main_label = config.window_label.clone();
         tauri_app.run_iteration(move |app_handle, event: RunEvent| {
+            if let Some(event_handler) = &event_handler {
+                event_handler(app_handle, &event);
+            }
+            if let RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::Moved(_),
+                ..
+            } = &event
+            {
+                moved.set(moved.get() || *label == main_label);
+            }
+            if let RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::CloseRequested { api, .. },
+                ..
+            } = &event
+            {
+                if close_to_tray && *label == main_label {
+                    api.prevent_close();
+                    close.set(true);
+                }
+            }
+            frame_stats::handle_window_destroyed(&event);
+            let redraw = requests_redraw(&event, &main_label);
             handle_tauri_events(app_handle, event, app_clone.borrow_mut());
+            if redraw && redraw_now(app_clone.borrow_mut(), &main_label) {
+                drawn.set(true);
+            }
         });
 
-        if tauri_app.webview_windows().is_empty() {
+        if let Some(exit) = exiting {
+            tauri_app.cleanup_before_exit();
+            return exit;
+        }
+        // startup failed, e.g. without a surface, and bevy can't update
+        let starting = app.borrow_mut().plugins_state() != PluginsState::Cleaned;
+        if let Some(exit) = starting.then(|| app.borrow().should_exit()).flatten() {
+            let code = match exit {
+                AppExit::Success => 0,
+                AppExit::Error(code) => code.get() as i32,
+            };
+            notify_exiting(tauri_app.handle(), ExitReason::Error, code);
+            exiting = Some(exit);
+            continue;
+        }
+
+        // other windows can't keep the app alive without the one bevy renders to
+        let main_window = tauri_app.get_webview_window(&config.window_label);
+        if main_window.is_none() && !config.close_to_tray {
             bevy::log::info!("cleanup_before_exit");
             tauri_app.cleanup_before_exit();
+            if app.borrow().world().contains_resource::<RendererFailed>() {
+                return AppExit::error();
+            }
             break;
         }
+        // the render thread may still present to the surface until the next
+        // update has extracted its removal, destroy the window after that
+        if close_main.take() {
+            release_surface(app.borrow_mut().world_mut());
+            destroy_main = true;
+        }
+        if main_window.is_none() != main_closed {
+            main_closed = main_window.is_none();
+            match &main_window {
+                None => {
+                    bevy::log::info!(
+                        "{} window closed, bevy keeps running without a surface",
+                        config.window_label
+                    );
+                    release_surface(app.borrow_mut().world_mut());
+                }
+                Some(window) => reopen_main_window(window, &config, app.borrow_mut()),
+            }
+        }
+
+        // the surface may have gone with the old web content, build a new one
+        if config.webview_reloaded.swap(false, Ordering::Relaxed) && !main_closed && !starting {
+            bevy::log::info!("{} webview reloaded, recreating the surface", config.window_label);
+            recreate_surface(app.borrow_mut().world_mut());
+            if let Err(err) = tauri_app.emit("webview-reloaded", &config.window_label) {
+                bevy::log::warn!("failed to emit webview-reloaded: {}", err);
+            }
+        }
+
+        check_monitor |= main_moved.take() && config.match_refresh_rate;
+        if check_monitor {
+            check_monitor = false;
+            // a window whose center is off every monitor keeps the last rate
+            let monitor = tauri_app
+                .get_webview_window(&config.window_label)
+                .and_then(|window| monitor_at_window_center(&window));
+            if let Some(monitor) =
+                monitor.filter(|monitor| paced_monitor != Some(*monitor.position()))
+            {
+                paced_monitor = Some(*monitor.position());
+                let rate = monitor_refresh_rate(&monitor).unwrap_or(DEFAULT_REFRESH_RATE);
+                bevy::log::info!("pacing updates to {}Hz", rate);
+                target_frame_duration = Duration::from_secs_f64(1.0 / rate);
+                app.borrow_mut()
+                    .world_mut()
+                    .insert_resource(RefreshRate(rate));
+            }
+        }
+
+        // without its window there is nothing to wait for, bevy runs headless
+        let occluded = !main_closed && windows_occluded(&tauri_app);
+        let occlusion_changed = app
+            .borrow_mut()
+            .world_mut()
+            .resource_mut::<WindowOcclusion>()
+            .set_if_neq(WindowOcclusion { occluded });
+        if occluded && occlusion_changed {
+            reset_input_on(app.borrow_mut().world_mut(), InputResetTriggers::OCCLUDED);
+        }
+        UPDATES_PAUSED.store(occluded, Ordering::Relaxed);
 
-        app.borrow_mut().update();
-        let frame_duration = frame_start.elapsed();
-        if frame_duration < target_frame_duration {
-            std::thread::sleep(target_frame_duration - frame_duration);
+        if starting || occluded || !SIMULATION_ENABLED.load(Ordering::Relaxed) {
+            // time paused this way isn't caught up on
+            last_tick = Instant::now();
+        } else if !config.timed_schedules.is_empty() {
+            let elapsed = last_tick.elapsed();
+            last_tick += elapsed;
+            let mut app = app.borrow_mut();
+            for schedule in &mut config.timed_schedules {
+                schedule.tick(app.world_mut(), elapsed);
+            }
         }
+        if starting {
+            // the renderer failed to start under with_renderer_error_screen,
+            // there is no bevy app to update
+        } else if !occluded {
+            // a frame rendered for a resize counts as this iteration's update
+            if !redrawn.take() {
+                enter(RunnerPhase::BevyUpdate);
+                let now = Instant::now();
+                let cap = *config.render_fps_cap.0.lock().unwrap();
+                let render = cap.is_none_or(|_| now >= next_present);
+                if run_update(&mut app.borrow_mut(), render) {
+                    frame_stats::record_present(&config.window_label);
+                    latency::record_present(app.borrow_mut().world_mut());
+                    if let Some(fps) = cap {
+                        next_present = (next_present + Duration::from_secs_f64(1.0 / fps)).max(now);
+                    }
+                }
+            }
+            frame_count += 1;
 
-        frame_count += 1;
+            if let Some(exit) = app.borrow_mut().should_exit() {
+                let (reason, code) = match exit {
+                    AppExit::Success => (ExitReason::AppExit, 0),
+                    AppExit::Error(code) => (ExitReason::Error, code.get() as i32),
+                };
+                notify_exiting(tauri_app.handle(), reason, code);
+                // one more iteration hands the event to the webview before it goes away
+                exiting = Some(exit);
+                continue;
+            }
+        } else {
+            fail_paused_frame_waiters(app.borrow_mut().world_mut());
+            latency::fail_paused_probe(app.borrow_mut().world_mut());
+        }
+        if std::mem::take(&mut destroy_main) {
+            if let Some(window) = tauri_app.get_webview_window(&config.window_label) {
+                if let Err(err) = window.destroy() {
+                    bevy::log::error!("failed to close the main window: {}", err);
+                }
+            }
+        }
+        // the RefMut handed to the tauri event handlers is released by now
+        if let Some(on_frame) = &mut config.on_frame {
+            enter(RunnerPhase::OnFrame);
+            on_frame(&mut app.borrow_mut());
+        }
+        if let Some(heartbeat) = &heartbeat {
+            heartbeat.beat();
+        }
+        enter(RunnerPhase::FrameLimiter);
+        let paced = match &config.power {
+            Some(power) => {
+                let status = power.status();
+                let mut app = app.borrow_mut();
+                if app.world().get_resource::<PowerStatus>() != Some(&status) {
+                    app.world_mut().insert_resource(status);
+                }
+                status.frame_target(target_frame_duration)
+            }
+            None => target_frame_duration,
+        };
+        let throttle = *config.cpu_throttle.0.lock().unwrap();
+        frame_limiter::end_frame(&mut *config.frame_limiter, throttle, frame_start, paced);
 
+        let mut measured_fps = None;
         if last_second.elapsed() >= Duration::from_secs(1) {
             AVERAGE_FRAME_RATE.store(frame_count, Ordering::Relaxed);
+            measured_fps = Some(frame_count as u32);
             frame_count = 0;
             last_second = Instant::now();
+            check_monitor = config.match_refresh_rate;
+        }
+        if let Some(adaptive_vsync) = &mut adaptive_vsync {
+            adaptive_vsync.update(app.borrow_mut().world_mut(), measured_fps);
         }
     }
 
     AppExit::Success
 }
 
+// tauri has no redraw event, tao's RedrawRequested is handled internally, so
+// the resize events of bevy's window stand in for it. While the user drags the window
+// border, Windows and macOS run a modal loop that doesn't return to the runner
+// until the drag ends.
+fn requests_redraw(event: &RunEvent, main_label: &str) -> bool {
+    match event {
+        RunEvent::WindowEvent {
+            label,
+            event: tauri::WindowEvent::Resized(size),
+            ..
+        } => label == main_label && size.width > 0 && size.height > 0,
+        RunEvent::WindowEvent {
+            label,
+            event: tauri::WindowEvent::ScaleFactorChanged { .. },
+            ..
+        } => label == main_label,
+        _ => false,
+    }
+}
+
+// Renders a frame right away, outside the frame pacing, so the new size shows
+// up without waiting for the event loop to return. False before plugins finished.
+fn redraw_now(mut app: RefMut<'_, App>, main_label: &str) -> bool {
+    if app.plugins_state() != PluginsState::Cleaned {
+        return false;
+    }
+    if run_update(&mut app, true) {
+        frame_stats::record_present(main_label);
+        latency::record_present(app.world_mut());
+    }
+    true
+}
+
+// One update of the runner, without rendering when `render` is false or
+// set_render_enabled turned it off, and without the main schedule while
+// set_simulation_enabled did. Returns whether a frame was rendered.
+fn run_update(app: &mut App, render: bool) -> bool {
+    let render = render && RENDER_ENABLED.load(Ordering::Relaxed);
+    update_app(app, SIMULATION_ENABLED.load(Ordering::Relaxed), render)
+}
+
+fn update_app(app: &mut App, simulate: bool, render: bool) -> bool {
+    if simulate {
+        if render {
+            app.update();
+        } else {
+            // the main schedule without the render app's extraction and
+            // rendering, so the frame isn't presented. App::update clears
+            // the trackers after it, change detection relies on that.
+            app.main_mut().run_default_schedule();
+            app.main_mut().world_mut().clear_trackers();
+        }
+        return render;
+    }
+    // commands still reach the world, which is rendered as they left it.
+    // Last doesn't run, so frame waiters fail like while updates are paused.
+    apply_world_callbacks(app.world_mut());
+    fail_paused_frame_waiters(app.world_mut());
+    if render {
+        // pipelined rendering moves the render app to its thread and leaves
+        // an extract app here, the other one doesn't exist
+        app.update_sub_app_by_label(RenderApp);
+        app.update_sub_app_by_label(RenderExtractApp);
+    }
+    app.world_mut().clear_trackers();
+    render
+}
+
+// Gives bevy a surface for its window rebuilt after closing to the tray, which
+// starts out with the size and scale factor from tauri.conf.json
+fn reopen_main_window(window: &WebviewWindow, config: &RunnerConfig, mut app: RefMut<'_, App>) {
+    if let Some(constraints) = &config.size_constraints {
+        if let Err(err) = constraints.apply_to(window) {
+            bevy::log::warn!("failed to constrain the window size: {}", err);
+        }
+    }
+    if let Err(err) = app.world_mut().run_system_once(create_window_handle) {
+        bevy::log::error!("failed to create the window handle: {}", err);
+    }
+    if let (Ok(scale_factor), Ok(size)) = (window.scale_factor(), window.inner_size()) {
+        handle_window_factor_change(scale_factor, size, app);
+    }
+}
+
 fn handle_tauri_events(app_handle: &tauri::AppHandle, event: RunEvent, mut app: RefMut<'_, App>) {
     if app.plugins_state() != PluginsState::Cleaned {
         if app.plugins_state() != PluginsState::Ready {
             tick_global_task_pools_on_main_thread();
         }
     }
+    let close_to_tray = app.world().contains_resource::<CloseToTray>();
+    let render_window = app.world().resource::<RenderWindow>().clone();
 
     match event {
-        tauri::RunEvent::Ready => handle_ready_event(app_handle, app),
-        tauri::RunEvent::ExitRequested { api, .. } => {}
-        tauri::RunEvent::WindowEvent { label, event, .. } => handle_window_event(event, app),
+        tauri::RunEvent::Ready => handle_ready_event(app_handle, &render_window, app),
+        // no code means the last window was closed rather than exit called
+        tauri::RunEvent::ExitRequested { code: None, api, .. } if close_to_tray => {
+            api.prevent_exit()
+        }
+        tauri::RunEvent::ExitRequested { code, .. } => {
+            notify_exiting(app_handle, ExitReason::ExitRequested, code.unwrap_or(0))
+        }
+        tauri::RunEvent::WindowEvent {
+            label,
+            event: tauri::WindowEvent::CloseRequested { .. },
+            ..
+        } if label == render_window.0 && !close_to_tray => {
+            notify_exiting(app_handle, ExitReason::WindowClosed, 0)
+        }
+        tauri::RunEvent::WindowEvent { label, event, .. } if label == render_window.0 => {
+            handle_window_event(event, app)
+        }
+        #[cfg(desktop)]
+        tauri::RunEvent::MenuEvent(event) => {
+            if close_to_tray {
+                tray::handle_tray_menu_event(app_handle, app.world_mut(), &event);
+            }
+            menu::handle_menu_event(app.world_mut(), &event.id().0)
+        }
         tauri::RunEvent::MainEventsCleared => {}
         _ => (),
     }
 }
 
-fn handle_ready_event(app_handle: &tauri::AppHandle, mut app: RefMut<'_, App>) {
+// Why the render world can't run on its own thread here, if it can't
+fn pipelined_rendering_unsupported() -> Option<&'static str> {
+    if cfg!(target_arch = "wasm32") {
+        return Some("there are no threads on the web");
+    }
+    // the OS destroys the surface whenever the app goes to the background,
+    // which has to happen while the render thread isn't using it
+    if cfg!(any(target_os = "android", target_os = "ios")) {
+        return Some("mobile surfaces can be destroyed while a frame renders");
+    }
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    if threads < 2 {
+        return Some("there is a single CPU core to run both worlds on");
+    }
+    None
+}
+
+fn handle_ready_event(
+    app_handle: &tauri::AppHandle,
+    render_window: &RenderWindow,
+    mut app: RefMut<'_, App>,
+) {
     if app.plugins_state() != PluginsState::Cleaned {
-        let window = app_handle.get_webview_window("main").unwrap();
+        let Some(window) = render_window.get(app_handle) else {
+            panic!("the {} window bevy renders to was closed before startup", render_window.0);
+        };
+        let instance = app
+            .world_mut()
+            .remove_resource::<RendererInstance>()
+            .unwrap_or_default();
+        let safe_mode = app.world().contains_resource::<SafeMode>();
+        if safe_mode {
+            bevy::log::warn!("starting the renderer in safe mode");
+        }
+        let force_adapter = app
+            .world_mut()
+            .remove_resource::<ForcedAdapter>()
+            .map(|forced| forced.0);
+        let mut status = RendererStatus::default();
+        let result = create_renderer(&window, instance.0, safe_mode, force_adapter, &mut status);
+        if let Err(err) = &result {
+            status.error = Some(err.clone());
+        }
+        app_handle.manage(status.clone());
+        let render_creation = match result {
+            Ok(render_creation) => render_creation,
+            Err(err) => {
+                bevy::log::error!("failed to start the renderer: {}", err);
+                if app.world().contains_resource::<RendererErrorScreen>() {
+                    // bevy never updates, the webview explains why until it's closed
+                    app.world_mut().insert_resource(RendererFailed);
+                    // commands fail with NotReady instead of waiting for an update
+                    app.world_mut()
+                        .remove_resource::<bridge::WorldCallbackReceiver>();
+                    if let Err(err) = app_handle.emit("renderer-failed", status) {
+                        bevy::log::warn!("failed to emit renderer-failed: {}", err);
+                    }
+                } else {
+                    // the runner exits before the first update
+                    app.world_mut().send_event(AppExit::error());
+                }
+                return;
+            }
+        };
         app.add_plugins(CustomRendererPlugin {
-            webview_window: window,
+            render_creation: Mutex::new(Some(render_creation)),
         });
 
+        let picking = app
+            .world()
+            .get_resource::<PickingBackends>()
+            .copied()
+            .unwrap_or_default();
         app.add_plugins((
             bevy::render::texture::ImagePlugin::default(),
-            bevy::render::pipelined_rendering::PipelinedRenderingPlugin::default(),
             bevy::core_pipeline::CorePipelinePlugin::default(),
-            bevy::sprite::SpritePlugin::default(),
+            bevy::sprite::SpritePlugin {
+                add_picking: picking.contains(PickingBackends::SPRITE),
+            },
             bevy::text::TextPlugin::default(),
-            bevy::ui::UiPlugin::default(),
+            bevy::ui::UiPlugin {
+                add_picking: picking.contains(PickingBackends::UI),
+                ..default()
+            },
             bevy::pbr::PbrPlugin::default(),
             bevy::gltf::GltfPlugin::default(),
-            bevy::audio::AudioPlugin::default(),
             bevy::gilrs::GilrsPlugin::default(),
             bevy::animation::AnimationPlugin::default(),
             bevy::gizmos::GizmoPlugin::default(),
-            bevy::state::app::StatesPlugin::default(),
             bevy::picking::DefaultPickingPlugins::default(),
         ));
+        if app.world().contains_resource::<AudioDisabled>() {
+            bevy::log::info!("audio is disabled");
+        } else {
+            app.add_plugins(bevy::audio::AudioPlugin::default());
+        }
+        if safe_mode {
+            bevy::log::info!("rendering on the main thread in safe mode");
+        } else if let Some(reason) = pipelined_rendering_unsupported() {
+            bevy::log::info!("rendering on the main thread: {}", reason);
+        } else {
+            app.add_plugins(bevy::render::pipelined_rendering::PipelinedRenderingPlugin);
+        }
+        // DefaultPickingPlugins leaves the mesh backend out
+        if picking.contains(PickingBackends::MESH) {
+            app.add_plugins(bevy::picking::mesh_picking::MeshPickingPlugin);
+        }
+        if let Some(PendingRenderPlugins(plugins)) =
+            app.world_mut().remove_resource::<PendingRenderPlugins>()
+        {
+            for add_plugin in plugins {
+                add_plugin(&mut app);
+            }
+        }
         // wait for bevy to be ready
 
         while app.plugins_state() != PluginsState::Ready {
@@ -195,6 +1538,12 @@ fn handle_ready_event(app_handle: &tauri::AppHandle, mut app: RefMut<'_, App>) {
 }
 
 fn handle_window_event(event: tauri::WindowEvent, app: RefMut<'_, App>) {
+    if matches!(
+        event,
+        tauri::WindowEvent::Resized(_) | tauri::WindowEvent::ScaleFactorChanged { .. }
+    ) {
+        webview_region::fit_to_window(app.world());
+    }
     match event {
         tauri::WindowEvent::Resized(size) => handle_window_resize(size, app),
         tauri::WindowEvent::ScaleFactorChanged {
@@ -202,24 +1551,61 @@ fn handle_window_event(event: tauri::WindowEvent, app: RefMut<'_, App>) {
             new_inner_size,
             ..
         } => handle_window_factor_change(scale_factor, new_inner_size, app),
+        tauri::WindowEvent::Focused(focused) => handle_window_focus(focused, app),
         _ => (),
     }
 }
 
+// Keys held while focus moves elsewhere never see their release, so like
+// bevy_winit everything pressed is released when the window loses focus
+fn handle_window_focus(focused: bool, mut app: RefMut<'_, App>) {
+    set_window_focus(app.world_mut(), focused);
+}
+
+// Marks bevy's window (un)focused and releases held input when it loses focus
+fn set_window_focus(world: &mut World, focused: bool) {
+    let mut window_focused = Vec::new();
+    let mut windows = world.query_filtered::<(Entity, &mut Window), With<PrimaryWindow>>();
+    for (entity, mut window) in windows.iter_mut(world) {
+        window.focused = focused;
+        window_focused.push(WindowFocused {
+            window: entity,
+            focused,
+        });
+    }
+    world.send_event_batch(window_focused);
+
+    if !focused {
+        reset_input_on(world, InputResetTriggers::FOCUS_LOST);
+    }
+}
+
 fn handle_window_resize(size: tauri::PhysicalSize<u32>, mut app: RefMut<'_, App>) {
+    let window = app
+        .world()
+        .resource::<RenderWindow>()
+        .get(app.world().non_send_resource::<tauri::AppHandle>());
+    let scale_factor = window
+        .as_ref()
+        .and_then(|window| window.scale_factor().ok())
+        .unwrap_or(1.0) as f32;
+    // minimizing shows up as a resize, there is no window event of its own
+    if window.is_some_and(|window| window.is_minimized().unwrap_or(false)) {
+        reset_input_on(app.world_mut(), InputResetTriggers::MINIMIZED);
+    }
     let mut event_writer_system_state: SystemState<(
         EventWriter<WindowResized>,
-        Query<(Entity, &mut Window)>,
+        Query<(Entity, &mut Window), With<PrimaryWindow>>,
     )> = SystemState::new(app.world_mut());
 
     let (mut window_resized, mut window_query) = event_writer_system_state.get_mut(app.world_mut());
 
     for (entity, mut window) in window_query.iter_mut() {
-        window.resolution = WindowResolution::new(size.width as f32, size.height as f32);
+        let (width, height) = resize_window(&mut window, size, scale_factor);
         window_resized.send(WindowResized {
             window: entity,
-            width: size.width as f32,
-            height: size.height as f32,
+            width,
+            height,
         });
     }
 }
@@ -232,25 +1618,257 @@ fn handle_window_factor_change(
     let mut event_writer_system_state: SystemState<(
         EventWriter<WindowResized>,
         EventWriter<WindowScaleFactorChanged>,
-        Query<(Entity, &mut Window)>,
+        Query<(Entity, &mut Window), With<PrimaryWindow>>,
     )> = SystemState::new(app.world_mut());
 
     let (mut window_resized, mut window_scale_factor_changed, mut window_query) =
         event_writer_system_state.get_mut(app.world_mut());
 
     for (entity, mut window) in window_query.iter_mut() {
-        window.resolution =
-            WindowResolution::new(new_inner_size.width as f32, new_inner_size.height as f32);
+        let (width, height) = resize_window(&mut window, new_inner_size, scale_factor as f32);
         window_scale_factor_changed.send(WindowScaleFactorChanged {
             window: entity,
             scale_factor,
         });
         window_resized.send(WindowResized {
             window: entity,
-            width: new_inner_size.width as f32,
-            height: new_inner_size.height as f32,
+            width,
+            height,
         });
     }
 }
 
+// Gives bevy's window the physical size and scale factor of the tauri window,
+// like bevy_winit, so its logical size is in the webview's CSS pixels. Returns
+// the logical size for WindowResized.
+pub fn resize_window(
+    window: &mut Window,
+    size: tauri::PhysicalSize<u32>,
+    scale_factor: f32,
+) -> (f32, f32) {
+    let (width, height) = constrain_size(size, &window.resize_constraints, scale_factor);
+    window.resolution.set_scale_factor(scale_factor);
+    window
+        .resolution
+        .set_physical_resolution(width as u32, height as u32);
+    (window.width(), window.height())
+}
+
+// tauri enforces the constraints, but a resize racing set_size_constraints may
+// still report a size outside of them. Window::resize_constraints can be set
+// directly, check_constraints raises a maximum below its minimum with a warning.
+fn constrain_size(
+    size: tauri::PhysicalSize<u32>,
+    constraints: &WindowResizeConstraints,
+    scale_factor: f32,
+) -> (f32, f32) {
+    let constraints = constraints.check_constraints();
+    let width = (size.width as f32).clamp(
+        constraints.min_width * scale_factor,
+        constraints.max_width * scale_factor,
+    );
+    let height = (size.height as f32).clamp(
+        constraints.min_height * scale_factor,
+        constraints.max_height * scale_factor,
+    );
+    (width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::{self, CommandError};
+    use crate::frame_sync::add_frame_waiter;
+    use bevy::input::keyboard::KeyboardFocusLost;
+    use bevy::window::{PresentMode, WindowResolution};
+    use wgpu::rwh::{
+        DisplayHandle, HandleError, RawDisplayHandle, RawWindowHandle, WindowHandle,
+        XlibDisplayHandle, XlibWindowHandle,
+    };
+
+    // Handles that are never used to create a surface
+    struct FakeWindow;
+
+    impl HasWindowHandle for FakeWindow {
+        fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+            let handle = RawWindowHandle::Xlib(XlibWindowHandle::new(1));
+            Ok(unsafe { WindowHandle::borrow_raw(handle) })
+        }
+    }
+
+    impl HasDisplayHandle for FakeWindow {
+        fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+            let handle = RawDisplayHandle::Xlib(XlibDisplayHandle::new(None, 0));
+            Ok(unsafe { DisplayHandle::borrow_raw(handle) })
+        }
+    }
+
+    fn attach(world: &mut World) {
+        let window_wrapper = WindowWrapper::new(FakeWindow);
+        world
+            .run_system_once(
+                move |commands: Commands,
+                      query: Query<WindowHandleData, With<PrimaryWindow>>,
+                      window_created: EventWriter<WindowCreated>| {
+                    attach_window_handle(&window_wrapper, commands, query, window_created);
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn release_and_recreate_surface_keep_the_window() {
+        let mut world = World::new();
+        world.init_resource::<Events<WindowCreated>>();
+        let window = world
+            .spawn((
+                Window {
+                    resolution: WindowResolution::new(640.0, 480.0).with_scale_factor_override(2.0),
+                    present_mode: PresentMode::Immediate,
+                    ..default()
+                },
+                PrimaryWindow,
+                RawHandleWrapperHolder(Arc::new(Mutex::new(None))),
+            ))
+            .id();
+        attach(&mut world);
+        assert!(world.get::<RawHandleWrapper>(window).is_some());
+        let resolution = world.get::<Window>(window).unwrap().resolution.clone();
+
+        release_surface(&mut world);
+        assert!(world.get::<RawHandleWrapper>(window).is_none());
+        let holder = world.get::<RawHandleWrapperHolder>(window).unwrap();
+        assert!(holder.0.lock().unwrap().is_none());
+
+        attach(&mut world);
+        assert!(world.get::<RawHandleWrapper>(window).is_some());
+        let holder = world.get::<RawHandleWrapperHolder>(window).unwrap();
+        assert!(holder.0.lock().unwrap().is_some());
+        let window = world.get::<Window>(window).unwrap();
+        assert_eq!(window.resolution, resolution);
+        assert_eq!(window.resolution.scale_factor(), 2.0);
+        assert_eq!(window.present_mode, PresentMode::Immediate);
+        // the window was only announced the first time
+        assert_eq!(world.resource::<Events<WindowCreated>>().len(), 1);
+    }
+
+    #[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+    struct Tick;
+
+    #[derive(Resource, Default)]
+    struct Runs(u32);
+
+    const STEP: Duration = Duration::from_millis(100);
+
+    fn timed_world() -> (World, TimedSchedule) {
+        let mut world = World::new();
+        world.init_resource::<Runs>();
+        let mut schedule = Schedule::new(Tick);
+        schedule.add_systems(|mut runs: ResMut<Runs>| runs.0 += 1);
+        world.add_schedule(schedule);
+        let timed = TimedSchedule {
+            label: Tick.intern(),
+            interval: STEP,
+            accumulated: Duration::ZERO,
+        };
+        (world, timed)
+    }
+
+    #[test]
+    fn timed_schedule_runs_once_per_interval() {
+        let (mut world, mut timed) = timed_world();
+        timed.tick(&mut world, STEP * 3);
+        assert_eq!(world.resource::<Runs>().0, 3);
+        // the rest of an interval carries over to the next tick
+        timed.tick(&mut world, STEP / 2);
+        assert_eq!(world.resource::<Runs>().0, 3);
+        timed.tick(&mut world, STEP / 2);
+        assert_eq!(world.resource::<Runs>().0, 4);
+        assert_eq!(timed.accumulated, Duration::ZERO);
+    }
+
+    #[test]
+    fn timed_schedule_drops_time_beyond_the_catch_up() {
+        let (mut world, mut timed) = timed_world();
+        timed.tick(&mut world, STEP * 10 + STEP / 2);
+        assert_eq!(world.resource::<Runs>().0, MAX_TIMED_CATCH_UP);
+        assert_eq!(timed.accumulated, Duration::ZERO);
+        timed.tick(&mut world, STEP);
+        assert_eq!(world.resource::<Runs>().0, MAX_TIMED_CATCH_UP + 1);
+    }
+
+    #[test]
+    fn focus_loss_releases_held_input() {
+        let mut world = World::new();
+        world.init_resource::<Events<WindowFocused>>();
+        world.init_resource::<Events<KeyboardFocusLost>>();
+        world.init_resource::<ButtonInput<KeyCode>>();
+        world.init_resource::<ButtonInput<MouseButton>>();
+        let window = world.spawn((Window::default(), PrimaryWindow)).id();
+        world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyW);
+        world
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .press(MouseButton::Left);
+
+        set_window_focus(&mut world, false);
+
+        assert!(!world
+            .resource::<ButtonInput<KeyCode>>()
+            .pressed(KeyCode::KeyW));
+        assert!(!world
+            .resource::<ButtonInput<MouseButton>>()
+            .pressed(MouseButton::Left));
+        assert!(!world.get::<Window>(window).unwrap().focused);
+        assert_eq!(world.resource::<Events<WindowFocused>>().len(), 1);
+    }
+
+    #[derive(Component)]
+    struct Marker;
 
+    #[derive(Resource, Default)]
+    struct ChangedCount(usize);
+
+    #[test]
+    fn skipped_frames_clear_the_trackers() {
+        let mut app = App::new();
+        app.init_resource::<ChangedCount>().add_systems(
+            Update,
+            |changed: Query<(), Changed<Marker>>, mut count: ResMut<ChangedCount>| {
+                count.0 += changed.iter().count();
+            },
+        );
+        let entity = app.world_mut().spawn(Marker).id();
+        update_app(&mut app, true, false);
+        update_app(&mut app, true, false);
+        assert_eq!(app.world().resource::<ChangedCount>().0, 1);
+
+        app.world_mut().despawn(entity);
+        assert_eq!(app.world().removed::<Marker>().count(), 1);
+        // removals are kept for the update after the one they happened in
+        update_app(&mut app, true, false);
+        update_app(&mut app, true, false);
+        assert_eq!(app.world().removed::<Marker>().count(), 0);
+    }
+
+    #[test]
+    fn frame_waiters_fail_while_the_simulation_is_disabled() {
+        let mut app = App::new();
+        app.init_resource::<FrameWaiters>()
+            .insert_resource(bridge::channel().1)
+            .add_systems(Last, notify_frame_waiters);
+        let mut failing = add_frame_waiter(app.world_mut(), 1, false);
+        let mut blocking = add_frame_waiter(app.world_mut(), 1, true);
+
+        update_app(&mut app, false, false);
+        assert!(matches!(
+            failing.try_recv(),
+            Ok(Err(CommandError::NotReady(_)))
+        ));
+        assert!(blocking.try_recv().is_err());
+
+        update_app(&mut app, true, false);
+        assert!(matches!(blocking.try_recv(), Ok(Ok(()))));
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use tauri::menu::{MenuBuilder, MenuEvent};
+use tauri::tray::{TrayIcon, TrayIconBuilder};
+use tauri::{Manager, WebviewWindowBuilder};
+
+use crate::tauri_plugin::RenderWindow;
+
+// Menu item ids of the tray icon, also seen by bevy as MenuItemTriggered
+pub const TRAY_SHOW: &str = "tray-show";
+pub const TRAY_QUIT: &str = "tray-quit";
+
+// Tray icon with the app icon and Show / Quit items, used by
+// TauriPlugin::with_close_to_tray
+pub fn create_tray(app: &tauri::App) -> tauri::Result<TrayIcon> {
+    let menu = MenuBuilder::new(app)
+        .text(TRAY_SHOW, "Show")
+        .separator()
+        .text(TRAY_QUIT, "Quit")
+        .build()?;
+    let mut tray = TrayIconBuilder::new().menu(&menu);
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)
+}
+
+// Quit goes through bevy's AppExit like File > Quit, so the runner shuts down
+// the same way as when bevy exits
+pub fn handle_tray_menu_event(app_handle: &tauri::AppHandle, world: &mut World, event: &MenuEvent) {
+    if event.id() == TRAY_SHOW {
+        if let Err(err) = show_main_window(app_handle) {
+            bevy::log::error!("failed to reopen the main window: {}", err);
+        }
+    } else if event.id() == TRAY_QUIT {
+        world.send_event(AppExit::Success);
+    }
+}
+
+// Brings bevy's window back, building it again from tauri.conf.json if it was
+// closed. The runner notices the new window and gives bevy a surface for it.
+pub fn show_main_window(app_handle: &tauri::AppHandle) -> tauri::Result<()> {
+    let label = app_handle.state::<RenderWindow>().0.clone();
+    if let Some(window) = app_handle.get_webview_window(&label) {
+        window.unminimize()?;
+        window.show()?;
+        return window.set_focus();
+    }
+    let Some(config) = app_handle
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|window| window.label == label)
+    else {
+        return Err(tauri::Error::WindowNotFound);
+    };
+    WebviewWindowBuilder::from_config(app_handle, config)?.build()?;
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use std::time::Duration;
+use tauri::State;
+
+use crate::assets;
+use crate::bridge::{BevyBridge, CommandError};
+
+// Text nodes made by `spawn_ui_text`, the only ones `remove_ui` removes
+#[derive(Component)]
+pub struct UiText;
+
+// Bevy UI text at `x`, `y` logical pixels from the top left of the window,
+// drawn over the 3d scene and under the webview. `font` is a path in the assets
+// folder, bevy's built-in font is used without one. Returns the node's id.
+#[tauri::command]
+pub async fn spawn_ui_text(
+    bridge: State<'_, BevyBridge>,
+    text: String,
+    x: f32,
+    y: f32,
+    size: f32,
+    color: [f32; 3],
+    font: Option<String>,
+) -> Result<u64, CommandError> {
+    if !x.is_finite() || !y.is_finite() {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid position {}, {}",
+            x, y
+        )));
+    }
+    if !size.is_finite() || size <= 0.0 {
+        return Err(CommandError::InvalidArgument(format!(
+            "invalid font size {}",
+            size
+        )));
+    }
+    let font = match font {
+        Some(path) => {
+            let timeout = Duration::from_millis(assets::DEFAULT_TIMEOUT_MS);
+            assets::load::<Font>(&bridge, path, timeout).await?
+        }
+        None => Handle::default(),
+    };
+    let color = Color::srgb(color[0], color[1], color[2]);
+
+    bridge
+        .run(move |world| {
+            world
+                .spawn((
+                    Text::new(text),
+                    TextFont {
+                        font,
+                        font_size: size,
+                        ..default()
+                    },
+                    TextColor(color),
+                    Node {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(x),
+                        top: Val::Px(y),
+                        ..default()
+                    },
+                    UiText,
+                ))
+                .id()
+                .to_bits()
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn remove_ui(bridge: State<'_, BevyBridge>, id: u64) -> Result<(), CommandError> {
+    bridge
+        .run(move |world| {
+            let entity = Entity::try_from_bits(id)
+                .ok()
+                .filter(|&entity| {
+                    world
+                        .get_entity(entity)
+                        .is_ok_and(|entity| entity.contains::<UiText>())
+                })
+                .ok_or_else(|| {
+                    CommandError::EntityNotFound(format!("no ui text with id {}", id))
+                })?;
+            world.entity_mut(entity).despawn_recursive();
+            Ok(())
+        })
+        .await?
+}
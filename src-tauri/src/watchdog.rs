@@ -0,0 +1,117 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+// What the runner was doing last, reported when it stalls. Rust can't sample
+// another thread's stack, so this is the closest thing to a stack hint.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[repr(u8)]
+pub enum RunnerPhase {
+    TauriEvents,
+    BevyUpdate,
+    OnFrame,
+    FrameLimiter,
+}
+
+impl RunnerPhase {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::TauriEvents,
+            1 => Self::BevyUpdate,
+            2 => Self::OnFrame,
+            _ => Self::FrameLimiter,
+        }
+    }
+
+    fn hint(self) -> &'static str {
+        match self {
+            Self::TauriEvents => "in a tauri event handler or the window's event loop",
+            Self::BevyUpdate => "in a bevy system, run with RUST_LOG=bevy_ecs=trace to see which",
+            Self::OnFrame => "in the on_frame callback",
+            Self::FrameLimiter => "in the frame limiter",
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct StalledPayload {
+    stalled_for_ms: u64,
+    phase: String,
+}
+
+// Updated by the runner every iteration, read by the watchdog thread
+#[derive(Clone)]
+pub struct Heartbeat {
+    start: Instant,
+    last_beat_ms: Arc<AtomicU64>,
+    phase: Arc<AtomicU8>,
+}
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        self.last_beat_ms
+            .store(self.start.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn enter(&self, phase: RunnerPhase) {
+        self.phase.store(phase as u8, Ordering::Relaxed);
+    }
+
+    // Time since the last beat. The beat is read first, a beat landing in
+    // between would otherwise be newer than `now`.
+    fn since_last_beat(&self) -> Duration {
+        let last_beat_ms = self.last_beat_ms.load(Ordering::Relaxed);
+        let now = self.start.elapsed().as_millis() as u64;
+        Duration::from_millis(now.saturating_sub(last_beat_ms))
+    }
+}
+
+// Starts a thread that warns and emits "bevy-stalled" once no runner iteration
+// has completed for `timeout`. It can't recover the hang. The log is written
+// right away, but the event only reaches the webview once the main thread
+// runs the event loop again, i.e. if the stall turns out to be a long block.
+pub fn spawn(app_handle: tauri::AppHandle, timeout: Duration) -> Heartbeat {
+    let heartbeat = Heartbeat {
+        start: Instant::now(),
+        last_beat_ms: Arc::new(AtomicU64::new(0)),
+        phase: Arc::new(AtomicU8::new(RunnerPhase::TauriEvents as u8)),
+    };
+    let watched = heartbeat.clone();
+    std::thread::Builder::new()
+        .name(String::from("bevy-watchdog"))
+        .spawn(move || {
+            let mut stalled = false;
+            loop {
+                std::thread::sleep(timeout / 4);
+                let stalled_for = watched.since_last_beat();
+                if stalled_for < timeout {
+                    if stalled {
+                        bevy::log::info!("bevy updates resumed");
+                        stalled = false;
+                    }
+                    continue;
+                }
+                if stalled {
+                    continue;
+                }
+                stalled = true;
+                let phase = RunnerPhase::from_u8(watched.phase.load(Ordering::Relaxed));
+                bevy::log::warn!(
+                    "no bevy update completed for {:.1}s, the main thread is stuck {}",
+                    stalled_for.as_secs_f32(),
+                    phase.hint()
+                );
+                let _ = app_handle.emit(
+                    "bevy-stalled",
+                    StalledPayload {
+                        stalled_for_ms: stalled_for.as_millis() as u64,
+                        phase: format!("{:?}", phase),
+                    },
+                );
+            }
+        })
+        .expect("failed to spawn the watchdog thread");
+    heartbeat
+}
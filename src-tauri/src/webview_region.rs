@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use tauri::{LogicalPosition, LogicalSize, WebviewWindow, WebviewWindowBuilder};
+
+use crate::tauri_plugin::RenderWindow;
+
+// Where the webview sits in bevy's window, see TauriPlugin::with_webview_region.
+// Logical pixels from the window edges, placed like CSS absolute positioning:
+// an edge left None is placed by the size, a size left None stretches between
+// the edges, and with neither the webview spans the window along that axis.
+#[derive(Resource, Clone, Copy, Default, Debug)]
+pub struct WebviewRegion {
+    pub left: Option<f64>,
+    pub top: Option<f64>,
+    pub right: Option<f64>,
+    pub bottom: Option<f64>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+}
+
+impl WebviewRegion {
+    // A panel `width` wide along the right edge, as tall as the window
+    pub fn right_panel(width: f64) -> Self {
+        Self {
+            right: Some(0.0),
+            width: Some(width),
+            ..default()
+        }
+    }
+
+    fn bounds(&self, window: LogicalSize<f64>) -> (LogicalPosition<f64>, LogicalSize<f64>) {
+        let (x, width) = span(self.left, self.right, self.width, window.width);
+        let (y, height) = span(self.top, self.bottom, self.height, window.height);
+        (LogicalPosition::new(x, y), LogicalSize::new(width, height))
+    }
+}
+
+// Start and length along one axis of a window `extent` long
+fn span(start: Option<f64>, end: Option<f64>, length: Option<f64>, extent: f64) -> (f64, f64) {
+    let (start, length) = match (start, end, length) {
+        (start, None, Some(length)) => (start.unwrap_or(0.0), length),
+        (None, Some(end), Some(length)) => (extent - end - length, length),
+        // both edges and a size, the size wins like in CSS
+        (Some(start), Some(_), Some(length)) => (start, length),
+        (start, end, None) => {
+            let start = start.unwrap_or(0.0);
+            (start, extent - start - end.unwrap_or(0.0))
+        }
+    };
+    (start, length.max(0.0))
+}
+
+// Creates the window `label` from its config when tauri didn't because it has
+// "create": false, see TauriPlugin::with_webview_region.
+// With a region it is a bare window bevy fills, with a child webview in the
+// region on top, labeled like the window so it is still found as a webview
+// window. Child webviews are desktop only, on mobile the webview covers the
+// whole window as usual.
+pub fn create_window(
+    app: &tauri::App,
+    label: &str,
+    region: Option<WebviewRegion>,
+) -> Result<(), String> {
+    let config = app
+        .config()
+        .app
+        .windows
+        .iter()
+        .find(|config| config.label == label)
+        .ok_or_else(|| format!("tauri.conf.json has no window labeled {}", label))?
+        .clone();
+    #[cfg(desktop)]
+    if let Some(region) = region {
+        let window = tauri::window::WindowBuilder::from_config(app, &config)
+            .and_then(|builder| builder.build())
+            .map_err(|err| format!("failed to create the {} window: {}", label, err))?;
+        let size = window
+            .inner_size()
+            .and_then(|size| Ok(size.to_logical(window.scale_factor()?)))
+            .map_err(|err| err.to_string())?;
+        let (position, size) = region.bounds(size);
+        window
+            .add_child(
+                tauri::webview::WebviewBuilder::from_config(&config),
+                position,
+                size,
+            )
+            .map_err(|err| format!("failed to create the {} webview: {}", label, err))?;
+        bevy::log::info!("webview placed at {:?}, {:?}", position, size);
+        return Ok(());
+    }
+    #[cfg(mobile)]
+    if region.is_some() {
+        bevy::log::warn!("child webviews are desktop only, the webview covers the window");
+    }
+    WebviewWindowBuilder::from_config(app, &config)
+        .and_then(|builder| builder.build())
+        .map(drop)
+        .map_err(|err| format!("failed to create the {} window: {}", label, err))
+}
+
+fn fit(window: &WebviewWindow, region: &WebviewRegion) -> tauri::Result<()> {
+    let (position, size) = region.bounds(window.inner_size()?.to_logical(window.scale_factor()?));
+    let webview: &tauri::Webview = window.as_ref();
+    webview.set_position(position)?;
+    webview.set_size(size)
+}
+
+// Moves the webview back into its region after the window was resized or
+// changed scale factor. Child webviews otherwise keep their size.
+pub fn fit_to_window(world: &World) {
+    let Some(region) = world.get_resource::<WebviewRegion>() else {
+        return;
+    };
+    let app_handle = world.non_send_resource::<tauri::AppHandle>();
+    let Some(window) = world.resource::<RenderWindow>().get(app_handle) else {
+        return;
+    };
+    if let Err(err) = fit(&window, region) {
+        bevy::log::warn!("failed to fit the webview to the window: {}", err);
+    }
+}
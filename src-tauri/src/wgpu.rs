@@ -1,5 +1,11 @@
 use std::{borrow::Cow, sync::Mutex};
-use tauri::{async_runtime::block_on, Manager, RunEvent, WindowEvent};
+use tauri::{async_runtime::block_on, Manager, RunEvent, State, WindowEvent};
+use tokio::sync::oneshot;
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::diagnostics::{self, SurfaceInfo};
+use crate::frame_stats;
+use crate::tauri_plugin::{self, RenderWindow};
 
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
@@ -8,62 +14,306 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[tauri::command]
-fn get_average_frame_rate() -> usize {
-    0
+
+
+// Settings of the triangle demo from the command line
+#[derive(Clone, Copy, Debug)]
+pub struct TriangleOptions {
+    // MSAA sample count, 1 renders without multisampling
+    pub sample_count: u32,
+    // inner size of the window in physical pixels, instead of the one in tauri.conf.json
+    pub size: Option<(u32, u32)>,
+    // clear every frame to transparent, or draw over what the target holds, see set_wgpu_clear
+    pub clear: bool,
 }
 
+impl Default for TriangleOptions {
+    fn default() -> Self {
+        Self {
+            sample_count: 1,
+            size: None,
+            clear: true,
+        }
+    }
+}
 
-pub fn setup_wgpu() {
+// `instance` configures the wgpu instance of the triangle demo, e.g. the DX12
+// shader compiler. `surface_format` overrides the sRGB format picked by default,
+// see choose_surface_format.
+pub fn setup_wgpu(
+    instance: wgpu::InstanceDescriptor,
+    surface_format: Option<wgpu::TextureFormat>,
+    options: TriangleOptions,
+) {
     tauri::Builder::default()
-            .setup(move |app| {
-                return setup_wgpu_handler(app);
-            })
-            .invoke_handler(tauri::generate_handler![greet])
-            .invoke_handler(tauri::generate_handler![get_average_frame_rate])
+            .setup(move |app| setup_wgpu_handler(app, instance, surface_format, options))
+            .invoke_handler(tauri::generate_handler![
+                greet,
+                frame_stats::get_average_frame_rate,
+                diagnostics::get_surface_info,
+                set_wgpu_clear,
+            ])
             .build(crate::generate_tauri_context())
             .expect("error while building tauri application")
             .run(move |app_handle, event: RunEvent| {
-                wgpu_callback(app_handle, event);
+                frame_stats::handle_window_destroyed(&event);
+                wgpu_callback(app_handle, &event);
             });
 }
 
-pub fn setup_wgpu_handler(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+pub fn setup_wgpu_handler(
+    app: &tauri::App,
+    instance: wgpu::InstanceDescriptor,
+    surface_format: Option<wgpu::TextureFormat>,
+    options: TriangleOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
     let window = app.get_webview_window("main").unwrap();
-            let size = window.inner_size()?;
-
-            let instance = wgpu::Instance::default();
-
-            let surface = instance.create_surface(window).unwrap();
-            let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                force_fallback_adapter: false,
-                // Request an adapter which can render to our surface
-                compatible_surface: Some(&surface),
-            }))
-            .expect("Failed to find an appropriate adapter");
-
-            // Create the logical device and command queue
-            let (device, queue) = block_on(
-                adapter.request_device(
-                    &wgpu::DeviceDescriptor {
-                        label: None,
-                        memory_hints: wgpu::MemoryHints::default(),
-                        required_features: wgpu::Features::empty(),
-                        // Make sure we use the texture resolution limits from the adapter, so we can support images the size of the swapchain.
-                        required_limits: wgpu::Limits::downlevel_webgl2_defaults()
-                            .using_resolution(adapter.limits()),
+    setup_wgpu_window(app.handle(), window, instance, surface_format, options)
+}
+
+// Opens a second window running the triangle demo, used when bevy owns "main"
+pub fn setup_wgpu_secondary_window(
+    app: &tauri::App,
+    instance: wgpu::InstanceDescriptor,
+    surface_format: Option<wgpu::TextureFormat>,
+    options: TriangleOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let window = tauri::WebviewWindowBuilder::new(app, "wgpu", tauri::WebviewUrl::default())
+        .title("Tauri + React + wgpu Example")
+        .inner_size(800.0, 600.0)
+        .decorations(false)
+        .transparent(true)
+        .build()?;
+    setup_wgpu_window(app.handle(), window, instance, surface_format, options)
+}
+
+// `requested` if the surface supports it, otherwise the first sRGB format like
+// bevy picks for its surface, otherwise the first format. Colors written to a
+// linear format aren't gamma encoded and show up too dark.
+pub fn choose_surface_format(
+    formats: &[wgpu::TextureFormat],
+    requested: Option<wgpu::TextureFormat>,
+) -> Result<wgpu::TextureFormat, String> {
+    if let Some(requested) = requested {
+        if formats.contains(&requested) {
+            return Ok(requested);
+        }
+        bevy::log::warn!("surface doesn't support {:?}, choosing another format", requested);
+    }
+    formats
+        .iter()
+        .find(|format| format.is_srgb())
+        .or(formats.first())
+        .copied()
+        .ok_or_else(|| String::from("the surface supports no formats"))
+}
+
+// The surface formats --surface-format accepts, named like in WebGPU
+pub fn surface_format_from_name(name: &str) -> Option<wgpu::TextureFormat> {
+    match name {
+        "bgra8unorm" => Some(wgpu::TextureFormat::Bgra8Unorm),
+        "bgra8unorm-srgb" => Some(wgpu::TextureFormat::Bgra8UnormSrgb),
+        "rgba8unorm" => Some(wgpu::TextureFormat::Rgba8Unorm),
+        "rgba8unorm-srgb" => Some(wgpu::TextureFormat::Rgba8UnormSrgb),
+        "rgb10a2unorm" => Some(wgpu::TextureFormat::Rgb10a2Unorm),
+        "rgba16float" => Some(wgpu::TextureFormat::Rgba16Float),
+        _ => None,
+    }
+}
+
+// Everything the triangle demo renders with
+struct Triangle {
+    // label of the window it renders to
+    window: String,
+    surface: wgpu::Surface<'static>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::RenderPipeline,
+    config: wgpu::SurfaceConfiguration,
+    sample_count: u32,
+    // multisampled color target resolved into the surface texture, None without MSAA
+    msaa: Option<wgpu::TextureView>,
+    clear: bool,
+}
+
+impl Triangle {
+    // Also recreates the MSAA target at the size of the surface
+    fn configure(&mut self) {
+        self.surface.configure(&self.device, &self.config);
+        self.msaa = (self.sample_count > 1).then(|| {
+            self.device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some("msaa target"),
+                    size: wgpu::Extent3d {
+                        width: self.config.width,
+                        height: self.config.height,
+                        depth_or_array_layers: 1,
                     },
+                    mip_level_count: 1,
+                    sample_count: self.sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: self.config.format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+    }
+}
+
+// The running triangle demo, None before it starts and after stop_triangle
+#[derive(Default)]
+struct TriangleState(Mutex<Option<Triangle>>);
+
+// Drops the triangle demo's surface and device so another renderer can use
+// its window, returns false if it wasn't running
+pub fn stop_triangle(app: &tauri::AppHandle) -> bool {
+    app.try_state::<TriangleState>()
+        .and_then(|state| state.0.lock().unwrap().take())
+        .is_some()
+}
+
+// Switches bevy's window between "bevy" and the raw wgpu triangle demo
+// ("wgpu"). Bevy keeps updating without a surface while the triangle draws,
+// so the scene is where it left off when switching back. Only registered when
+// bevy runs, `--use-wgpu` has no bevy renderer to switch to.
+#[tauri::command]
+pub async fn set_renderer(
+    app: tauri::AppHandle,
+    bridge: State<'_, BevyBridge>,
+    mode: String,
+) -> Result<(), CommandError> {
+    match mode.as_str() {
+        "wgpu" => {
+            bridge.run(tauri_plugin::release_surface).await?;
+            // the render world drops the surface when it extracts that update
+            bridge.run(|_| ()).await?;
+            let window = app
+                .state::<RenderWindow>()
+                .get(&app)
+                .ok_or_else(|| String::from("bevy's window is gone"))?;
+            let (reply, rx) = oneshot::channel();
+            let handle = app.clone();
+            app.run_on_main_thread(move || {
+                let result = setup_wgpu_window(
+                    &handle,
+                    window,
+                    wgpu::InstanceDescriptor::default(),
                     None,
-                ),
-            )
-            .expect("Failed to create device");
-
-            // Load the shaders from disk
-            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: None,
-                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(
-                    r#"
+                    TriangleOptions::default(),
+                )
+                .map_err(|err| err.to_string());
+                let _ = reply.send(result);
+            })
+            .map_err(|err| err.to_string())?;
+            let result = rx
+                .await
+                .map_err(|_| String::from("the wgpu demo didn't start"))?;
+            if result.is_err() {
+                // give the window back to bevy
+                bridge.run(tauri_plugin::recreate_surface).await?;
+            }
+            result.map_err(CommandError::Failed)
+        }
+        "bevy" => {
+            stop_triangle(&app);
+            bridge.run(tauri_plugin::recreate_surface).await
+        }
+        _ => Err(CommandError::InvalidArgument(format!(
+            "unknown renderer {:?}, expected bevy or wgpu",
+            mode
+        ))),
+    }
+}
+
+// Switches the triangle demo between clearing its target to transparent every
+// frame and loading what's there (LoadOp::Load) to draw over it. Loading
+// doesn't bring back the webview: it's composited by the OS, never part of the
+// surface. What's loaded depends on the platform. Vulkan and DX12 swapchains
+// cycle through 2-3 images, so it's the frame from that many presents ago,
+// and GL and Metal may hand out undefined contents. With MSAA it's the
+// multisampled texture, which holds the last frame on every platform, until a
+// resize recreates it.
+#[tauri::command]
+pub fn set_wgpu_clear(app: tauri::AppHandle, clear: bool) -> Result<(), CommandError> {
+    let state = app
+        .try_state::<TriangleState>()
+        .ok_or_else(|| CommandError::NotReady(String::from("the wgpu demo isn't running")))?;
+    let mut triangle = state.0.lock().unwrap();
+    let triangle = triangle
+        .as_mut()
+        .ok_or_else(|| CommandError::NotReady(String::from("the wgpu demo isn't running")))?;
+    triangle.clear = clear;
+    Ok(())
+}
+
+// Starts the triangle demo in `window`, replacing the one running if any
+pub fn setup_wgpu_window(
+    app: &tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    descriptor: wgpu::InstanceDescriptor,
+    surface_format: Option<wgpu::TextureFormat>,
+    options: TriangleOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let label = window.label().to_string();
+    let size = match options.size {
+        Some((width, height)) => {
+            let size = tauri::PhysicalSize::new(width, height);
+            window.set_size(size)?;
+            size
+        }
+        None => window.inner_size()?,
+    };
+
+    bevy::log::info!("creating the wgpu instance with {:?}", descriptor);
+    let instance = wgpu::Instance::new(descriptor);
+
+    let surface = instance
+        .create_surface(window)
+        .map_err(|err| format!("failed to create the triangle's surface: {}", err))?;
+    let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::default(),
+        force_fallback_adapter: false,
+        // Request an adapter which can render to our surface
+        compatible_surface: Some(&surface),
+    }))
+    .ok_or("no adapter can render to the triangle's surface")?;
+
+    // sample counts other than 1 and 4 aren't guaranteed by WebGPU, the
+    // adapter has to report them
+    let adapter_sample_counts = options.sample_count != 1 && options.sample_count != 4;
+    let required_features = if adapter_sample_counts {
+        wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+    } else {
+        wgpu::Features::empty()
+    };
+    if !adapter.features().contains(required_features) {
+        return Err(format!(
+            "{}x MSAA needs adapter specific format features, use 1 or 4",
+            options.sample_count
+        )
+        .into());
+    }
+
+    // Create the logical device and command queue
+    let (device, queue) = block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+            memory_hints: wgpu::MemoryHints::default(),
+            required_features,
+            // Make sure we use the texture resolution limits from the adapter, so we can support images the size of the swapchain.
+            required_limits:
+                wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits()),
+        },
+        None,
+    ))
+    .map_err(|err| format!("failed to create the triangle's device: {}", err))?;
+
+    // Load the shaders from disk
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(
+            r#"
 @vertex
 fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> @builtin(position) vec4<f32> {
     let x = f32(i32(in_vertex_index) - 1);
@@ -76,103 +326,155 @@ fn fs_main() -> @location(0) vec4<f32> {
     return vec4<f32>(1.0, 0.0, 0.0, 1.0);
 }
 "#,
-                )),
-            });
+        )),
+    });
 
-            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: None,
-                bind_group_layouts: &[],
-                push_constant_ranges: &[],
-            });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
 
-            let swapchain_capabilities = surface.get_capabilities(&adapter);
-            let swapchain_format = swapchain_capabilities.formats[0];
-
-            let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                cache: None,
-                label: None,
-                layout: Some(&pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_main"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    buffers: &[],
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader,
-                    entry_point: Some("fs_main"),
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    targets: &[Some(swapchain_format.into())],
-                }),
-                primitive: wgpu::PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
-                multiview: None,
-            });
+    let swapchain_capabilities = surface.get_capabilities(&adapter);
+    diagnostics::store_surface_info(app, SurfaceInfo::new(&swapchain_capabilities));
+    let swapchain_format = choose_surface_format(&swapchain_capabilities.formats, surface_format)?;
+    bevy::log::info!("triangle demo renders to {:?}", swapchain_format);
+    let format_features = adapter.get_texture_format_features(swapchain_format);
+    if !format_features
+        .flags
+        .sample_count_supported(options.sample_count)
+    {
+        return Err(format!(
+            "{:?} doesn't support {}x MSAA, only {:?}",
+            swapchain_format,
+            options.sample_count,
+            format_features.flags.supported_sample_counts()
+        )
+        .into());
+    }
 
-            let config = wgpu::SurfaceConfiguration {
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                format: swapchain_format,
-                width: size.width,
-                height: size.height,
-                present_mode: wgpu::PresentMode::Fifo,
-                alpha_mode: swapchain_capabilities.alpha_modes[0],
-                view_formats: vec![],
-                desired_maximum_frame_latency: 2,
-            };
+    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        cache: None,
+        label: None,
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(swapchain_format.into())],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: options.sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    });
+
+    let config = wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format: swapchain_format,
+        width: size.width,
+        height: size.height,
+        present_mode: wgpu::PresentMode::Fifo,
+        alpha_mode: swapchain_capabilities.alpha_modes[0],
+        view_formats: vec![],
+        desired_maximum_frame_latency: 2,
+    };
 
-            surface.configure(&device, &config);
+    let mut triangle = Triangle {
+        window: label,
+        surface,
+        device,
+        queue,
+        pipeline: render_pipeline,
+        config,
+        sample_count: options.sample_count,
+        msaa: None,
+        clear: options.clear,
+    };
+    triangle.configure();
 
-            app.manage(surface);
-            app.manage(render_pipeline);
-            app.manage(device);
-            app.manage(queue);
-            app.manage(Mutex::new(config));
+    // only the first demo's state is managed, later ones replace its contents
+    app.manage(TriangleState::default());
+    *app.state::<TriangleState>().0.lock().unwrap() = Some(triangle);
 
     Ok(())
-} 
+}
 
+pub fn wgpu_callback(app_handle: &tauri::AppHandle, event: &RunEvent) {
+    let Some(state) = app_handle.try_state::<TriangleState>() else {
+        return;
+    };
+    let mut triangle = state.0.lock().unwrap();
+    let Some(triangle) = triangle.as_mut() else {
+        return;
+    };
 
-pub fn wgpu_callback(app_handle: &tauri::AppHandle, event: RunEvent) {
     match event {
         RunEvent::WindowEvent {
-            label: _,
+            label,
             event: WindowEvent::Resized(size),
             ..
-        } => {
-            let config = app_handle.state::<Mutex<wgpu::SurfaceConfiguration>>();
-            let surface = app_handle.state::<wgpu::Surface>();
-            let device = app_handle.state::<wgpu::Device>();
-
-            let mut config = config.lock().unwrap();
-            config.width = if size.width > 0 { size.width } else { 1 };
-            config.height = if size.height > 0 { size.height } else { 1 };
-            surface.configure(&device, &config)
+        } if *label == triangle.window => {
+            triangle.config.width = if size.width > 0 { size.width } else { 1 };
+            triangle.config.height = if size.height > 0 { size.height } else { 1 };
+            triangle.configure();
 
             // TODO: Request redraw on macos (not exposed in tauri yet).
         }
-        RunEvent::MainEventsCleared => {
-            let surface = app_handle.state::<wgpu::Surface>();
-            let render_pipeline = app_handle.state::<wgpu::RenderPipeline>();
-            let device = app_handle.state::<wgpu::Device>();
-            let queue = app_handle.state::<wgpu::Queue>();
-
-            let frame = surface
-                .get_current_texture()
-                .expect("Failed to acquire next swap chain texture");
+        // the window may have been closed while others keep the event loop running
+        RunEvent::MainEventsCleared if app_handle.get_webview_window(&triangle.window).is_some() => {
+            let frame = match triangle.surface.get_current_texture() {
+                Ok(frame) => frame,
+                Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                    // the window changed size before its Resized event arrived,
+                    // or the surface has to be configured again, try next frame
+                    if let Some(size) = app_handle
+                        .get_webview_window(&triangle.window)
+                        .and_then(|window| window.inner_size().ok())
+                    {
+                        triangle.config.width = size.width.max(1);
+                        triangle.config.height = size.height.max(1);
+                    }
+                    triangle.configure();
+                    return;
+                }
+                Err(err) => {
+                    bevy::log::warn!("skipping a triangle frame: {}", err);
+                    return;
+                }
+            };
             let view = frame
                 .texture
                 .create_view(&wgpu::TextureViewDescriptor::default());
-            let mut encoder = device
+            let mut encoder = triangle
+                .device
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+            // with MSAA the triangle is drawn multisampled and resolved into the frame
+            let (target, resolve_target) = match triangle.msaa.as_ref() {
+                Some(msaa_view) => (msaa_view, Some(&view)),
+                None => (&view, None),
+            };
             {
                 let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: None,
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
-                        resolve_target: None,
+                        view: target,
+                        resolve_target,
                         ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            load: if triangle.clear {
+                                wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT)
+                            } else {
+                                wgpu::LoadOp::Load
+                            },
                             store: wgpu::StoreOp::Store,
                         },
                     })],
@@ -180,13 +482,40 @@ pub fn wgpu_callback(app_handle: &tauri::AppHandle, event: RunEvent) {
                     timestamp_writes: None,
                     occlusion_query_set: None,
                 });
-                rpass.set_pipeline(&render_pipeline);
+                rpass.set_pipeline(&triangle.pipeline);
                 rpass.draw(0..3, 0..1);
             }
 
-            queue.submit(Some(encoder.finish()));
+            triangle.queue.submit(Some(encoder.finish()));
             frame.present();
+            frame_stats::record_present(&triangle.window);
         }
         _ => (),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::TextureFormat;
+
+    #[test]
+    fn choose_surface_format_prefers_the_request_then_srgb() {
+        let formats = [
+            TextureFormat::Bgra8Unorm,
+            TextureFormat::Bgra8UnormSrgb,
+            TextureFormat::Rgba16Float,
+        ];
+        let requested = surface_format_from_name("rgba16float");
+        assert_eq!(
+            choose_surface_format(&formats, requested),
+            Ok(TextureFormat::Rgba16Float)
+        );
+        let unsupported = Some(TextureFormat::Rgb10a2Unorm);
+        assert_eq!(
+            choose_surface_format(&formats, unsupported),
+            Ok(TextureFormat::Bgra8UnormSrgb)
+        );
+        assert!(choose_surface_format(&[], None).is_err());
+    }
+}
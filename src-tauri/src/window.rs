@@ -0,0 +1,386 @@
+use bevy::prelude::*;
+use bevy::window::{CompositeAlphaMode, PrimaryWindow, WindowResizeConstraints};
+use serde::Serialize;
+use tauri::{LogicalUnit, Manager, PixelUnit, State, WebviewWindow, WindowSizeConstraints};
+
+use crate::bridge::{BevyBridge, CommandError};
+use crate::child_window;
+use crate::diagnostics;
+use crate::environment::SceneSettings;
+use crate::tauri_plugin::{self, RenderWindow};
+
+// Window controls for a title bar drawn in HTML when decorations are disabled
+
+#[tauri::command]
+pub fn start_window_drag(window: WebviewWindow) -> Result<(), CommandError> {
+    window
+        .start_dragging()
+        .map_err(|err| CommandError::Failed(err.to_string()))
+}
+
+#[tauri::command]
+pub fn minimize_window(window: WebviewWindow) -> Result<(), CommandError> {
+    window
+        .minimize()
+        .map_err(|err| CommandError::Failed(err.to_string()))
+}
+
+#[tauri::command]
+pub fn toggle_maximize_window(window: WebviewWindow) -> Result<(), CommandError> {
+    if window.is_maximized().map_err(|err| err.to_string())? {
+        window
+            .unmaximize()
+            .map_err(|err| CommandError::Failed(err.to_string()))
+    } else {
+        window
+            .maximize()
+            .map_err(|err| CommandError::Failed(err.to_string()))
+    }
+}
+
+// Closes the window labeled `label`, the calling window without one
+#[tauri::command]
+pub async fn close_window(
+    app: tauri::AppHandle,
+    window: WebviewWindow,
+    bridge: State<'_, BevyBridge>,
+    label: Option<String>,
+) -> Result<(), CommandError> {
+    let label = label.unwrap_or_else(|| window.label().to_string());
+    child_window::close(&app, &bridge, &label).await
+}
+
+// Position and size in physical pixels of the virtual desktop
+#[derive(Serialize, Clone, Debug)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub position: [i32; 2],
+    pub size: [u32; 2],
+    pub scale_factor: f64,
+    // None where the platform doesn't report it
+    pub refresh_rate: Option<f64>,
+}
+
+impl MonitorInfo {
+    fn new(monitor: &tauri::Monitor) -> Self {
+        let position = monitor.position();
+        let size = monitor.size();
+        Self {
+            name: monitor.name().cloned(),
+            position: [position.x, position.y],
+            size: [size.width, size.height],
+            scale_factor: monitor.scale_factor(),
+            refresh_rate: tauri_plugin::monitor_refresh_rate(monitor),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn list_monitors(app: tauri::AppHandle) -> Vec<MonitorInfo> {
+    match app.available_monitors() {
+        Ok(monitors) => monitors.iter().map(MonitorInfo::new).collect(),
+        Err(err) => {
+            bevy::log::warn!("failed to list monitors: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+// Rebuilds bevy's surface, see tauri_plugin::recreate_surface
+#[tauri::command]
+pub async fn recreate_surface(bridge: State<'_, BevyBridge>) -> Result<(), CommandError> {
+    bridge.run(tauri_plugin::recreate_surface).await
+}
+
+#[cfg(target_os = "linux")]
+fn apply_window_opacity(window: &WebviewWindow, alpha: f64) -> Result<(), String> {
+    use gtk::prelude::WidgetExt;
+    window
+        .gtk_window()
+        .map_err(|err| err.to_string())?
+        .set_opacity(alpha);
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn apply_window_opacity(window: &WebviewWindow, alpha: f64) -> Result<(), String> {
+    let ns_window = window.ns_window().map_err(|err| err.to_string())?;
+    // SAFETY: tauri returns a live NSWindow, and sync commands run on the main thread
+    unsafe {
+        let ns_window = &*ns_window.cast::<objc2::runtime::AnyObject>();
+        let _: () = objc2::msg_send![ns_window, setAlphaValue: alpha];
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn apply_window_opacity(_window: &WebviewWindow, _alpha: f64) -> Result<(), String> {
+    bevy::log::warn!("window opacity isn't supported on this platform");
+    Ok(())
+}
+
+// Fades the calling window as a whole, webview and bevy's surface together,
+// e.g. for splash screens. `alpha` is clamped to 0..=1. It is applied by the
+// window system on top of the surface alpha of set_webview_transparent, which
+// it leaves alone. Linux needs a compositing window manager and X11, Wayland
+// ignores it; on Windows and mobile it only logs a warning.
+#[tauri::command]
+pub fn set_window_opacity(window: WebviewWindow, alpha: f32) -> Result<(), CommandError> {
+    if alpha.is_nan() {
+        return Err(CommandError::InvalidArgument(String::from(
+            "opacity is NaN",
+        )));
+    }
+    apply_window_opacity(&window, alpha.clamp(0.0, 1.0) as f64).map_err(CommandError::Failed)
+}
+
+// Alpha modes that let the window behind bevy's surface show through, in order
+// of preference. Names as listed by get_surface_info.
+const TRANSPARENT_ALPHA_MODES: [(&str, CompositeAlphaMode); 3] = [
+    ("PreMultiplied", CompositeAlphaMode::PreMultiplied),
+    ("PostMultiplied", CompositeAlphaMode::PostMultiplied),
+    ("Inherit", CompositeAlphaMode::Inherit),
+];
+
+// Switches bevy's surface between opaque and transparent, setting the clear
+// color alpha to match, so the layering of bevy and the window can be checked
+// live. The surface is recreated with the new alpha mode, which takes a frame.
+// The native window must have been created transparent ("transparent" in
+// tauri.conf.json), tauri can't change that afterwards. macOS (Metal) and
+// Linux under a compositing window manager (Vulkan, GL) apply it without a
+// restart; DX12 and Vulkan swapchains on Windows usually only offer Opaque,
+// which makes enabling it an error.
+#[tauri::command]
+pub async fn set_webview_transparent(
+    app: tauri::AppHandle,
+    bridge: State<'_, BevyBridge>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    let info = diagnostics::surface_info(&app)?;
+    let alpha_mode = if enabled {
+        TRANSPARENT_ALPHA_MODES
+            .iter()
+            .find(|(name, _)| info.alpha_modes.iter().any(|mode| mode == name))
+            .map(|(_, mode)| *mode)
+            .ok_or_else(|| {
+                format!(
+                    "the surface doesn't support transparency, only {}",
+                    info.alpha_modes.join(", ")
+                )
+            })?
+    } else {
+        CompositeAlphaMode::Opaque
+    };
+
+    bridge
+        .run(move |world| {
+            let mut windows = world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+            let mut window = windows
+                .get_single_mut(world)
+                .map_err(|_| CommandError::EntityNotFound(String::from("no primary window")))?;
+            window.composite_alpha_mode = alpha_mode;
+            window.transparent = enabled;
+            // EnvironmentPlugin owns ClearColor
+            world.resource_mut::<SceneSettings>().clear_color[3] = if enabled { 0.0 } else { 1.0 };
+            tauri_plugin::recreate_surface(world);
+            Ok(())
+        })
+        .await?
+}
+
+// Logical inner size limits of a window, None or 0 leaves a dimension
+// unconstrained. Built with `new`, which rejects minimums above maximums.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SizeConstraints {
+    min_width: Option<f64>,
+    min_height: Option<f64>,
+    max_width: Option<f64>,
+    max_height: Option<f64>,
+}
+
+impl SizeConstraints {
+    pub fn new(
+        min_width: Option<f64>,
+        min_height: Option<f64>,
+        max_width: Option<f64>,
+        max_height: Option<f64>,
+    ) -> Result<Self, String> {
+        let limit = |value: Option<f64>| match value {
+            Some(value) if !value.is_finite() || value < 0.0 => {
+                Err(format!("invalid size limit {}", value))
+            }
+            Some(value) if value > 0.0 => Ok(Some(value)),
+            _ => Ok(None),
+        };
+        let constraints = Self {
+            min_width: limit(min_width)?,
+            min_height: limit(min_height)?,
+            max_width: limit(max_width)?,
+            max_height: limit(max_height)?,
+        };
+        if let (Some(min), Some(max)) = (constraints.min_width, constraints.max_width) {
+            if min > max {
+                return Err(format!("minimum width {} exceeds maximum {}", min, max));
+            }
+        }
+        if let (Some(min), Some(max)) = (constraints.min_height, constraints.max_height) {
+            if min > max {
+                return Err(format!("minimum height {} exceeds maximum {}", min, max));
+            }
+        }
+        Ok(constraints)
+    }
+
+    // The same limits for bevy's Window, which the runner clamps forwarded
+    // resizes to. Unconstrained minimums stay at 1 so the surface never gets
+    // configured with a zero size, rather than bevy's default of 180x120.
+    pub fn resize_constraints(&self) -> WindowResizeConstraints {
+        let limit = |value: Option<f64>, default: f32| value.map_or(default, |value| value as f32);
+        WindowResizeConstraints {
+            min_width: limit(self.min_width, 1.0),
+            min_height: limit(self.min_height, 1.0),
+            max_width: limit(self.max_width, f32::INFINITY),
+            max_height: limit(self.max_height, f32::INFINITY),
+        }
+    }
+
+    pub fn apply_to(&self, window: &WebviewWindow) -> Result<(), String> {
+        let unit = |value: Option<f64>| value.map(|value| PixelUnit::Logical(LogicalUnit(value)));
+        window
+            .set_size_constraints(WindowSizeConstraints {
+                min_width: unit(self.min_width),
+                min_height: unit(self.min_height),
+                max_width: unit(self.max_width),
+                max_height: unit(self.max_height),
+            })
+            .map_err(|err| err.to_string())
+    }
+}
+
+// Keeps the window from being resized past these logical sizes. Shrinking it
+// to nothing leaves the surface without a usable size.
+#[tauri::command]
+pub async fn set_size_constraints(
+    window: WebviewWindow,
+    bridge: State<'_, BevyBridge>,
+    render_window: State<'_, RenderWindow>,
+    min_w: Option<f64>,
+    min_h: Option<f64>,
+    max_w: Option<f64>,
+    max_h: Option<f64>,
+) -> Result<(), CommandError> {
+    let constraints =
+        SizeConstraints::new(min_w, min_h, max_w, max_h).map_err(CommandError::InvalidArgument)?;
+    constraints.apply_to(&window)?;
+    if window.label() != render_window.0 {
+        return Ok(());
+    }
+    bridge
+        .run(move |world| {
+            let mut windows = world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+            for mut bevy_window in windows.iter_mut(world) {
+                bevy_window.resize_constraints = constraints.resize_constraints();
+            }
+        })
+        .await
+}
+
+// Last title written to or read from bevy's tauri window, so the diff
+// system only calls set_title when bevy's Window::title really changed
+#[derive(Resource, Default)]
+struct SyncedTitle(String);
+
+// Keeps the title of bevy's primary window and its tauri window in sync,
+// starting from the one in tauri.conf.json. Needs the tauri::AppHandle inserted
+// by TauriPlugin, so add it after that one.
+pub struct WindowTitlePlugin;
+
+impl Plugin for WindowTitlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SyncedTitle>()
+            .add_systems(Startup, read_window_title)
+            .add_systems(PostUpdate, write_window_title);
+    }
+}
+
+fn read_window_title(
+    tauri_app: NonSend<tauri::AppHandle>,
+    render_window: Res<RenderWindow>,
+    mut synced: ResMut<SyncedTitle>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Some(title) = render_window
+        .get(&tauri_app)
+        .and_then(|window| window.title().ok())
+    else {
+        return;
+    };
+    for mut window in windows.iter_mut() {
+        window.title = title.clone();
+    }
+    synced.0 = title;
+}
+
+fn write_window_title(
+    tauri_app: NonSend<tauri::AppHandle>,
+    render_window: Res<RenderWindow>,
+    mut synced: ResMut<SyncedTitle>,
+    windows: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    if window.title == synced.0 {
+        return;
+    }
+    if let Some(tauri_window) = render_window.get(&tauri_app) {
+        if let Err(err) = tauri_window.set_title(&window.title) {
+            bevy::log::warn!("failed to set the window title: {}", err);
+        }
+    }
+    synced.0 = window.title.clone();
+}
+
+fn webview_window(app: &tauri::AppHandle, label: &str) -> Result<WebviewWindow, CommandError> {
+    app.get_webview_window(label)
+        .ok_or_else(|| CommandError::EntityNotFound(format!("no window labeled {}", label)))
+}
+
+// `label` defaults to the window bevy renders to, whose title is also bevy's
+// Window::title
+#[tauri::command]
+pub async fn set_window_title(
+    app: tauri::AppHandle,
+    bridge: State<'_, BevyBridge>,
+    render_window: State<'_, RenderWindow>,
+    title: String,
+    label: Option<String>,
+) -> Result<(), CommandError> {
+    let label = label.unwrap_or_else(|| render_window.0.clone());
+    webview_window(&app, &label)?
+        .set_title(&title)
+        .map_err(|err| err.to_string())?;
+    if label != render_window.0 {
+        return Ok(());
+    }
+    bridge
+        .run(move |world| {
+            world.resource_mut::<SyncedTitle>().0 = title.clone();
+            let mut windows = world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+            for mut window in windows.iter_mut(world) {
+                window.title = title.clone();
+            }
+        })
+        .await
+}
+
+#[tauri::command]
+pub fn get_window_title(
+    app: tauri::AppHandle,
+    render_window: State<'_, RenderWindow>,
+    label: Option<String>,
+) -> Result<String, CommandError> {
+    webview_window(&app, label.as_deref().unwrap_or(&render_window.0))?
+        .title()
+        .map_err(|err| CommandError::Failed(err.to_string()))
+}
@@ -1,9 +1,25 @@
-import { useEffect, useState } from "react";
+import { MouseEvent, useEffect, useRef, useState } from "react";
 import reactLogo from "./assets/react.svg";
 import bevyLogo from "./assets/bevy.svg";
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import "./App.css";
 
+// What commands reject with, see CommandError in src-tauri/src/bridge.rs
+type CommandError = {
+  kind: "entity-not-found" | "not-ready" | "invalid-argument" | "failed";
+  message: string;
+};
+
+function isCommandError(e: unknown): e is CommandError {
+  return typeof e === "object" && e !== null && "kind" in e && "message" in e;
+}
+
+function errorMessage(e: unknown): string {
+  return isCommandError(e) ? e.message : String(e);
+}
+
 
 function FpsDisplay() {
 
@@ -13,7 +29,9 @@ function FpsDisplay() {
   useEffect(() => {
     // 创建定时器每秒更新一次FPS
     const timer = setInterval(async () => {
-      const currentFps = await invoke("get_average_frame_rate");
+      const currentFps = await invoke("get_average_frame_rate", {
+        label: getCurrentWebviewWindow().label,
+      });
       setFps(currentFps as number);
     }, 1000);
 
@@ -29,10 +47,945 @@ function FpsDisplay() {
 }
 
 
+type HoverPayload = {
+  entity: number | null;
+  name: string | null;
+};
+
+function HoverDisplay() {
+
+  const [hovered, setHovered] = useState<HoverPayload | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<HoverPayload>("entity-hovered", (event) => {
+      setHovered(event.payload);
+    });
+
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  if (!hovered || hovered.entity === null) {
+    return null;
+  }
+
+  return (
+    <div>
+      Hovered: {hovered.name ?? hovered.entity}
+    </div>
+  );
+}
+
+// Recolors the entity selected by clicking it, e.g. the satellite
+function EntityColor() {
+
+  const [selected, setSelected] = useState<HoverPayload | null>(null);
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    const unlisten = listen<HoverPayload>("entity-selected", (event) => {
+      setSelected(event.payload);
+      setError("");
+    });
+
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  if (!selected || selected.entity === null) {
+    return null;
+  }
+
+  async function setColor(hex: string) {
+    const [r, g, b] = [1, 3, 5].map((i) => parseInt(hex.slice(i, i + 2), 16) / 255);
+    try {
+      await invoke("set_entity_color", { entityId: selected!.entity, r, g, b });
+      setError("");
+    } catch (err) {
+      setError(errorMessage(err));
+    }
+  }
+
+  return (
+    <div>
+      Color of {selected.name ?? selected.entity}:{" "}
+      <input type="color" onChange={(e) => setColor(e.currentTarget.value)} />
+      {error && <p>{error}</p>}
+    </div>
+  );
+}
+
+
+// An HTML label that stays over the selected entity
+function SelectionLabel() {
+
+  const [selected, setSelected] = useState<HoverPayload | null>(null);
+  const [position, setPosition] = useState<[number, number] | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<HoverPayload>("entity-selected", (event) => {
+      setSelected(event.payload);
+    });
+
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!selected || selected.entity === null) {
+      setPosition(null);
+      return;
+    }
+    const timer = setInterval(async () => {
+      try {
+        setPosition(await invoke<[number, number] | null>("world_to_screen", {
+          entityId: selected.entity,
+        }));
+      } catch (e) {
+        setPosition(null);
+        if (isCommandError(e) && e.kind === "entity-not-found") {
+          // despawned, stop polling until the next selection
+          setSelected(null);
+        }
+      }
+    }, 33);
+    return () => clearInterval(timer);
+  }, [selected]);
+
+  if (!selected || !position) {
+    return null;
+  }
+
+  return (
+    <div style={{
+      position: "fixed",
+      left: position[0],
+      top: position[1] - 32,
+      transform: "translateX(-50%)",
+      pointerEvents: "none",
+    }}>
+      {selected.name ?? selected.entity}
+    </div>
+  );
+}
+
+
+type TransformData = {
+  position: [number, number, number];
+  rotation: [number, number, number];
+  scale: [number, number, number];
+};
+
+// Edits the transform of the selected entity, rotation in degrees
+function TransformInspector() {
+
+  const [entity, setEntity] = useState<number | null>(null);
+  const [transform, setTransform] = useState<TransformData | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<HoverPayload>("entity-selected", async (event) => {
+      const id = event.payload.entity;
+      setEntity(id);
+      setTransform(id === null ? null : await invoke<TransformData>("get_transform", { entityId: id }));
+    });
+
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  if (entity === null || !transform) {
+    return null;
+  }
+
+  async function update(field: keyof TransformData, axis: number, value: number) {
+    const next = { ...transform!, [field]: [...transform![field]] };
+    next[field][axis] = value;
+    setTransform(next);
+    await invoke("set_transform", {
+      entityId: entity, pos: next.position, rot: next.rotation, scale: next.scale,
+    });
+  }
+
+  return (
+    <div>
+      {(["position", "rotation", "scale"] as const).map((field) => (
+        <div key={field}>
+          {field}:{" "}
+          {transform[field].map((value, axis) => (
+            <input
+              key={axis}
+              type="number"
+              step={field === "rotation" ? 5 : 0.1}
+              value={Number(value.toFixed(2))}
+              onChange={(e) => update(field, axis, e.currentTarget.valueAsNumber || 0)}
+            />
+          ))}
+        </div>
+      ))}
+    </div>
+  );
+}
+
+
+type GamepadPayload = {
+  id: number;
+  name: string | null;
+};
+
+function GamepadStatus() {
+
+  const [gamepads, setGamepads] = useState<GamepadPayload[]>([]);
+
+  useEffect(() => {
+    // pads connected before the listeners existed are only reported by get_gamepads
+    invoke<GamepadPayload[]>("get_gamepads").then(setGamepads);
+
+    const connected = listen<GamepadPayload>("gamepad-connected", (event) => {
+      setGamepads((pads) => [...pads.filter((pad) => pad.id !== event.payload.id), event.payload]);
+    });
+    const disconnected = listen<GamepadPayload>("gamepad-disconnected", (event) => {
+      setGamepads((pads) => pads.filter((pad) => pad.id !== event.payload.id));
+    });
+
+    return () => {
+      connected.then((f) => f());
+      disconnected.then((f) => f());
+    };
+  }, []);
+
+  return (
+    <>
+      {gamepads.map((pad) => (
+        <div key={pad.id}>
+          Gamepad: {pad.name ?? pad.id}
+        </div>
+      ))}
+    </>
+  );
+}
+
+
+type StalledPayload = {
+  stalled_for_ms: number;
+  phase: string;
+};
+
+interface RendererStatus {
+  surface: boolean;
+  adapter: boolean;
+  device: boolean;
+  backend: string | null;
+  adapter_name: string | null;
+  error: string | null;
+}
+
+// Explains a renderer that failed to start instead of leaving the window blank.
+// The event may fire before this mounts, so the status is also asked for once.
+function RendererError() {
+
+  const [status, setStatus] = useState<RendererStatus | null>(null);
+
+  useEffect(() => {
+    invoke<RendererStatus>("get_renderer_status").then(setStatus).catch(() => {});
+    const unlisten = listen<RendererStatus>("renderer-failed", (event) => {
+      setStatus(event.payload);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  if (!status?.error) {
+    return null;
+  }
+  const step = (done: boolean) => (done ? "ok" : "failed");
+  return (
+    <div className="renderer-error">
+      <h2>The 3D view couldn't start</h2>
+      <p>{status.error}</p>
+      <ul>
+        <li>Window surface: {step(status.surface)}</li>
+        <li>
+          GPU adapter: {step(status.adapter)}
+          {status.adapter_name && ` (${status.adapter_name}, ${status.backend})`}
+        </li>
+        <li>Device: {step(status.device)}</li>
+      </ul>
+      <p>Try starting with --safe-mode, or --adapter to pick another GPU.</p>
+    </div>
+  );
+}
+
+
+// Only arrives once the stall is over, the main thread delivers events
+function StallWarning() {
+
+  const [stall, setStall] = useState<StalledPayload | null>(null);
+
+  useEffect(() => {
+    const unlisten = listen<StalledPayload>("bevy-stalled", (event) => {
+      setStall(event.payload);
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  if (!stall) {
+    return null;
+  }
+  return (
+    <div onClick={() => setStall(null)}>
+      Bevy stalled for {(stall.stalled_for_ms / 1000).toFixed(1)}s ({stall.phase})
+    </div>
+  );
+}
+
+
+function OffscreenCanvas() {
+
+  const canvas = useRef<HTMLCanvasElement>(null);
+  const [enabled, setEnabled] = useState(false);
+
+  useEffect(() => {
+    // frames are a width and height as little-endian u32 followed by RGBA rows
+    const channel = new Channel<ArrayBuffer>();
+    channel.onmessage = (frame) => {
+      const context = canvas.current?.getContext("2d");
+      if (!context) {
+        return;
+      }
+      const header = new DataView(frame, 0, 8);
+      const width = header.getUint32(0, true);
+      const height = header.getUint32(4, true);
+      if (context.canvas.width !== width || context.canvas.height !== height) {
+        context.canvas.width = width;
+        context.canvas.height = height;
+      }
+      const pixels = new Uint8ClampedArray(frame, 8, width * height * 4);
+      context.putImageData(new ImageData(pixels, width, height), 0, 0);
+    };
+
+    // fails unless the app was started with --offscreen
+    invoke("subscribe_offscreen_frames", { channel })
+      .then(() => setEnabled(true))
+      .catch(() => setEnabled(false));
+  }, []);
+
+  return <canvas ref={canvas} className="offscreen-canvas" hidden={!enabled} />;
+}
+
+
+function ProjectionToggle() {
+
+  const [orthographic, setOrthographic] = useState(false);
+
+  async function toggle() {
+    await invoke("set_camera_projection", {
+      kind: orthographic ? "Perspective" : "Orthographic",
+    });
+    setOrthographic(!orthographic);
+  }
+
+  return (
+    <button onClick={toggle}>
+      {orthographic ? "Perspective" : "Orthographic"} view
+    </button>
+  );
+}
+
+
+// Turns the camera with raw mouse motion while the pointer is locked, Esc unlocks
+function MouseLook() {
+
+  useEffect(() => {
+    let dx = 0;
+    let dy = 0;
+    let frame: number | null = null;
+
+    // one command per animation frame, pointer events can come at 1000Hz
+    function onMove(event: PointerEvent) {
+      if (document.pointerLockElement === null) {
+        return;
+      }
+      dx += event.movementX;
+      dy += event.movementY;
+      frame ??= requestAnimationFrame(() => {
+        invoke("send_mouse_motion", { dx, dy });
+        dx = 0;
+        dy = 0;
+        frame = null;
+      });
+    }
+    function onLockChange() {
+      invoke("set_pointer_locked", { locked: document.pointerLockElement !== null });
+    }
+
+    document.addEventListener("pointermove", onMove);
+    document.addEventListener("pointerlockchange", onLockChange);
+    return () => {
+      document.removeEventListener("pointermove", onMove);
+      document.removeEventListener("pointerlockchange", onLockChange);
+    };
+  }, []);
+
+  async function lock() {
+    try {
+      // raw deltas without OS acceleration where supported
+      await document.body.requestPointerLock({ unadjustedMovement: true });
+    } catch {
+      await document.body.requestPointerLock();
+    }
+  }
+
+  return <button onClick={lock}>Mouse look</button>;
+}
+
+
+// Respawns the scene and puts the camera back where it started
+function RestartButton() {
+  return <button onClick={() => invoke("reset_scene")}>Restart scene</button>;
+}
+
+
+// Draws the main window with the raw wgpu triangle instead of Bevy
+function RendererToggle() {
+
+  const [wgpu, setWgpu] = useState(false);
+
+  async function toggle() {
+    await invoke("set_renderer", { mode: wgpu ? "bevy" : "wgpu" });
+    setWgpu(!wgpu);
+  }
+
+  return <button onClick={toggle}>Render with {wgpu ? "Bevy" : "wgpu"}</button>;
+}
+
+
+// Master volume and mute for Bevy's audio
+function VolumeControl() {
+
+  const [volume, setVolume] = useState(1);
+  const [muted, setMuted] = useState(false);
+  const [error, setError] = useState("");
+
+  async function changeVolume(value: number) {
+    setVolume(value);
+    try {
+      await invoke("set_master_volume", { volume: value });
+      setError("");
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  }
+
+  async function toggleMute() {
+    try {
+      await invoke("set_muted", { muted: !muted });
+      setMuted(!muted);
+      setError("");
+    } catch (e) {
+      setError(errorMessage(e));
+    }
+  }
+
+  return (
+    <div className="row">
+      <input type="range" min={0} max={1} step={0.05} value={volume}
+        onChange={(e) => changeVolume(Number(e.currentTarget.value))} />
+      <button onClick={toggleMute}>{muted ? "Unmute" : "Mute"}</button>
+      {error && <span>{error}</span>}
+    </div>
+  );
+}
+
+
+// Runs the demo's one-shot system registered with TauriPlugin::with_system
+function ToggleLights() {
+  return (
+    <button onClick={() => invoke("run_system", { name: "toggle_lights" })}>
+      Toggle lights
+    </button>
+  );
+}
+
+
+// Records two seconds of the animation demo to PNGs in a frames folder
+function RecordButton() {
+
+  const [progress, setProgress] = useState<string | null>(null);
+
+  useEffect(() => {
+    const unlistenProgress = listen<{ written: number; frames: number }>(
+      "recording-progress",
+      (event) => setProgress(`${event.payload.written}/${event.payload.frames}`),
+    );
+    const unlistenFinished = listen<{ written: number; error: string | null }>(
+      "recording-finished",
+      (event) => setProgress(event.payload.error ?? null),
+    );
+
+    return () => {
+      unlistenProgress.then((f) => f());
+      unlistenFinished.then((f) => f());
+    };
+  }, []);
+
+  async function start() {
+    try {
+      await invoke("start_recording", {
+        fps: 30, frames: 60, output: { kind: "png", directory: "frames" },
+      });
+      setProgress("0/60");
+    } catch (e) {
+      setProgress(errorMessage(e));
+    }
+  }
+
+  return (
+    <div className="row">
+      <button onClick={start}>Record</button>
+      <button onClick={() => invoke("stop_recording").catch(() => {})}>Stop</button>
+      {progress && <span>{progress}</span>}
+    </div>
+  );
+}
+
+
+// Draws the scene only inside a box laid out by CSS, following it on scroll
+// and resize
+function RenderRegionBox() {
+
+  const box = useRef<HTMLDivElement>(null);
+  const [clipped, setClipped] = useState(false);
+
+  useEffect(() => {
+    if (!clipped || !box.current) {
+      return;
+    }
+    const element = box.current;
+    const update = () => {
+      const rect = element.getBoundingClientRect();
+      invoke("set_render_region", {
+        x: rect.x, y: rect.y, width: rect.width, height: rect.height,
+      }).catch(() => {});
+    };
+    update();
+    const observer = new ResizeObserver(update);
+    observer.observe(element);
+    window.addEventListener("resize", update);
+    window.addEventListener("scroll", update, true);
+
+    return () => {
+      observer.disconnect();
+      window.removeEventListener("resize", update);
+      window.removeEventListener("scroll", update, true);
+      invoke("clear_render_region").catch(() => {});
+    };
+  }, [clipped]);
+
+  return (
+    <div>
+      <label>
+        <input type="checkbox" checked={clipped} onChange={(e) => setClipped(e.currentTarget.checked)} />
+        Clip the scene to the box
+      </label>
+      <div ref={box} style={{ width: 320, height: 180, border: "1px dashed gray" }} />
+    </div>
+  );
+}
+
+
+// Swaps the main camera's clear color for a gradient sky and back
+function SkyToggle() {
+
+  const [sky, setSky] = useState(false);
+
+  async function toggle() {
+    const mode = sky
+      ? { kind: "default" }
+      : { kind: "gradient", top: [0.25, 0.5, 0.9], bottom: [0.85, 0.9, 1.0] };
+    await invoke("set_camera_clear", { mode });
+    setSky(!sky);
+  }
+
+  return <button onClick={toggle}>{sky ? "Plain background" : "Gradient sky"}</button>;
+}
+
+
+// A label drawn by Bevy's UI instead of the webview
+function BevyLabel() {
+
+  const [text, setText] = useState("Hello from Bevy UI");
+  const [id, setId] = useState<number | null>(null);
+
+  async function toggle() {
+    if (id === null) {
+      setId(await invoke<number>("spawn_ui_text", {
+        text, x: 12, y: 48, size: 24, color: [1, 1, 1],
+      }));
+    } else {
+      await invoke("remove_ui", { id });
+      setId(null);
+    }
+  }
+
+  return (
+    <div>
+      <input value={text} onChange={(e) => setText(e.currentTarget.value)} />
+      <button onClick={toggle}>{id === null ? "Show" : "Remove"} label</button>
+    </div>
+  );
+}
+
+
+// Saves a camera view and flies back to it
+function CameraBookmark() {
+
+  const [saved, setSaved] = useState(false);
+
+  async function save() {
+    await invoke("save_camera_bookmark", { name: "view" });
+    setSaved(true);
+  }
+
+  return (
+    <div className="row">
+      <button onClick={save}>Save view</button>
+      <button
+        disabled={!saved}
+        onClick={() => invoke("animate_camera_to", { bookmark: "view", seconds: 1.5 })}
+      >
+        Fly to view
+      </button>
+    </div>
+  );
+}
+
+
+// Keeps the camera on the orbiting satellite
+function FollowToggle() {
+
+  const [following, setFollowing] = useState(false);
+
+  async function toggle() {
+    if (following) {
+      await invoke("stop_follow");
+    } else {
+      await invoke("follow_entity", { name: "satellite" });
+    }
+    setFollowing(!following);
+  }
+
+  return (
+    <button onClick={toggle}>
+      {following ? "Stop following" : "Follow satellite"}
+    </button>
+  );
+}
+
+
+// Makes the emissive satellite glow
+function BloomControls() {
+
+  const [enabled, setEnabled] = useState(false);
+  const [intensity, setIntensity] = useState(0.15);
+
+  async function update(nextEnabled: boolean, nextIntensity: number) {
+    setEnabled(nextEnabled);
+    setIntensity(nextIntensity);
+    await invoke("set_bloom", {
+      camera: "camera",
+      enabled: nextEnabled,
+      intensity: nextIntensity,
+    });
+  }
+
+  return (
+    <div>
+      <label>
+        <input
+          type="checkbox"
+          checked={enabled}
+          onChange={(e) => update(e.currentTarget.checked, intensity)}
+        />
+        Bloom
+      </label>
+      <input
+        type="range"
+        min={0}
+        max={1}
+        step={0.01}
+        value={intensity}
+        disabled={!enabled}
+        onChange={(e) => update(enabled, Number(e.currentTarget.value))}
+      />
+    </div>
+  );
+}
+
+// Fades distant objects into the fog color
+function FogControls() {
+  const [enabled, setEnabled] = useState(false);
+  const [end, setEnd] = useState(20);
+
+  async function update(nextEnabled: boolean, nextEnd: number) {
+    setEnabled(nextEnabled);
+    setEnd(nextEnd);
+    await invoke("set_fog", {
+      color: [0.1, 0.3, 0.45, 1.0],
+      start: 2,
+      end: nextEnd,
+      enabled: nextEnabled,
+    });
+  }
+
+  return (
+    <div>
+      <label>
+        <input
+          type="checkbox"
+          checked={enabled}
+          onChange={(e) => update(e.currentTarget.checked, end)}
+        />
+        Fog
+      </label>
+      <input
+        type="range"
+        min={3}
+        max={40}
+        step={1}
+        value={end}
+        disabled={!enabled}
+        onChange={(e) => update(enabled, Number(e.currentTarget.value))}
+      />
+    </div>
+  );
+}
+
+
+// Outlines the entity selected by clicking it
+function DebugGizmosToggle() {
+
+  const [enabled, setEnabled] = useState(false);
+
+  async function toggle() {
+    await invoke("set_debug_gizmos", { enabled: !enabled });
+    setEnabled(!enabled);
+  }
+
+  return (
+    <button onClick={toggle}>
+      {enabled ? "Hide" : "Show"} debug gizmos
+    </button>
+  );
+}
+
+type GpuMemoryStats = {
+  allocated_bytes: number | null;
+  reserved_bytes: number | null;
+  allocations: number | null;
+  memory_blocks: number | null;
+  buffers: number | null;
+  textures: number | null;
+  texture_views: number | null;
+  image_bytes: number;
+};
+
+function mebibytes(bytes: number) {
+  return `${(bytes / (1024 * 1024)).toFixed(1)}MiB`;
+}
+
+function GpuMemory() {
+
+  const [stats, setStats] = useState<GpuMemoryStats | null>(null);
+
+  async function refresh() {
+    setStats(await invoke<GpuMemoryStats>("get_gpu_memory"));
+  }
+
+  return (
+    <div>
+      <button onClick={refresh}>GPU memory</button>
+      {stats && (
+        <p>
+          {stats.allocated_bytes !== null
+            ? `${mebibytes(stats.allocated_bytes)} allocated, `
+            : ""}
+          ~{mebibytes(stats.image_bytes)} of images
+          {stats.textures !== null && `, ${stats.textures} textures, ${stats.buffers} buffers`}
+        </p>
+      )}
+    </div>
+  );
+}
+
+type LatencyReport = {
+  latency_ms: number;
+  queued_ms: number;
+  frames: number;
+  present_mode: string;
+};
+
+function LatencyProbe() {
+
+  const [report, setReport] = useState<LatencyReport | null>(null);
+
+  async function measure() {
+    setReport(await invoke<LatencyReport>("measure_latency"));
+  }
+
+  return (
+    <div>
+      <button onClick={measure}>Measure latency</button>
+      {report && (
+        <p>
+          {report.latency_ms.toFixed(1)}ms over {report.frames} frames ({report.present_mode})
+        </p>
+      )}
+    </div>
+  );
+}
+
+// Timeline of the demo animation, whose clip is 4 seconds long
+function AnimationTimeline() {
+
+  const [time, setTime] = useState(0);
+  const [repeat, setRepeat] = useState(true);
+  const [frozen, setFrozen] = useState(false);
+  const [names, setNames] = useState<string[]>([]);
+
+  useEffect(() => {
+    invoke<string[]>("list_animations").then(setNames);
+    const interval = setInterval(async () => {
+      setTime(await invoke<number>("get_animation_time"));
+    }, 100);
+    return () => clearInterval(interval);
+  }, []);
+
+  async function toggleRepeat() {
+    await invoke("set_animation_repeat", { repeat: !repeat });
+    setRepeat(!repeat);
+  }
+
+  // holds the satellite in place for posing it with set_transform
+  async function toggleFrozen() {
+    await invoke(frozen ? "unfreeze_entity" : "freeze_entity", { target: "satellite" });
+    setFrozen(!frozen);
+  }
+
+  return (
+    <div>
+      {names.length > 0 && (
+        <select>
+          {names.map((name) => <option key={name}>{name}</option>)}
+        </select>
+      )}
+      <input
+        type="range"
+        min={0}
+        max={4}
+        step={0.01}
+        value={time}
+        onChange={(e) => invoke("seek_animation", { seconds: Number(e.currentTarget.value) })}
+      />
+      <label>
+        <input type="checkbox" checked={repeat} onChange={toggleRepeat} /> Repeat
+      </label>
+      <label>
+        <input type="checkbox" checked={frozen} onChange={toggleFrozen} /> Freeze satellite
+      </label>
+    </div>
+  );
+}
+
+function TransparencyToggle() {
+
+  const [enabled, setEnabled] = useState(false);
+  const [error, setError] = useState("");
+
+  async function toggle() {
+    try {
+      await invoke("set_webview_transparent", { enabled: !enabled });
+      setEnabled(!enabled);
+      setError("");
+    } catch (err) {
+      setError(errorMessage(err));
+    }
+  }
+
+  return (
+    <div>
+      <button onClick={toggle}>
+        {enabled ? "Opaque" : "Transparent"} surface
+      </button>
+      {error && <p>{error}</p>}
+    </div>
+  );
+}
+
+
+function TitleBar() {
+  const [decorated, setDecorated] = useState(true);
+
+  // only drawn when started with --custom-titlebar, which removes the native one
+  useEffect(() => {
+    getCurrentWebviewWindow().isDecorated().then(setDecorated);
+  }, []);
+
+  function onMouseDown(e: MouseEvent) {
+    if (e.button !== 0) {
+      return;
+    }
+    // keep the press from reaching anything below the title bar
+    e.preventDefault();
+    if (e.detail === 2) {
+      invoke("toggle_maximize_window");
+    } else {
+      invoke("start_window_drag");
+    }
+  }
+
+  if (decorated) {
+    return null;
+  }
+
+  return (
+    <div className="titlebar" onMouseDown={onMouseDown}>
+      <span className="titlebar-title">Tauri + React + Bevy Example</span>
+      <div className="titlebar-buttons" onMouseDown={(e) => e.stopPropagation()}>
+        <button onClick={() => invoke("minimize_window")}>&#x2013;</button>
+        <button onClick={() => invoke("toggle_maximize_window")}>&#x25a1;</button>
+        <button onClick={() => invoke("close_window")}>&#x2715;</button>
+      </div>
+    </div>
+  );
+}
+
+
+type ExitingPayload = {
+  reason: "window-closed" | "exit-requested" | "app-exit" | "error";
+  code: number;
+};
+
 function App() {
   const [greetMsg, setGreetMsg] = useState("");
   const [name, setName] = useState("");
 
+  // the last chance to save anything before the webview is torn down
+  useEffect(() => {
+    const unlisten = listen<ExitingPayload>("app-exiting", (event) => {
+      localStorage.setItem("last-exit", JSON.stringify({ ...event.payload, name }));
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, [name]);
+
   async function greet() {
     // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
     setGreetMsg(await invoke("greet", { name }));
@@ -41,8 +994,37 @@ function App() {
 
   return (
     <main className="container">
+      <OffscreenCanvas />
+      <TitleBar />
+      <RendererError />
+      <SelectionLabel />
+
       <div style={{ position: 'absolute', top: '10px', right: '10px' }}>
         <FpsDisplay />
+        <HoverDisplay />
+        <EntityColor />
+        <TransformInspector />
+        <GamepadStatus />
+        <StallWarning />
+        <ProjectionToggle />
+        <RestartButton />
+        <MouseLook />
+        <RendererToggle />
+        <SkyToggle />
+        <RenderRegionBox />
+        <RecordButton />
+        <ToggleLights />
+        <VolumeControl />
+        <FollowToggle />
+        <CameraBookmark />
+        <DebugGizmosToggle />
+        <BloomControls />
+        <FogControls />
+        <BevyLabel />
+        <TransparencyToggle />
+        <AnimationTimeline />
+        <LatencyProbe />
+        <GpuMemory />
       </div>
 
       <h1>Welcome to Tauri + React + Bevy</h1>